@@ -783,6 +783,32 @@ impl Constraint {
             (None, None) => Ok((sw, sh)),
         }
     }
+
+    /// Size of the window a crop mode cuts from an `sw × sh` source, before
+    /// gravity places it.
+    ///
+    /// `None` when this constraint does not crop that source: non-crop
+    /// modes, single-axis targets, or a window covering the whole source.
+    #[cfg(feature = "smart-crop")]
+    pub(crate) fn crop_window(&self, sw: u32, sh: u32) -> Option<Size> {
+        use ConstraintMode::*;
+        let (tw, th) = (self.width?, self.height?);
+        if sw == 0 || sh == 0 || tw == 0 || th == 0 {
+            return None;
+        }
+        let aspect = || {
+            let r = crop_to_aspect(sw, sh, tw, th, &Gravity::Center);
+            Size::new(r.width, r.height)
+        };
+        let window = match self.mode {
+            FitCrop | AspectCrop => aspect(),
+            WithinCrop if sw <= tw && sh <= th => return None,
+            WithinCrop if sw >= tw && sh >= th => aspect(),
+            WithinCrop => Size::new(sw.min(tw), sh.min(th)),
+            _ => return None,
+        };
+        (window != Size::new(sw, sh)).then_some(window)
+    }
 }
 
 /// Computed layout from applying a [`Constraint`] to source dimensions.
@@ -875,6 +901,12 @@ pub enum LayoutError {
     ZeroRegionDimension,
     /// A float parameter contains NaN or infinity.
     NonFiniteFloat,
+    /// The referenced command is not an unresolved analysis barrier.
+    NotABarrier,
+    /// An effect's parameters describe no valid geometry for its input,
+    /// e.g. a warp that crosses the horizon line. See
+    /// [`EffectKind::Invalid`](crate::EffectKind::Invalid).
+    InvalidEffect,
 }
 
 impl core::fmt::Display for LayoutError {
@@ -886,6 +918,10 @@ impl core::fmt::Display for LayoutError {
                 f.write_str("region viewport has zero or negative width or height")
             }
             Self::NonFiniteFloat => f.write_str("a float parameter contains NaN or infinity"),
+            Self::NotABarrier => {
+                f.write_str("referenced command is not an unresolved analysis barrier")
+            }
+            Self::InvalidEffect => f.write_str("effect parameters are invalid for its input"),
         }
     }
 }
@@ -991,7 +1027,7 @@ fn combine_crops(user_crop: Option<Rect>, constraint_crop: Rect) -> Rect {
 }
 
 /// Compute placement offset for a resized image within a canvas.
-fn gravity_offset(cw: u32, ch: u32, iw: u32, ih: u32, gravity: &Gravity) -> (i32, i32) {
+pub(crate) fn gravity_offset(cw: u32, ch: u32, iw: u32, ih: u32, gravity: &Gravity) -> (i32, i32) {
     let x = gravity_offset_1d(cw.saturating_sub(iw), gravity, true);
     let y = gravity_offset_1d(ch.saturating_sub(ih), gravity, false);
     (x as i32, y as i32)
@@ -5,18 +5,21 @@
 //! for spatial transforms before and after resize.
 //!
 //! zenlayout provides built-in implementations for common effects
-//! ([`RotateEffect`], [`PadEffect`], [`ExpandEffect`], [`TrimEffect`],
-//! [`WarpEffect`]). Downstream crates can implement the trait for new
-//! operations (lens distortion, content-aware resize, etc.) without
-//! modifying zenlayout.
+//! ([`RotateEffect`], [`RotatedCropEffect`], [`PadEffect`], [`ExpandEffect`], [`TrimEffect`],
+//! [`SeamCarveEffect`], [`WarpEffect`], [`LensEffect`], [`EquirectViewEffect`],
+//! [`ResolvedBarrier`], [`FusedEffect`]).
+//! Downstream crates can implement the trait for new operations
+//! (auto-deskew, etc.) without modifying zenlayout.
 
 use alloc::boxed::Box;
+use alloc::vec::Vec;
 use core::fmt::Debug;
 
-use crate::constraint::CanvasColor;
+use crate::constraint::{CanvasColor, Gravity, Rect, Size};
 #[allow(unused_imports)]
 use crate::float_math::Float;
-use crate::plan::RegionCoord;
+use crate::orientation::Orientation;
+use crate::plan::{Region, RegionCoord};
 
 // ── Trait ──
 
@@ -31,7 +34,9 @@ pub trait DimensionEffect: Debug + Send + Sync {
     /// Returns `None` for content-adaptive effects whose output depends on
     /// pixel analysis (e.g. auto-trim, auto-deskew). The planner treats
     /// `None` as an **analysis barrier** — it cannot plan through the effect
-    /// without runtime data from an `Analyze` node.
+    /// without runtime data from an `Analyze` node — unless
+    /// [`kind()`](Self::kind) reports [`EffectKind::Invalid`], which makes
+    /// planning fail.
     fn forward(&self, w: u32, h: u32) -> Option<(u32, u32)>;
 
     /// Required input dimensions for desired output.
@@ -75,6 +80,73 @@ pub trait DimensionEffect: Debug + Send + Sync {
 
     /// Clone into a boxed trait object.
     fn clone_boxed(&self) -> Box<dyn DimensionEffect>;
+
+    /// The same effect on a plane scaled by `(sx, sy)` relative to this one.
+    ///
+    /// Used by [`IdealLayout::derive_secondary()`](crate::IdealLayout::derive_secondary)
+    /// to keep gain maps and depth maps locked to the primary plane.
+    /// Default implementation clones — correct for effects defined in
+    /// resolution-independent terms (angles, percentages). Override when
+    /// the effect holds pixel amounts.
+    fn scaled(&self, sx: f64, sy: f64) -> Box<dyn DimensionEffect> {
+        let _ = (sx, sy);
+        self.clone_boxed()
+    }
+
+    /// Output → input pixel mapping as a 3×3 projective matrix (row-major),
+    /// for an input of `in_w × in_h`.
+    ///
+    /// `Some` marks the effect as a single resample that the planner may
+    /// fuse with adjacent matrix-expressible effects into one
+    /// [`FusedEffect`], or fold into [`Orientation`] when it is a cardinal
+    /// rotation or flip. Default `None` (not fusable).
+    fn warp_matrix(&self, in_w: u32, in_h: u32) -> Option<[f64; 9]> {
+        let _ = (in_w, in_h);
+        None
+    }
+
+    /// Fill color for output areas not covered by the input, if any.
+    fn fill_color(&self) -> Option<CanvasColor> {
+        None
+    }
+
+    /// What the planner can know about this effect for a `w × h` input.
+    ///
+    /// Default: [`AnalysisBarrier`](EffectKind::AnalysisBarrier) when
+    /// [`forward()`](Self::forward) returns `None`, otherwise
+    /// [`Geometric`](EffectKind::Geometric). Effects whose output size is
+    /// fixed but whose pixel mapping depends on content override this with
+    /// [`ContentMapped`](EffectKind::ContentMapped); effects whose `None`
+    /// means bad parameters rather than missing analysis override it with
+    /// [`Invalid`](EffectKind::Invalid).
+    fn kind(&self, w: u32, h: u32) -> EffectKind {
+        if self.forward(w, h).is_some() {
+            EffectKind::Geometric
+        } else {
+            EffectKind::AnalysisBarrier
+        }
+    }
+}
+
+/// How much of an effect is known before pixels are seen.
+#[non_exhaustive]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum EffectKind {
+    /// Output dims and point mapping follow from the input dims alone
+    /// (rotation, padding, warps).
+    Geometric,
+    /// Output dims are known, but where each pixel lands depends on
+    /// content (seam carving). The planner plans through it;
+    /// [`forward_point()`](DimensionEffect::forward_point) and
+    /// [`inverse_point()`](DimensionEffect::inverse_point) return `None`.
+    ContentMapped,
+    /// Output dims depend on content (auto-trim). The planner stops here
+    /// until [`resume_after_barrier()`](crate::IdealLayout::resume_after_barrier).
+    AnalysisBarrier,
+    /// The parameters describe no valid geometry for this input (a warp
+    /// crossing the horizon, a field of view of 180° or more). The planner
+    /// rejects it with [`LayoutError::InvalidEffect`](crate::LayoutError::InvalidEffect).
+    Invalid,
 }
 
 impl Clone for Box<dyn DimensionEffect> {
@@ -110,6 +182,16 @@ pub enum RotateMode {
     /// Crop to original dimensions (subtle correction).
     /// Output same size as input. Corners lost.
     CropToOriginal,
+    /// Largest inscribed axis-aligned rectangle with aspect ratio
+    /// `aspect_w : aspect_h` (`None` keeps the input's). Output shrinks.
+    ///
+    /// The rotated frame usually leaves the rectangle room to slide in one
+    /// direction; `gravity` picks where, as a point on the rotated frame's
+    /// bounding box. `None` with [`Gravity::Center`] matches `InscribedCrop`.
+    InscribedAspect {
+        aspect: Option<(u32, u32)>,
+        gravity: Gravity,
+    },
 }
 
 impl RotateEffect {
@@ -120,6 +202,17 @@ impl RotateEffect {
             mode,
         }
     }
+
+    /// Offset of the output centre from the rotated frame's centre.
+    /// Non-zero only for [`RotateMode::InscribedAspect`] with gravity.
+    fn centre_offset(&self, in_w: u32, in_h: u32, out_w: u32, out_h: u32) -> (f64, f64) {
+        match self.mode {
+            RotateMode::InscribedAspect { gravity, .. } => {
+                inscribed_aspect_offset(in_w, in_h, self.angle_rad, out_w, out_h, gravity)
+            }
+            _ => (0.0, 0.0),
+        }
+    }
 }
 
 impl DimensionEffect for RotateEffect {
@@ -128,6 +221,10 @@ impl DimensionEffect for RotateEffect {
             RotateMode::InscribedCrop => inscribed_crop_dims(w, h, self.angle_rad),
             RotateMode::Expand { .. } => expanded_canvas_dims(w, h, self.angle_rad),
             RotateMode::CropToOriginal => (w, h),
+            RotateMode::InscribedAspect { aspect, .. } => {
+                let (aw, ah) = aspect.unwrap_or((w, h));
+                inscribed_aspect_dims(w, h, self.angle_rad, aw, ah)
+            }
         })
     }
 
@@ -136,6 +233,11 @@ impl DimensionEffect for RotateEffect {
             RotateMode::InscribedCrop => inscribed_crop_inverse(w, h, self.angle_rad),
             RotateMode::Expand { .. } => expanded_canvas_inverse(w, h, self.angle_rad),
             RotateMode::CropToOriginal => (w, h),
+            RotateMode::InscribedAspect { aspect: None, .. } => {
+                inscribed_crop_inverse(w, h, self.angle_rad)
+            }
+            // The output has the caller's ratio; the input's is unknown.
+            RotateMode::InscribedAspect { .. } => return None,
         })
     }
 
@@ -146,9 +248,10 @@ impl DimensionEffect for RotateEffect {
         let ow = out_w as f32;
         let oh = out_h as f32;
 
-        // Center of input and output
+        // Center of input and output (the output may sit off-centre).
+        let (dx_out, dy_out) = self.centre_offset(in_w, in_h, out_w, out_h);
         let (cx_in, cy_in) = (fw / 2.0, fh / 2.0);
-        let (cx_out, cy_out) = (ow / 2.0, oh / 2.0);
+        let (cx_out, cy_out) = (ow / 2.0 - dx_out as f32, oh / 2.0 - dy_out as f32);
 
         // Rotate point around input center
         let (sin, cos) = (self.angle_rad.sin(), self.angle_rad.cos());
@@ -168,8 +271,9 @@ impl DimensionEffect for RotateEffect {
         let ow = out_w as f32;
         let oh = out_h as f32;
 
+        let (dx_out, dy_out) = self.centre_offset(in_w, in_h, out_w, out_h);
         let (cx_in, cy_in) = (fw / 2.0, fh / 2.0);
-        let (cx_out, cy_out) = (ow / 2.0, oh / 2.0);
+        let (cx_out, cy_out) = (ow / 2.0 - dx_out as f32, oh / 2.0 - dy_out as f32);
 
         // Inverse rotation (negate angle)
         let (sin, cos) = ((-self.angle_rad).sin(), (-self.angle_rad).cos());
@@ -184,6 +288,195 @@ impl DimensionEffect for RotateEffect {
     fn clone_boxed(&self) -> Box<dyn DimensionEffect> {
         Box::new(*self)
     }
+
+    fn warp_matrix(&self, in_w: u32, in_h: u32) -> Option<[f64; 9]> {
+        let (out_w, out_h) = self.forward(in_w, in_h)?;
+        let (dx_out, dy_out) = self.centre_offset(in_w, in_h, out_w, out_h);
+        let (cx_in, cy_in) = (in_w as f64 / 2.0, in_h as f64 / 2.0);
+        let (cx_out, cy_out) = (out_w as f64 / 2.0 - dx_out, out_h as f64 / 2.0 - dy_out);
+        // Same mapping as inverse_point(): rotate by −θ about the centres.
+        let (sin, cos) = (self.angle_rad as f64).sin_cos();
+        Some([
+            cos,
+            sin,
+            cx_in - cos * cx_out - sin * cy_out,
+            -sin,
+            cos,
+            cy_in + sin * cx_out - cos * cy_out,
+            0.0,
+            0.0,
+            1.0,
+        ])
+    }
+
+    fn fill_color(&self) -> Option<CanvasColor> {
+        match self.mode {
+            RotateMode::Expand { color } => Some(color),
+            _ => None,
+        }
+    }
+}
+
+/// Crop of an oriented rectangle, straightened into the output.
+///
+/// The rectangle is `width × height` input pixels centred on `center`,
+/// rotated clockwise by `angle_rad`. The output is that rectangle made
+/// axis-aligned: output x runs along the rectangle's rotated x axis. Areas
+/// of the rectangle outside the input are filled with `color`.
+///
+/// At multiples of 90° the crop needs no resampling — see
+/// [`cardinal()`](Self::cardinal) and
+/// [`Pipeline::rotated_crop()`](crate::Pipeline::rotated_crop), which
+/// plans it as an orientation plus a [`Region`].
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct RotatedCropEffect {
+    /// Rectangle centre in input pixels.
+    pub center: (f32, f32),
+    /// Rectangle width in input pixels (along its own x axis).
+    pub width: f32,
+    /// Rectangle height in input pixels.
+    pub height: f32,
+    /// Clockwise rotation of the rectangle, radians.
+    pub angle_rad: f32,
+    /// Fill for parts of the rectangle outside the input.
+    pub color: CanvasColor,
+}
+
+impl RotatedCropEffect {
+    /// Create from a centre, size and angle in degrees (transparent fill).
+    pub fn from_degrees(center: (f32, f32), width: f32, height: f32, angle_degrees: f32) -> Self {
+        Self {
+            center,
+            width,
+            height,
+            angle_rad: angle_degrees.to_radians(),
+            color: CanvasColor::Transparent,
+        }
+    }
+
+    /// Set the fill color.
+    pub fn with_color(mut self, color: CanvasColor) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// Rectangle axes in input space: its x and y directions.
+    fn axes(&self) -> ((f64, f64), (f64, f64)) {
+        let (sin, cos) = (self.angle_rad as f64).sin_cos();
+        ((cos, sin), (-sin, cos))
+    }
+
+    /// The same crop as an orientation followed by a region, when the
+    /// angle is a multiple of 90° (within 10⁻⁴ rad).
+    ///
+    /// The region is in the oriented space of a `w × h` input, with edges
+    /// rounded to whole pixels.
+    pub fn cardinal(&self, w: u32, h: u32) -> Option<(Orientation, Region)> {
+        let quarters = self.angle_rad as f64 / FRAC_PI_2_F64;
+        let k = quarters.round();
+        if (quarters - k).abs() * FRAC_PI_2_F64 > 1e-4 {
+            return None;
+        }
+        // Straightening a rectangle turned clockwise turns the image back.
+        let orientation = match (k as i64).rem_euclid(4) {
+            0 => Orientation::Identity,
+            1 => Orientation::Rotate270,
+            2 => Orientation::Rotate180,
+            _ => Orientation::Rotate90,
+        };
+        let (ox, oy) = orientation.transform_point_to_display(self.center.0, self.center.1, w, h);
+        let (ow, oh) = (self.width as f64, self.height as f64);
+        let left = (ox as f64 - ow / 2.0).round() as i32;
+        let top = (oy as f64 - oh / 2.0).round() as i32;
+        let mut region = Region::crop(
+            left,
+            top,
+            left + (ow.round() as i32).max(1),
+            top + (oh.round() as i32).max(1),
+        );
+        region.color = self.color;
+        Some((orientation, region))
+    }
+}
+
+impl DimensionEffect for RotatedCropEffect {
+    fn forward(&self, _w: u32, _h: u32) -> Option<(u32, u32)> {
+        if !(self.width.is_finite() && self.height.is_finite() && self.angle_rad.is_finite()) {
+            return None;
+        }
+        Some((
+            self.width.round().max(1.0) as u32,
+            self.height.round().max(1.0) as u32,
+        ))
+    }
+
+    fn inverse(&self, _w: u32, _h: u32) -> Option<(u32, u32)> {
+        None // Output size is fixed by the rectangle, not the input.
+    }
+
+    fn forward_point(&self, x: f32, y: f32, in_w: u32, in_h: u32) -> Option<(f32, f32)> {
+        let (out_w, out_h) = self.forward(in_w, in_h)?;
+        let (u, v) = self.axes();
+        let (dx, dy) = (
+            x as f64 - self.center.0 as f64,
+            y as f64 - self.center.1 as f64,
+        );
+        let lx = dx * u.0 + dy * u.1 + self.width as f64 / 2.0;
+        let ly = dx * v.0 + dy * v.1 + self.height as f64 / 2.0;
+        Some((
+            (lx * out_w as f64 / self.width as f64) as f32,
+            (ly * out_h as f64 / self.height as f64) as f32,
+        ))
+    }
+
+    fn inverse_point(&self, x: f32, y: f32, in_w: u32, in_h: u32) -> Option<(f32, f32)> {
+        let m = self.warp_matrix(in_w, in_h)?;
+        let (sx, sy) = apply_projective(&m, x as f64, y as f64);
+        Some((sx as f32, sy as f32))
+    }
+
+    fn clone_boxed(&self) -> Box<dyn DimensionEffect> {
+        Box::new(*self)
+    }
+
+    fn scaled(&self, sx: f64, sy: f64) -> Box<dyn DimensionEffect> {
+        // Exact for uniform scale; under non-uniform scale the rectangle
+        // would shear, so its size follows its own axes' stretch.
+        let ((ux, uy), (vx, vy)) = self.axes();
+        let stretch = |ax: f64, ay: f64| ((ax * sx).powi(2) + (ay * sy).powi(2)).sqrt();
+        Box::new(Self {
+            center: (
+                (self.center.0 as f64 * sx) as f32,
+                (self.center.1 as f64 * sy) as f32,
+            ),
+            width: (self.width as f64 * stretch(ux, uy)) as f32,
+            height: (self.height as f64 * stretch(vx, vy)) as f32,
+            ..*self
+        })
+    }
+
+    fn warp_matrix(&self, in_w: u32, in_h: u32) -> Option<[f64; 9]> {
+        let (out_w, out_h) = self.forward(in_w, in_h)?;
+        let ((ux, uy), (vx, vy)) = self.axes();
+        let (bw, bh) = (self.width as f64, self.height as f64);
+        let (kx, ky) = (bw / out_w as f64, bh / out_h as f64);
+        // input = centre + u·(x·kx − bw/2) + v·(y·ky − bh/2)
+        Some([
+            ux * kx,
+            vx * ky,
+            self.center.0 as f64 - ux * bw / 2.0 - vx * bh / 2.0,
+            uy * kx,
+            vy * ky,
+            self.center.1 as f64 - uy * bw / 2.0 - vy * bh / 2.0,
+            0.0,
+            0.0,
+            1.0,
+        ])
+    }
+
+    fn fill_color(&self) -> Option<CanvasColor> {
+        Some(self.color)
+    }
 }
 
 /// Padding/border using percentage or pixel amounts.
@@ -264,6 +557,20 @@ impl DimensionEffect for PadEffect {
     fn clone_boxed(&self) -> Box<dyn DimensionEffect> {
         Box::new(*self)
     }
+
+    fn scaled(&self, sx: f64, sy: f64) -> Box<dyn DimensionEffect> {
+        let scale = |c: RegionCoord, s: f64| RegionCoord {
+            pixels: (c.pixels as f64 * s).round() as i32,
+            ..c
+        };
+        Box::new(Self {
+            top: scale(self.top, sy),
+            right: scale(self.right, sx),
+            bottom: scale(self.bottom, sy),
+            left: scale(self.left, sx),
+            color: self.color,
+        })
+    }
 }
 
 /// Canvas expansion by absolute pixel amounts.
@@ -298,6 +605,16 @@ impl DimensionEffect for ExpandEffect {
     fn clone_boxed(&self) -> Box<dyn DimensionEffect> {
         Box::new(*self)
     }
+
+    fn scaled(&self, sx: f64, sy: f64) -> Box<dyn DimensionEffect> {
+        let scale = |v: u32, s: f64| (v as f64 * s).round().max(0.0) as u32;
+        Box::new(Self {
+            left: scale(self.left, sx),
+            top: scale(self.top, sy),
+            right: scale(self.right, sx),
+            bottom: scale(self.bottom, sy),
+        })
+    }
 }
 
 /// Content-aware trim (**analysis barrier**).
@@ -346,6 +663,167 @@ impl DimensionEffect for TrimEffect {
     }
 }
 
+/// Runtime result of an `Analyze` step for an analysis barrier.
+///
+/// Fed to [`IdealLayout::resume_after_barrier()`](crate::IdealLayout::resume_after_barrier).
+#[non_exhaustive]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum BarrierResolution {
+    /// Only the output dimensions are known. Points map by linear scaling.
+    Dims(Size),
+    /// The output is this rectangle of the barrier's input (e.g. the trim
+    /// rect found by auto-trim). Points map by offset.
+    Rect(Rect),
+}
+
+/// An analysis barrier whose result is known.
+///
+/// Replaces the barrier's command when planning resumes, so the planner
+/// can walk through it like any other effect.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ResolvedBarrier {
+    /// What the analysis found.
+    pub resolution: BarrierResolution,
+}
+
+impl DimensionEffect for ResolvedBarrier {
+    fn forward(&self, _w: u32, _h: u32) -> Option<(u32, u32)> {
+        Some(match self.resolution {
+            BarrierResolution::Dims(s) => (s.width, s.height),
+            BarrierResolution::Rect(r) => (r.width, r.height),
+        })
+    }
+
+    fn inverse(&self, _w: u32, _h: u32) -> Option<(u32, u32)> {
+        None // Input size is whatever the analysis saw; not recoverable.
+    }
+
+    fn forward_point(&self, x: f32, y: f32, in_w: u32, in_h: u32) -> Option<(f32, f32)> {
+        match self.resolution {
+            BarrierResolution::Dims(s) => Some((
+                x * s.width as f32 / in_w.max(1) as f32,
+                y * s.height as f32 / in_h.max(1) as f32,
+            )),
+            BarrierResolution::Rect(r) => Some((x - r.x as f32, y - r.y as f32)),
+        }
+    }
+
+    fn inverse_point(&self, x: f32, y: f32, in_w: u32, in_h: u32) -> Option<(f32, f32)> {
+        match self.resolution {
+            BarrierResolution::Dims(s) => Some((
+                x * in_w as f32 / s.width.max(1) as f32,
+                y * in_h as f32 / s.height.max(1) as f32,
+            )),
+            BarrierResolution::Rect(r) => Some((x + r.x as f32, y + r.y as f32)),
+        }
+    }
+
+    fn clone_boxed(&self) -> Box<dyn DimensionEffect> {
+        Box::new(*self)
+    }
+
+    fn scaled(&self, sx: f64, sy: f64) -> Box<dyn DimensionEffect> {
+        let len = |v: u32, s: f64| (v as f64 * s).round().max(1.0) as u32;
+        let resolution = match self.resolution {
+            BarrierResolution::Dims(d) => {
+                BarrierResolution::Dims(Size::new(len(d.width, sx), len(d.height, sy)))
+            }
+            BarrierResolution::Rect(r) => BarrierResolution::Rect(Rect::new(
+                (r.x as f64 * sx).round() as u32,
+                (r.y as f64 * sy).round() as u32,
+                len(r.width, sx),
+                len(r.height, sy),
+            )),
+        };
+        Box::new(Self { resolution })
+    }
+}
+
+/// Content-aware resize (seam carving) to a target size.
+///
+/// The output size is known up front — it is the target — so the planner
+/// plans through this effect like any other. Which pixels survive depends
+/// on content, so point mapping is unknown:
+/// [`forward_point()`](DimensionEffect::forward_point) and
+/// [`inverse_point()`](DimensionEffect::inverse_point) return `None`, and
+/// [`kind()`](DimensionEffect::kind) is [`EffectKind::ContentMapped`].
+///
+/// An axis without a target keeps its input size.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SeamCarveEffect {
+    /// Target width, or `None` to keep the input width.
+    pub width: Option<u32>,
+    /// Target height, or `None` to keep the input height.
+    pub height: Option<u32>,
+}
+
+impl SeamCarveEffect {
+    /// Retarget both axes.
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width: Some(width),
+            height: Some(height),
+        }
+    }
+
+    /// Retarget the width only (vertical seams).
+    pub fn width_only(width: u32) -> Self {
+        Self {
+            width: Some(width),
+            height: None,
+        }
+    }
+
+    /// Retarget the height only (horizontal seams).
+    pub fn height_only(height: u32) -> Self {
+        Self {
+            width: None,
+            height: Some(height),
+        }
+    }
+}
+
+impl DimensionEffect for SeamCarveEffect {
+    fn forward(&self, w: u32, h: u32) -> Option<(u32, u32)> {
+        Some((
+            self.width.unwrap_or(w).max(1),
+            self.height.unwrap_or(h).max(1),
+        ))
+    }
+
+    fn inverse(&self, w: u32, h: u32) -> Option<(u32, u32)> {
+        // Untargeted axes pass through; a targeted axis accepts any input.
+        match (self.width, self.height) {
+            (None, None) => Some((w, h)),
+            _ => None,
+        }
+    }
+
+    fn forward_point(&self, _x: f32, _y: f32, _in_w: u32, _in_h: u32) -> Option<(f32, f32)> {
+        None // Depends on which seams the content selects.
+    }
+
+    fn inverse_point(&self, _x: f32, _y: f32, _in_w: u32, _in_h: u32) -> Option<(f32, f32)> {
+        None
+    }
+
+    fn clone_boxed(&self) -> Box<dyn DimensionEffect> {
+        Box::new(*self)
+    }
+
+    fn scaled(&self, sx: f64, sy: f64) -> Box<dyn DimensionEffect> {
+        let len = |v: u32, s: f64| (v as f64 * s).round().max(1.0) as u32;
+        Box::new(Self {
+            width: self.width.map(|v| len(v, sx)),
+            height: self.height.map(|v| len(v, sy)),
+        })
+    }
+
+    fn kind(&self, _w: u32, _h: u32) -> EffectKind {
+        EffectKind::ContentMapped
+    }
+}
+
 // ── Warp / projective effects ──
 
 /// How to choose output dimensions for a non-uniform spatial transform.
@@ -380,15 +858,36 @@ pub enum ResolutionPolicy {
     Custom(u32, u32),
 }
 
+/// Which part of the warped image a [`WarpEffect`] keeps.
+///
+/// The warp matrix defines a reference output frame the size of the input.
+/// Coverage picks a window in that frame; the [`ResolutionPolicy`] sets its
+/// pixel density. Mirrors [`RotateMode`] for arbitrary warps.
+#[non_exhaustive]
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum WarpCoverage {
+    /// Largest axis-aligned rectangle inside the warped source, with the
+    /// input's aspect ratio. Output shrinks. No fill color needed.
+    InscribedCrop,
+    /// Bounding box of the warped source. Output grows. Areas outside the
+    /// source filled with color.
+    Expand { color: CanvasColor },
+    /// The reference frame itself — exactly what the matrix describes.
+    /// Output same size as input (before the resolution policy).
+    #[default]
+    CropToOriginal,
+}
+
 /// Spatial warp via 3×3 projective matrix with resolution policy.
 ///
 /// The matrix maps **output** coordinates to **source** coordinates (inverse
 /// mapping), matching the convention used by zenfilters' `Warp::projective()`.
 ///
-/// Unlike [`RotateEffect`] (which handles content coverage — inscribed crop
-/// vs expand), `WarpEffect` handles **resolution**: when a transform has
-/// non-uniform local scale (perspective, lens distortion), the policy
-/// determines how to size the output.
+/// Like [`RotateEffect`], the [`WarpCoverage`] chooses which part of the
+/// warped image is kept. On top of that, `WarpEffect` handles
+/// **resolution**: when a transform has non-uniform local scale
+/// (perspective, lens distortion), the policy determines how to size the
+/// output.
 ///
 /// # Matrix convention
 ///
@@ -401,66 +900,429 @@ pub enum ResolutionPolicy {
 /// ```
 ///
 /// For pure affine transforms, `m[6] = m[7] = 0, m[8] = 1`.
+#[non_exhaustive]
 #[derive(Clone, Debug, PartialEq)]
 pub struct WarpEffect {
     /// 3×3 transform matrix (row-major), output → source.
     pub matrix: [f64; 9],
     /// Resolution policy for non-uniform scale.
     pub policy: ResolutionPolicy,
+    /// Which part of the warped image is kept.
+    pub coverage: WarpCoverage,
 }
 
 impl WarpEffect {
     /// Create from a 3×3 projective matrix and resolution policy.
     pub fn new(matrix: [f32; 9], policy: ResolutionPolicy) -> Self {
+        Self::new_f64(matrix.map(|v| v as f64), policy)
+    }
+
+    /// Create from an f64 matrix.
+    pub fn new_f64(matrix: [f64; 9], policy: ResolutionPolicy) -> Self {
         Self {
-            matrix: matrix.map(|v| v as f64),
+            matrix,
             policy,
+            coverage: WarpCoverage::CropToOriginal,
         }
     }
 
-    /// Create from an f64 matrix.
-    pub fn new_f64(matrix: [f64; 9], policy: ResolutionPolicy) -> Self {
-        Self { matrix, policy }
+    /// Set which part of the warped image is kept.
+    pub fn with_coverage(mut self, coverage: WarpCoverage) -> Self {
+        self.coverage = coverage;
+        self
+    }
+
+    /// The kept window `(x, y, width, height)` in the reference output
+    /// frame for a `w × h` input.
+    ///
+    /// Returns `None` if the coverage can't be computed — the warped source
+    /// crosses the horizon line (`Expand`, `InscribedCrop`) or no rectangle
+    /// fits inside it (`InscribedCrop`).
+    pub fn coverage_window(&self, w: u32, h: u32) -> Option<(f64, f64, f64, f64)> {
+        let (fw, fh) = (w as f64, h as f64);
+        if self.coverage == WarpCoverage::CropToOriginal {
+            return Some((0.0, 0.0, fw, fh));
+        }
+        let inv = invert_3x3(&self.matrix)?;
+        let corners = [(0.0, 0.0), (fw, 0.0), (fw, fh), (0.0, fh)];
+        let ws = corners.map(|(x, y)| inv[6] * x + inv[7] * y + inv[8]);
+        if ws
+            .iter()
+            .any(|&d| d.abs() < 1e-12 || d.signum() != ws[0].signum())
+        {
+            return None;
+        }
+        let quad = corners.map(|(x, y)| apply_projective(&inv, x, y));
+        match self.coverage {
+            WarpCoverage::Expand { .. } => {
+                let (mut x0, mut y0) = (f64::INFINITY, f64::INFINITY);
+                let (mut x1, mut y1) = (f64::NEG_INFINITY, f64::NEG_INFINITY);
+                for (x, y) in quad {
+                    (x0, y0, x1, y1) = (x0.min(x), y0.min(y), x1.max(x), y1.max(y));
+                }
+                Some((x0, y0, x1 - x0, y1 - y0))
+            }
+            _ => inscribed_in_quad(&quad, fw, fh),
+        }
+    }
+
+    /// Perspective correction from four detected corners (document scanning).
+    ///
+    /// `quad` holds the corners in source pixel coordinates, clockwise from
+    /// top-left: `[top_left, top_right, bottom_right, bottom_left]`. The
+    /// output is an axis-aligned rectangle with the document's estimated
+    /// true aspect ratio (see [`quad_aspect_ratio()`]), sized by `policy`:
+    ///
+    /// - `MatchNarrow` / `MatchWide` / `MatchArea` — the quad's edge lengths
+    ///   (as measured by [`warp_output_dims()`]) give a width and a height
+    ///   candidate; the output takes the smaller, larger, or geometric-mean
+    ///   scale of the two at the estimated aspect ratio.
+    /// - `PreserveInput` — output is `src_w × src_h` (aspect not corrected).
+    /// - `Custom(w, h)` — output is exactly `w × h`.
+    ///
+    /// The quad is in absolute source pixels, so the effect is only
+    /// meaningful for an input of `src_w × src_h`; the result always uses a
+    /// `Custom` policy with the computed size.
+    ///
+    /// Returns `None` if the quad is degenerate (collinear corners,
+    /// non-finite values) or `src_w`/`src_h` is zero.
+    ///
+    /// `focal_length_px` is the camera focal length in source pixels,
+    /// used for the aspect estimate; pass `None` to estimate it from the quad.
+    pub fn from_quad(
+        src_w: u32,
+        src_h: u32,
+        quad: [(f32, f32); 4],
+        focal_length_px: Option<f32>,
+        policy: ResolutionPolicy,
+    ) -> Option<Self> {
+        if src_w == 0 || src_h == 0 {
+            return None;
+        }
+        let q = quad.map(|(x, y)| (x as f64, y as f64));
+        if q.iter().any(|(x, y)| !x.is_finite() || !y.is_finite()) {
+            return None;
+        }
+        let (fw, fh) = (src_w as f64, src_h as f64);
+
+        // Unit square → quad, then pre-scale so the reference output frame
+        // (0..src_w, 0..src_h) maps onto the quad.
+        let unit = square_to_quad(&q)?;
+        let matrix = mul_3x3(
+            &unit,
+            &[1.0 / fw, 0.0, 0.0, 0.0, 1.0 / fh, 0.0, 0.0, 0.0, 1.0],
+        );
+        invert_3x3(&matrix)?;
+
+        let (out_w, out_h) = match policy {
+            ResolutionPolicy::PreserveInput => (src_w, src_h),
+            ResolutionPolicy::Custom(w, h) => (w, h),
+            _ => {
+                let aspect = quad_aspect_ratio(quad, src_w, src_h, focal_length_px)?;
+                // Edge-length candidates for each axis.
+                let (cw, ch) = warp_output_dims(src_w, src_h, &matrix, policy);
+                let (by_w, by_h) = (cw as f64 / aspect, ch as f64);
+                let out_h = match policy {
+                    ResolutionPolicy::MatchNarrow => by_w.min(by_h),
+                    ResolutionPolicy::MatchWide => by_w.max(by_h),
+                    _ => (by_w * by_h).sqrt(),
+                };
+                (
+                    (out_h * aspect).round().max(1.0) as u32,
+                    out_h.round().max(1.0) as u32,
+                )
+            }
+        };
+        Some(Self::new_f64(
+            matrix,
+            ResolutionPolicy::Custom(out_w, out_h),
+        ))
     }
 }
 
+/// Estimate the true width/height ratio of a rectangle seen as `quad`.
+///
+/// Uses the projective rectangle constraint from Zhang & He, "Whiteboard
+/// scanning and image enhancement" (2007), with the principal point at the
+/// image centre. When `focal_length_px` is `None` the focal length is
+/// recovered from the quad itself; if that is impossible (the quad is a
+/// parallelogram, so there is no vanishing point) the estimate falls back
+/// to an orthographic one. `quad` is `[top_left, top_right, bottom_right,
+/// bottom_left]` in source pixels.
+///
+/// Returns `None` for degenerate quads.
+pub fn quad_aspect_ratio(
+    quad: [(f32, f32); 4],
+    src_w: u32,
+    src_h: u32,
+    focal_length_px: Option<f32>,
+) -> Option<f64> {
+    let (cx, cy) = (src_w as f64 / 2.0, src_h as f64 / 2.0);
+    // Centred homogeneous corners in Zhang's order: m1=TL, m2=TR, m3=BL, m4=BR.
+    let c = |i: usize| [quad[i].0 as f64 - cx, quad[i].1 as f64 - cy, 1.0];
+    let (m1, m2, m3, m4) = (c(0), c(1), c(3), c(2));
+
+    let d2 = dot3(&cross3(&m2, &m4), &m3);
+    let d3 = dot3(&cross3(&m3, &m4), &m2);
+    if d2.abs() < 1e-12 || d3.abs() < 1e-12 {
+        return None;
+    }
+    let k2 = dot3(&cross3(&m1, &m4), &m3) / d2;
+    let k3 = dot3(&cross3(&m1, &m4), &m2) / d3;
+    let n2 = [k2 * m2[0] - m1[0], k2 * m2[1] - m1[1], k2 * m2[2] - m1[2]];
+    let n3 = [k3 * m3[0] - m1[0], k3 * m3[1] - m1[1], k3 * m3[2] - m1[2]];
+
+    let f2 = match focal_length_px {
+        Some(f) if f.is_finite() && f > 0.0 => Some((f as f64).powi(2)),
+        _ => {
+            let den = n2[2] * n3[2];
+            let f2 = -(n2[0] * n3[0] + n2[1] * n3[1]) / den;
+            // Parallel edges (n·3 ≈ 0) carry no focal information.
+            (den.abs() > 1e-9 && f2.is_finite() && f2 > 0.0).then_some(f2)
+        }
+    };
+
+    let ratio2 = match f2 {
+        Some(f2) => {
+            (n2[0] * n2[0] / f2 + n2[1] * n2[1] / f2 + n2[2] * n2[2])
+                / (n3[0] * n3[0] / f2 + n3[1] * n3[1] / f2 + n3[2] * n3[2])
+        }
+        // Orthographic: ignore the depth component.
+        None => (n2[0] * n2[0] + n2[1] * n2[1]) / (n3[0] * n3[0] + n3[1] * n3[1]),
+    };
+    let ratio = ratio2.sqrt();
+    (ratio.is_finite() && ratio > 0.0).then_some(ratio)
+}
+
 impl DimensionEffect for WarpEffect {
     fn forward(&self, w: u32, h: u32) -> Option<(u32, u32)> {
-        Some(warp_output_dims(w, h, &self.matrix, self.policy))
+        if self.matrix.iter().any(|v| !v.is_finite()) {
+            return None;
+        }
+        let (ref_w, ref_h) = warp_output_dims(w, h, &self.matrix, self.policy);
+        if self.coverage == WarpCoverage::CropToOriginal {
+            return Some((ref_w, ref_h));
+        }
+        // The policy sets the density of the reference frame; the coverage
+        // window is sampled at that density.
+        let (_, _, ww, wh) = self.coverage_window(w, h)?;
+        Some((
+            (ww * ref_w as f64 / w.max(1) as f64).round().max(1.0) as u32,
+            (wh * ref_h as f64 / h.max(1) as f64).round().max(1.0) as u32,
+        ))
     }
 
     fn inverse(&self, out_w: u32, out_h: u32) -> Option<(u32, u32)> {
-        warp_inverse_dims(out_w, out_h, &self.matrix, self.policy)
+        if self.coverage == WarpCoverage::CropToOriginal {
+            return warp_inverse_dims(out_w, out_h, &self.matrix, self.policy);
+        }
+        refine_inverse(out_w, out_h, |w, h| self.forward(w, h))
+    }
+
+    fn forward_point(&self, x: f32, y: f32, in_w: u32, in_h: u32) -> Option<(f32, f32)> {
+        let (out_w, out_h) = self.forward(in_w, in_h)?;
+        let (x0, y0, ww, wh) = self.coverage_window(in_w, in_h)?;
+        // Forward point mapping: source → output via M⁻¹.
+        // M maps output→source, so M⁻¹ maps source→reference frame.
+        let inv = invert_3x3(&self.matrix)?;
+        let (ox, oy) = apply_projective(&inv, x as f64, y as f64);
+        // Offset into the coverage window, then scale to actual output dims.
+        Some((
+            ((ox - x0) * out_w as f64 / ww.max(1e-9)) as f32,
+            ((oy - y0) * out_h as f64 / wh.max(1e-9)) as f32,
+        ))
+    }
+
+    fn inverse_point(&self, x: f32, y: f32, in_w: u32, in_h: u32) -> Option<(f32, f32)> {
+        let (out_w, out_h) = self.forward(in_w, in_h)?;
+        let (x0, y0, ww, wh) = self.coverage_window(in_w, in_h)?;
+        // Inverse point: output → source via M.
+        // Scale from actual output coords to the reference frame first.
+        let mx = x as f64 * ww / out_w.max(1) as f64 + x0;
+        let my = y as f64 * wh / out_h.max(1) as f64 + y0;
+        let (sx, sy) = apply_projective(&self.matrix, mx, my);
+        Some((sx as f32, sy as f32))
+    }
+
+    fn clone_boxed(&self) -> Box<dyn DimensionEffect> {
+        Box::new(self.clone())
+    }
+
+    fn scaled(&self, sx: f64, sy: f64) -> Box<dyn DimensionEffect> {
+        // Conjugate by S = diag(sx, sy, 1): M' = S · M · S⁻¹.
+        let s = [sx, sy, 1.0];
+        let mut matrix = self.matrix;
+        for (i, v) in matrix.iter_mut().enumerate() {
+            *v *= s[i / 3] / s[i % 3];
+        }
+        let policy = match self.policy {
+            ResolutionPolicy::Custom(w, h) => ResolutionPolicy::Custom(
+                (w as f64 * sx).round().max(1.0) as u32,
+                (h as f64 * sy).round().max(1.0) as u32,
+            ),
+            p => p,
+        };
+        Box::new(Self {
+            matrix,
+            policy,
+            coverage: self.coverage,
+        })
+    }
+
+    fn warp_matrix(&self, in_w: u32, in_h: u32) -> Option<[f64; 9]> {
+        let (out_w, out_h) = self.forward(in_w, in_h)?;
+        let (x0, y0, ww, wh) = self.coverage_window(in_w, in_h)?;
+        // Output pixels → reference frame → source.
+        let to_reference = [
+            ww / out_w.max(1) as f64,
+            0.0,
+            x0,
+            0.0,
+            wh / out_h.max(1) as f64,
+            y0,
+            0.0,
+            0.0,
+            1.0,
+        ];
+        Some(mul_3x3(&self.matrix, &to_reference))
+    }
+
+    fn fill_color(&self) -> Option<CanvasColor> {
+        match self.coverage {
+            WarpCoverage::Expand { color } => Some(color),
+            _ => None,
+        }
+    }
+
+    fn kind(&self, w: u32, h: u32) -> EffectKind {
+        // A warp never needs analysis: no output means a degenerate matrix.
+        if self.forward(w, h).is_some() {
+            EffectKind::Geometric
+        } else {
+            EffectKind::Invalid
+        }
+    }
+}
+
+/// Adjacent matrix-expressible effects composed into a single resample.
+///
+/// Produced by [`compute_layout_sequential()`](crate::compute_layout_sequential)
+/// when consecutive effects all provide [`DimensionEffect::warp_matrix()`] —
+/// e.g. a rotation followed by a perspective warp. Sampling once through
+/// the combined matrix avoids the double blur of two passes.
+///
+/// Dimensions and point mapping chain through `parts`, so they match the
+/// unfused sequence exactly. The single resample does not reproduce
+/// intermediate clipping: source content that an earlier part cropped away
+/// can reappear where a later part expands the canvas.
+#[derive(Clone, Debug)]
+pub struct FusedEffect {
+    /// The fused effects, in application order.
+    pub parts: Vec<Box<dyn DimensionEffect>>,
+}
+
+impl FusedEffect {
+    /// Fuse `parts`, applied in order.
+    pub fn new(parts: Vec<Box<dyn DimensionEffect>>) -> Self {
+        Self { parts }
+    }
+
+    /// Input dimensions of each part, plus the final output.
+    fn stage_dims(&self, w: u32, h: u32) -> Option<Vec<(u32, u32)>> {
+        let mut dims = Vec::with_capacity(self.parts.len() + 1);
+        dims.push((w, h));
+        for part in &self.parts {
+            let &(cw, ch) = dims.last()?;
+            dims.push(part.forward(cw, ch)?);
+        }
+        Some(dims)
+    }
+}
+
+impl DimensionEffect for FusedEffect {
+    fn forward(&self, w: u32, h: u32) -> Option<(u32, u32)> {
+        self.stage_dims(w, h)?.last().copied()
+    }
+
+    fn inverse(&self, w: u32, h: u32) -> Option<(u32, u32)> {
+        self.parts
+            .iter()
+            .rev()
+            .try_fold((w, h), |(cw, ch), part| part.inverse(cw, ch))
     }
 
     fn forward_point(&self, x: f32, y: f32, in_w: u32, in_h: u32) -> Option<(f32, f32)> {
-        let (out_w, out_h) = self.forward(in_w, in_h)?;
-        // Forward point mapping: source → output via M⁻¹.
-        // M maps output→source, so M⁻¹ maps source→output.
-        // If output dims differ from input, scale the output coordinates.
-        let inv = invert_3x3(&self.matrix)?;
-        let (ox, oy) = apply_projective(&inv, x as f64, y as f64);
-        // The matrix was built for output space (0..in_w, 0..in_h).
-        // Scale to actual output dims.
-        Some((
-            (ox * out_w as f64 / in_w.max(1) as f64) as f32,
-            (oy * out_h as f64 / in_h.max(1) as f64) as f32,
-        ))
+        let dims = self.stage_dims(in_w, in_h)?;
+        self.parts
+            .iter()
+            .zip(&dims)
+            .try_fold((x, y), |(px, py), (part, &(w, h))| {
+                part.forward_point(px, py, w, h)
+            })
     }
 
     fn inverse_point(&self, x: f32, y: f32, in_w: u32, in_h: u32) -> Option<(f32, f32)> {
-        let (out_w, out_h) = self.forward(in_w, in_h)?;
-        // Inverse point: output → source via M.
-        // Scale from actual output coords to matrix output space first.
-        let mx = x as f64 * in_w as f64 / out_w.max(1) as f64;
-        let my = y as f64 * in_h as f64 / out_h.max(1) as f64;
-        let (sx, sy) = apply_projective(&self.matrix, mx, my);
-        Some((sx as f32, sy as f32))
+        let dims = self.stage_dims(in_w, in_h)?;
+        self.parts
+            .iter()
+            .zip(&dims)
+            .rev()
+            .try_fold((x, y), |(px, py), (part, &(w, h))| {
+                part.inverse_point(px, py, w, h)
+            })
     }
 
     fn clone_boxed(&self) -> Box<dyn DimensionEffect> {
         Box::new(self.clone())
     }
+
+    fn scaled(&self, sx: f64, sy: f64) -> Box<dyn DimensionEffect> {
+        Box::new(Self {
+            parts: self.parts.iter().map(|p| p.scaled(sx, sy)).collect(),
+        })
+    }
+
+    fn warp_matrix(&self, in_w: u32, in_h: u32) -> Option<[f64; 9]> {
+        // Output → input chains right to left: M₁ · M₂ · … · Mₙ.
+        let dims = self.stage_dims(in_w, in_h)?;
+        self.parts
+            .iter()
+            .zip(&dims)
+            .try_fold(IDENTITY_3X3, |acc, (part, &(w, h))| {
+                Some(mul_3x3(&acc, &part.warp_matrix(w, h)?))
+            })
+    }
+
+    fn fill_color(&self) -> Option<CanvasColor> {
+        self.parts.iter().find_map(|p| p.fill_color())
+    }
+}
+
+/// The [`Orientation`] an effect is equivalent to on a `w × h` input, if any.
+///
+/// Only matrix-expressible effects qualify. The output corners must land
+/// within a pixel of the orientation's, and the output dimensions within a
+/// pixel — effects that compute dims in floating point (e.g. a 90°
+/// expanding [`RotateEffect`]) can round up by one.
+pub(crate) fn effect_orientation(
+    effect: &dyn DimensionEffect,
+    w: u32,
+    h: u32,
+) -> Option<Orientation> {
+    effect.warp_matrix(w, h)?;
+    let (out_w, out_h) = effect.forward(w, h)?;
+    let (fw, fh) = (w as f32, h as f32);
+    let corners = [(0.0, 0.0), (fw, 0.0), (fw, fh), (0.0, fh)];
+    let mapped = corners.map(|(x, y)| effect.forward_point(x, y, w, h));
+    (1..=8).filter_map(Orientation::from_exif).find(|o| {
+        let expected = o.transform_dimensions(w, h);
+        expected.width.abs_diff(out_w) <= 1
+            && expected.height.abs_diff(out_h) <= 1
+            && corners.iter().zip(&mapped).all(|(&(x, y), m)| {
+                let (ex, ey) = o.transform_point_to_display(x, y, w, h);
+                m.is_some_and(|(mx, my)| (mx - ex).abs() <= 1.0 && (my - ey).abs() <= 1.0)
+            })
+    })
 }
 
 /// Compute output dimensions for a 3×3 projective warp.
@@ -588,6 +1450,620 @@ fn warp_inverse_dims(
     }
 }
 
+/// Inverse dims for effects whose output depends on the input size in ways
+/// that have no closed form: refine a guess by rescaling until `forward`
+/// lands on the target.
+fn refine_inverse(
+    out_w: u32,
+    out_h: u32,
+    forward: impl Fn(u32, u32) -> Option<(u32, u32)>,
+) -> Option<(u32, u32)> {
+    let (mut w, mut h) = (out_w.max(1), out_h.max(1));
+    for _ in 0..8 {
+        let (fw, fh) = forward(w, h)?;
+        if (fw, fh) == (out_w, out_h) {
+            break;
+        }
+        w = (w as f64 * out_w as f64 / fw.max(1) as f64)
+            .round()
+            .max(1.0) as u32;
+        h = (h as f64 * out_h as f64 / fh.max(1) as f64)
+            .round()
+            .max(1.0) as u32;
+    }
+    Some((w, h))
+}
+
+// ── Lens distortion ──
+
+/// Lens distortion model, in normalized camera coordinates
+/// (`(pixel − principal point) / focal length`).
+///
+/// Coefficients follow OpenCV's `calibrateCamera` and `fisheye` modules,
+/// so calibration results can be used directly.
+#[non_exhaustive]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum LensModel {
+    /// Brown–Conrady: radial `k1..k3` and tangential `p1, p2`.
+    ///
+    /// `k1 < 0` is barrel distortion, `k1 > 0` pincushion.
+    BrownConrady {
+        k1: f64,
+        k2: f64,
+        k3: f64,
+        p1: f64,
+        p2: f64,
+    },
+    /// Equidistant fisheye (Kannala–Brandt):
+    /// `θd = θ·(1 + k1·θ² + k2·θ⁴ + k3·θ⁶ + k4·θ⁸)`.
+    ///
+    /// Undistorts to a rectilinear image, which can't show rays at or past
+    /// 90° from the axis.
+    Fisheye { k1: f64, k2: f64, k3: f64, k4: f64 },
+}
+
+impl LensModel {
+    /// Radial-only Brown–Conrady.
+    pub fn radial(k1: f64, k2: f64, k3: f64) -> Self {
+        Self::BrownConrady {
+            k1,
+            k2,
+            k3,
+            p1: 0.0,
+            p2: 0.0,
+        }
+    }
+
+    /// Map an undistorted normalized point to where the lens images it.
+    fn distort(&self, x: f64, y: f64) -> (f64, f64) {
+        match *self {
+            Self::BrownConrady { k1, k2, k3, p1, p2 } => {
+                let r2 = x * x + y * y;
+                let radial = 1.0 + r2 * (k1 + r2 * (k2 + r2 * k3));
+                (
+                    x * radial + 2.0 * p1 * x * y + p2 * (r2 + 2.0 * x * x),
+                    y * radial + p1 * (r2 + 2.0 * y * y) + 2.0 * p2 * x * y,
+                )
+            }
+            Self::Fisheye { k1, k2, k3, k4 } => {
+                let r = (x * x + y * y).sqrt();
+                if r < 1e-12 {
+                    return (x, y);
+                }
+                let theta = r.atan();
+                let t2 = theta * theta;
+                let theta_d = theta * (1.0 + t2 * (k1 + t2 * (k2 + t2 * (k3 + t2 * k4))));
+                (x * theta_d / r, y * theta_d / r)
+            }
+        }
+    }
+
+    /// Invert [`distort`](Self::distort) by Newton iteration.
+    ///
+    /// Returns `None` if the iteration doesn't converge (the point lies
+    /// outside the model's invertible range).
+    fn undistort(&self, xd: f64, yd: f64) -> Option<(f64, f64)> {
+        const H: f64 = 1e-7;
+        let (mut x, mut y) = (xd, yd);
+        for _ in 0..50 {
+            let (fx, fy) = self.distort(x, y);
+            let (ex, ey) = (fx - xd, fy - yd);
+            if ex.abs() < 1e-12 && ey.abs() < 1e-12 {
+                return Some((x, y));
+            }
+            // Central-difference Jacobian, then a 2×2 solve.
+            let (ax, ay) = self.distort(x + H, y);
+            let (bx, by) = self.distort(x - H, y);
+            let (cx, cy) = self.distort(x, y + H);
+            let (dx, dy) = self.distort(x, y - H);
+            let (j00, j10) = ((ax - bx) / (2.0 * H), (ay - by) / (2.0 * H));
+            let (j01, j11) = ((cx - dx) / (2.0 * H), (cy - dy) / (2.0 * H));
+            let det = j00 * j11 - j01 * j10;
+            if det.abs() < 1e-12 || !det.is_finite() {
+                return None;
+            }
+            x -= (j11 * ex - j01 * ey) / det;
+            y -= (j00 * ey - j10 * ex) / det;
+        }
+        let (fx, fy) = self.distort(x, y);
+        ((fx - xd).abs() < 1e-9 && (fy - yd).abs() < 1e-9).then_some((x, y))
+    }
+}
+
+/// `(x, y, width, height)` in a reference output frame.
+type Window = (f64, f64, f64, f64);
+
+/// Lens distortion correction (undistortion).
+///
+/// The input is the image as the lens recorded it; the output is the
+/// rectilinear image an ideal pinhole camera with the same intrinsics
+/// would have taken. Output → source is the closed-form distortion model;
+/// source → output inverts it iteratively, so point mapping is exact in
+/// both directions and annotations survive undistortion.
+///
+/// As with [`WarpEffect`], the output is a window in a reference frame the
+/// size of the input, chosen by `coverage`, at a pixel density chosen by
+/// `policy` (measured from the local scale of the mapping across the
+/// window). With the defaults — the input's own frame at
+/// [`MatchArea`](ResolutionPolicy::MatchArea) density — barrel correction
+/// shrinks the output, since the lens squeezed fewer source pixels into the
+/// edges, and pincushion correction enlarges it. Other coverages move the
+/// window instead: undistorting barrel distortion pulls the corners
+/// outward, so [`WarpCoverage::Expand`] widens it; pincushion pulls them
+/// inward, so [`WarpCoverage::InscribedCrop`] narrows it. The inscribed
+/// rectangle is centred on the image.
+///
+/// Non-positive or non-finite focal lengths, and non-finite coefficients or
+/// principal points, are [invalid](EffectKind::Invalid); so is a coverage
+/// the lens cannot satisfy (e.g. `Expand` when part of the border cannot be
+/// undistorted).
+///
+/// Intrinsics are in input pixels, so the effect is tied to the input
+/// resolution it was calibrated at; [`scaled()`](DimensionEffect::scaled)
+/// adapts it for other planes.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct LensEffect {
+    /// Distortion model and coefficients.
+    pub model: LensModel,
+    /// Horizontal focal length in pixels.
+    pub fx: f64,
+    /// Vertical focal length in pixels.
+    pub fy: f64,
+    /// Principal point x in pixels.
+    pub cx: f64,
+    /// Principal point y in pixels.
+    pub cy: f64,
+    /// Output pixel density.
+    pub policy: ResolutionPolicy,
+    /// Which part of the undistorted image is kept.
+    pub coverage: WarpCoverage,
+}
+
+impl LensEffect {
+    /// Create from a model, focal length (pixels) and principal point.
+    ///
+    /// Defaults to [`ResolutionPolicy::MatchArea`] and
+    /// [`WarpCoverage::CropToOriginal`].
+    pub fn new(model: LensModel, focal_px: f64, principal_point: (f64, f64)) -> Self {
+        Self {
+            model,
+            fx: focal_px,
+            fy: focal_px,
+            cx: principal_point.0,
+            cy: principal_point.1,
+            policy: ResolutionPolicy::MatchArea,
+            coverage: WarpCoverage::CropToOriginal,
+        }
+    }
+
+    /// Set the resolution policy.
+    pub fn with_policy(mut self, policy: ResolutionPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Set which part of the undistorted image is kept.
+    pub fn with_coverage(mut self, coverage: WarpCoverage) -> Self {
+        self.coverage = coverage;
+        self
+    }
+
+    /// Whether the intrinsics and coefficients are usable: positive finite
+    /// focal lengths, a finite principal point and finite coefficients.
+    pub fn is_valid(&self) -> bool {
+        let coefficients: &[f64] = match &self.model {
+            LensModel::BrownConrady { k1, k2, k3, p1, p2 } => &[*k1, *k2, *k3, *p1, *p2],
+            LensModel::Fisheye { k1, k2, k3, k4 } => &[*k1, *k2, *k3, *k4],
+        };
+        self.fx.is_finite()
+            && self.fx > 0.0
+            && self.fy.is_finite()
+            && self.fy > 0.0
+            && self.cx.is_finite()
+            && self.cy.is_finite()
+            && coefficients.iter().all(|c| c.is_finite())
+    }
+
+    /// Reference frame → source pixels (closed form).
+    fn distort_px(&self, x: f64, y: f64) -> (f64, f64) {
+        let (dx, dy) = self
+            .model
+            .distort((x - self.cx) / self.fx, (y - self.cy) / self.fy);
+        (dx * self.fx + self.cx, dy * self.fy + self.cy)
+    }
+
+    /// Source pixels → reference frame (iterative).
+    fn undistort_px(&self, x: f64, y: f64) -> Option<(f64, f64)> {
+        let (ux, uy) = self
+            .model
+            .undistort((x - self.cx) / self.fx, (y - self.cy) / self.fy)?;
+        Some((ux * self.fx + self.cx, uy * self.fy + self.cy))
+    }
+
+    /// The kept window `(x, y, width, height)` in the reference output
+    /// frame for a `w × h` input.
+    ///
+    /// Returns `None` if part of the source boundary can't be undistorted
+    /// (`Expand`) or no rectangle fits (`InscribedCrop`).
+    pub fn coverage_window(&self, w: u32, h: u32) -> Option<(f64, f64, f64, f64)> {
+        let (fw, fh) = (w as f64, h as f64);
+        match self.coverage {
+            WarpCoverage::Expand { .. } => {
+                let (mut x0, mut y0) = (f64::INFINITY, f64::INFINITY);
+                let (mut x1, mut y1) = (f64::NEG_INFINITY, f64::NEG_INFINITY);
+                for (x, y) in rect_boundary(0.0, 0.0, fw, fh) {
+                    let (ux, uy) = self.undistort_px(x, y)?;
+                    (x0, y0, x1, y1) = (x0.min(ux), y0.min(uy), x1.max(ux), y1.max(uy));
+                }
+                Some((x0, y0, x1 - x0, y1 - y0))
+            }
+            WarpCoverage::InscribedCrop => {
+                // Scale a centred w:h rectangle until its boundary leaves
+                // the source.
+                let fits = |s: f64| {
+                    let (rw, rh) = (fw * s, fh * s);
+                    rect_boundary((fw - rw) / 2.0, (fh - rh) / 2.0, rw, rh).all(|(x, y)| {
+                        let (sx, sy) = self.distort_px(x, y);
+                        (-1e-9..=fw + 1e-9).contains(&sx) && (-1e-9..=fh + 1e-9).contains(&sy)
+                    })
+                };
+                let (mut lo, mut hi) = (0.0, 1.0);
+                while fits(hi) {
+                    lo = hi;
+                    hi *= 2.0;
+                    if hi > 64.0 {
+                        return None;
+                    }
+                }
+                for _ in 0..48 {
+                    let mid = (lo + hi) / 2.0;
+                    if fits(mid) { lo = mid } else { hi = mid }
+                }
+                (lo > 0.0).then(|| {
+                    let (rw, rh) = (fw * lo, fh * lo);
+                    ((fw - rw) / 2.0, (fh - rh) / 2.0, rw, rh)
+                })
+            }
+            _ => Some((0.0, 0.0, fw, fh)),
+        }
+    }
+
+    /// Output pixels per reference-frame pixel, per axis.
+    fn density(&self, w: u32, h: u32, window: Window) -> (f64, f64) {
+        let (fw, fh) = (w.max(1) as f64, h.max(1) as f64);
+        match self.policy {
+            ResolutionPolicy::PreserveInput => (1.0, 1.0),
+            ResolutionPolicy::Custom(cw, ch) => (cw as f64 / fw, ch as f64 / fh),
+            policy => {
+                // Local scale of reference → source across a 3×3 grid.
+                let (x0, y0, ww, wh) = window;
+                let (mut lo, mut hi) = ((f64::INFINITY, f64::INFINITY), (0.0f64, 0.0f64));
+                for i in 0..3 {
+                    for j in 0..3 {
+                        let x = x0 + ww * i as f64 / 2.0;
+                        let y = y0 + wh * j as f64 / 2.0;
+                        let p = self.distort_px(x, y);
+                        let scale_x = dist_f64(p, self.distort_px(x + 0.5, y)) * 2.0;
+                        let scale_y = dist_f64(p, self.distort_px(x, y + 0.5)) * 2.0;
+                        lo = (lo.0.min(scale_x), lo.1.min(scale_y));
+                        hi = (hi.0.max(scale_x), hi.1.max(scale_y));
+                    }
+                }
+                match policy {
+                    ResolutionPolicy::MatchNarrow => lo,
+                    ResolutionPolicy::MatchWide => hi,
+                    _ => ((lo.0 * hi.0).sqrt(), (lo.1 * hi.1).sqrt()),
+                }
+            }
+        }
+    }
+
+    /// Window and output dims for a `w × h` input.
+    fn resolve(&self, w: u32, h: u32) -> Option<(Window, (u32, u32))> {
+        if w == 0 || h == 0 || !self.is_valid() {
+            return None;
+        }
+        let window = self.coverage_window(w, h)?;
+        let (dx, dy) = self.density(w, h, window);
+        let out = (
+            (window.2 * dx).round().max(1.0) as u32,
+            (window.3 * dy).round().max(1.0) as u32,
+        );
+        Some((window, out))
+    }
+}
+
+impl DimensionEffect for LensEffect {
+    fn forward(&self, w: u32, h: u32) -> Option<(u32, u32)> {
+        Some(self.resolve(w, h)?.1)
+    }
+
+    fn inverse(&self, w: u32, h: u32) -> Option<(u32, u32)> {
+        match (self.policy, self.coverage) {
+            // Custom dims on the original canvas ignore the input size.
+            (ResolutionPolicy::Custom(..), WarpCoverage::CropToOriginal) => None,
+            _ => refine_inverse(w, h, |iw, ih| self.forward(iw, ih)),
+        }
+    }
+
+    fn forward_point(&self, x: f32, y: f32, in_w: u32, in_h: u32) -> Option<(f32, f32)> {
+        let ((x0, y0, ww, wh), (out_w, out_h)) = self.resolve(in_w, in_h)?;
+        let (rx, ry) = self.undistort_px(x as f64, y as f64)?;
+        Some((
+            ((rx - x0) * out_w as f64 / ww) as f32,
+            ((ry - y0) * out_h as f64 / wh) as f32,
+        ))
+    }
+
+    fn inverse_point(&self, x: f32, y: f32, in_w: u32, in_h: u32) -> Option<(f32, f32)> {
+        let ((x0, y0, ww, wh), (out_w, out_h)) = self.resolve(in_w, in_h)?;
+        let (sx, sy) = self.distort_px(
+            x as f64 * ww / out_w as f64 + x0,
+            y as f64 * wh / out_h as f64 + y0,
+        );
+        Some((sx as f32, sy as f32))
+    }
+
+    fn kind(&self, w: u32, h: u32) -> EffectKind {
+        // Output dims never depend on content; failing to size is invalid.
+        if self.resolve(w.max(1), h.max(1)).is_some() {
+            EffectKind::Geometric
+        } else {
+            EffectKind::Invalid
+        }
+    }
+
+    fn clone_boxed(&self) -> Box<dyn DimensionEffect> {
+        Box::new(*self)
+    }
+
+    fn scaled(&self, sx: f64, sy: f64) -> Box<dyn DimensionEffect> {
+        let policy = match self.policy {
+            ResolutionPolicy::Custom(w, h) => ResolutionPolicy::Custom(
+                (w as f64 * sx).round().max(1.0) as u32,
+                (h as f64 * sy).round().max(1.0) as u32,
+            ),
+            p => p,
+        };
+        Box::new(Self {
+            fx: self.fx * sx,
+            fy: self.fy * sy,
+            cx: self.cx * sx,
+            cy: self.cy * sy,
+            policy,
+            ..*self
+        })
+    }
+
+    fn fill_color(&self) -> Option<CanvasColor> {
+        match self.coverage {
+            WarpCoverage::Expand { color } => Some(color),
+            _ => None,
+        }
+    }
+}
+
+/// Evenly spaced points around a rectangle's boundary, for mapping curved
+/// edges.
+fn rect_boundary(x: f64, y: f64, w: f64, h: f64) -> impl Iterator<Item = (f64, f64)> {
+    const STEPS: usize = 32;
+    (0..STEPS).flat_map(move |i| {
+        let t = i as f64 / STEPS as f64;
+        [
+            (x + w * t, y),
+            (x + w, y + h * t),
+            (x + w * (1.0 - t), y + h),
+            (x, y + h * (1.0 - t)),
+        ]
+    })
+}
+
+// ── Equirectangular viewport ──
+
+/// Rectilinear view extracted from an equirectangular (360°) source.
+///
+/// The input is a full sphere: x spans longitude −180°..180° and y spans
+/// latitude 90°..−90°. The output is what a pinhole camera at the centre
+/// of the sphere sees, looking along `yaw` (positive turns right, 0 is the
+/// source's centre column) and `pitch` (positive looks up), rotated by
+/// `roll` about the view axis, with horizontal field of view `hfov`. The
+/// vertical field of view follows from `aspect`.
+///
+/// Output dims come from the FOV and `policy`. A rectilinear view samples
+/// more densely toward its edges (by `1/cos²` of the off-axis angle), so:
+///
+/// - `MatchWide` — view centre matches the source's angular resolution;
+///   edges are upsampled. Largest output.
+/// - `MatchNarrow` — view edges match it; nothing is upsampled.
+/// - `MatchArea` — geometric mean of the two (the default).
+/// - `PreserveInput` — output width equals input width.
+/// - `Custom(w, h)` — exact dims; the FOV still spans the width.
+///
+/// Points map through the spherical projection exactly. Source points
+/// behind the camera have no output position (`forward_point` returns
+/// `None`); points outside the view map outside the canvas.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct EquirectViewEffect {
+    /// View direction, radians. Positive turns right.
+    pub yaw_rad: f64,
+    /// View elevation, radians. Positive looks up.
+    pub pitch_rad: f64,
+    /// Rotation about the view axis, radians.
+    pub roll_rad: f64,
+    /// Horizontal field of view, radians, in `(0, π)`.
+    pub hfov_rad: f64,
+    /// Output width / height.
+    pub aspect: f64,
+    /// How to size the output.
+    pub policy: ResolutionPolicy,
+}
+
+impl EquirectViewEffect {
+    /// Create from angles in degrees. Defaults to
+    /// [`ResolutionPolicy::MatchArea`].
+    pub fn from_degrees(yaw: f64, pitch: f64, roll: f64, hfov: f64, aspect: f64) -> Self {
+        Self {
+            yaw_rad: yaw.to_radians(),
+            pitch_rad: pitch.to_radians(),
+            roll_rad: roll.to_radians(),
+            hfov_rad: hfov.to_radians(),
+            aspect,
+            policy: ResolutionPolicy::MatchArea,
+        }
+    }
+
+    /// Set the resolution policy.
+    pub fn with_policy(mut self, policy: ResolutionPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Whether the parameters describe a view: `hfov` in `(0, π)`, a
+    /// positive finite `aspect`, finite angles and a non-empty `Custom`
+    /// size. Invalid views report [`EffectKind::Invalid`] and fail planning.
+    pub fn is_valid(&self) -> bool {
+        self.hfov_rad > 0.0
+            && self.hfov_rad < PI_F64
+            && self.aspect.is_finite()
+            && self.aspect > 0.0
+            && [self.yaw_rad, self.pitch_rad, self.roll_rad]
+                .iter()
+                .all(|a| a.is_finite())
+            && !matches!(self.policy, ResolutionPolicy::Custom(w, h) if w == 0 || h == 0)
+    }
+
+    /// Output focal length in pixels, relative to the centre's angular
+    /// resolution, for each policy.
+    fn focal_factor(&self) -> f64 {
+        let edge = (self.hfov_rad / 2.0).cos();
+        match self.policy {
+            ResolutionPolicy::MatchNarrow => edge * edge,
+            ResolutionPolicy::MatchWide => 1.0,
+            _ => edge,
+        }
+    }
+
+    /// Camera → world rotation (row-major): yaw · pitch · roll.
+    fn rotation(&self) -> [f64; 9] {
+        let (sy, cy) = self.yaw_rad.sin_cos();
+        let (sp, cp) = self.pitch_rad.sin_cos();
+        let (sr, cr) = self.roll_rad.sin_cos();
+        let yaw = [cy, 0.0, sy, 0.0, 1.0, 0.0, -sy, 0.0, cy];
+        let pitch = [1.0, 0.0, 0.0, 0.0, cp, -sp, 0.0, sp, cp];
+        let roll = [cr, -sr, 0.0, sr, cr, 0.0, 0.0, 0.0, 1.0];
+        mul_3x3(&mul_3x3(&yaw, &pitch), &roll)
+    }
+
+    /// Horizontal and vertical focal lengths for an output of `w × h`.
+    fn focal(&self, w: u32, h: u32) -> (f64, f64) {
+        let tan_h = (self.hfov_rad / 2.0).tan();
+        let tan_v = tan_h / self.aspect;
+        (w as f64 / 2.0 / tan_h, h as f64 / 2.0 / tan_v)
+    }
+}
+
+impl DimensionEffect for EquirectViewEffect {
+    fn forward(&self, w: u32, h: u32) -> Option<(u32, u32)> {
+        if !self.is_valid() || w == 0 || h == 0 {
+            return None;
+        }
+        let out_w = match self.policy {
+            ResolutionPolicy::Custom(cw, ch) => return Some((cw, ch)),
+            ResolutionPolicy::PreserveInput => w as f64,
+            _ => {
+                // Source pixels per radian at the equator.
+                let src = (w as f64 / (2.0 * PI_F64) * h as f64 / PI_F64).sqrt();
+                2.0 * src * self.focal_factor() * (self.hfov_rad / 2.0).tan()
+            }
+        };
+        Some((
+            out_w.round().max(1.0) as u32,
+            (out_w / self.aspect).round().max(1.0) as u32,
+        ))
+    }
+
+    fn inverse(&self, w: u32, _h: u32) -> Option<(u32, u32)> {
+        if !self.is_valid() {
+            return None;
+        }
+        // Assumes a 2:1 source, where angular resolution is equal on both axes.
+        let src_w = match self.policy {
+            ResolutionPolicy::Custom(..) => return None,
+            ResolutionPolicy::PreserveInput => w as f64,
+            _ => {
+                let src = w as f64 / (2.0 * self.focal_factor() * (self.hfov_rad / 2.0).tan());
+                src * 2.0 * PI_F64
+            }
+        };
+        let src_w = src_w.round().max(2.0) as u32;
+        Some((src_w, src_w / 2))
+    }
+
+    fn forward_point(&self, x: f32, y: f32, in_w: u32, in_h: u32) -> Option<(f32, f32)> {
+        let (out_w, out_h) = self.forward(in_w, in_h)?;
+        let lon = (x as f64 / in_w as f64 - 0.5) * 2.0 * PI_F64;
+        let lat = (0.5 - y as f64 / in_h as f64) * PI_F64;
+        let (sin_lon, cos_lon) = lon.sin_cos();
+        let (sin_lat, cos_lat) = lat.sin_cos();
+        let world = [cos_lat * sin_lon, -sin_lat, cos_lat * cos_lon];
+        // World → camera is the transpose.
+        let r = self.rotation();
+        let cam: [f64; 3] =
+            core::array::from_fn(|i| r[i] * world[0] + r[3 + i] * world[1] + r[6 + i] * world[2]);
+        if cam[2] <= 1e-12 {
+            return None;
+        }
+        let (fx, fy) = self.focal(out_w, out_h);
+        Some((
+            (fx * cam[0] / cam[2] + out_w as f64 / 2.0) as f32,
+            (fy * cam[1] / cam[2] + out_h as f64 / 2.0) as f32,
+        ))
+    }
+
+    fn inverse_point(&self, x: f32, y: f32, in_w: u32, in_h: u32) -> Option<(f32, f32)> {
+        let (out_w, out_h) = self.forward(in_w, in_h)?;
+        let (fx, fy) = self.focal(out_w, out_h);
+        let cam = [
+            (x as f64 - out_w as f64 / 2.0) / fx,
+            (y as f64 - out_h as f64 / 2.0) / fy,
+            1.0,
+        ];
+        let r = self.rotation();
+        let world: [f64; 3] =
+            core::array::from_fn(|i| dot3(&[r[3 * i], r[3 * i + 1], r[3 * i + 2]], &cam));
+        let lon = world[0].atan2(world[2]);
+        let lat = (-world[1]).atan2((world[0] * world[0] + world[2] * world[2]).sqrt());
+        let u = lon / (2.0 * PI_F64) + 0.5;
+        let sx = (u - u.floor()) * in_w as f64;
+        let sy = (0.5 - lat / PI_F64) * in_h as f64;
+        Some((sx as f32, sy as f32))
+    }
+
+    fn kind(&self, _w: u32, _h: u32) -> EffectKind {
+        // Output dims never depend on content.
+        if self.is_valid() {
+            EffectKind::Geometric
+        } else {
+            EffectKind::Invalid
+        }
+    }
+
+    fn clone_boxed(&self) -> Box<dyn DimensionEffect> {
+        Box::new(*self)
+    }
+
+    fn scaled(&self, sx: f64, sy: f64) -> Box<dyn DimensionEffect> {
+        let policy = match self.policy {
+            ResolutionPolicy::Custom(w, h) => ResolutionPolicy::Custom(
+                (w as f64 * sx).round().max(1.0) as u32,
+                (h as f64 * sy).round().max(1.0) as u32,
+            ),
+            p => p,
+        };
+        Box::new(Self { policy, ..*self })
+    }
+}
+
 // ── 3×3 matrix helpers ──
 
 /// Apply a 3×3 projective matrix to a point, returning (x, y) after division.
@@ -623,6 +2099,157 @@ fn invert_3x3(m: &[f64; 9]) -> Option<[f64; 9]> {
     ])
 }
 
+/// Whether the output → input warp `m` drops part of its `in_w × in_h`
+/// input, i.e. an input corner lands outside the `out_w × out_h` output.
+pub(crate) fn warp_clips(m: &[f64; 9], in_w: u32, in_h: u32, out_w: u32, out_h: u32) -> bool {
+    match invert_3x3(m) {
+        Some(inv) => !corners_inside(&inv, (in_w, in_h), (out_w, out_h)),
+        None => true,
+    }
+}
+
+/// Whether the output → input warp `m` shows area outside its input,
+/// i.e. an output corner samples beyond the `in_w × in_h` input.
+pub(crate) fn warp_fills(m: &[f64; 9], in_w: u32, in_h: u32, out_w: u32, out_h: u32) -> bool {
+    !corners_inside(m, (out_w, out_h), (in_w, in_h))
+}
+
+/// Whether `m` maps every corner of `from` into `to`, within a pixel of
+/// rounding.
+fn corners_inside(m: &[f64; 9], from: (u32, u32), to: (u32, u32)) -> bool {
+    let (fw, fh) = (from.0 as f64, from.1 as f64);
+    let (tw, th) = (to.0 as f64, to.1 as f64);
+    [(0.0, 0.0), (fw, 0.0), (0.0, fh), (fw, fh)]
+        .iter()
+        .all(|&(x, y)| {
+            let (px, py) = apply_projective(m, x, y);
+            (-1.0..=tw + 1.0).contains(&px) && (-1.0..=th + 1.0).contains(&py)
+        })
+}
+
+const IDENTITY_3X3: [f64; 9] = [1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0];
+
+/// Multiply two 3×3 matrices (row-major): `a · b`.
+fn mul_3x3(a: &[f64; 9], b: &[f64; 9]) -> [f64; 9] {
+    let mut out = [0.0; 9];
+    for r in 0..3 {
+        for c in 0..3 {
+            out[r * 3 + c] = (0..3).map(|k| a[r * 3 + k] * b[k * 3 + c]).sum();
+        }
+    }
+    out
+}
+
+/// Homography mapping the unit square onto `q` (TL, TR, BR, BL).
+///
+/// Heckbert, "Fundamentals of Texture Mapping and Image Warping" (1989), §2.2.3.
+fn square_to_quad(q: &[(f64, f64); 4]) -> Option<[f64; 9]> {
+    let [(x0, y0), (x1, y1), (x2, y2), (x3, y3)] = *q;
+    let (sx, sy) = (x0 - x1 + x2 - x3, y0 - y1 + y2 - y3);
+    let (g, h) = if sx.abs() < 1e-12 && sy.abs() < 1e-12 {
+        (0.0, 0.0)
+    } else {
+        let (dx1, dx2, dy1, dy2) = (x1 - x2, x3 - x2, y1 - y2, y3 - y2);
+        let den = dx1 * dy2 - dx2 * dy1;
+        if den.abs() < 1e-12 {
+            return None;
+        }
+        ((sx * dy2 - dx2 * sy) / den, (dx1 * sy - sx * dy1) / den)
+    };
+    Some([
+        x1 - x0 + g * x1,
+        x3 - x0 + h * x3,
+        x0,
+        y1 - y0 + g * y1,
+        y3 - y0 + h * y3,
+        y0,
+        g,
+        h,
+        1.0,
+    ])
+}
+
+fn cross3(a: &[f64; 3], b: &[f64; 3]) -> [f64; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+fn dot3(a: &[f64; 3], b: &[f64; 3]) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+/// Largest `aspect_w : aspect_h` axis-aligned rectangle inside a convex quad.
+///
+/// A small LP in `(cx, cy, s)`: every rectangle corner must lie inside every
+/// quad edge. The optimum sits on a vertex of the feasible region, so try
+/// each triple of active constraints.
+fn inscribed_in_quad(
+    quad: &[(f64, f64); 4],
+    aspect_w: f64,
+    aspect_h: f64,
+) -> Option<(f64, f64, f64, f64)> {
+    let area2: f64 = (0..4)
+        .map(|i| {
+            let (a, b) = (quad[i], quad[(i + 1) % 4]);
+            a.0 * b.1 - b.0 * a.1
+        })
+        .sum();
+    if area2.abs() < 1e-12 {
+        return None;
+    }
+    let o = area2.signum();
+    let (hw, hh) = (aspect_w / 2.0, aspect_h / 2.0);
+
+    // Constraints `c · (cx, cy, s) >= rhs`.
+    let mut cons = [([0.0; 3], 0.0); 16];
+    for i in 0..4 {
+        let (p, q) = (quad[i], quad[(i + 1) % 4]);
+        let (ex, ey) = (q.0 - p.0, q.1 - p.1);
+        for (j, (a, b)) in [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)]
+            .into_iter()
+            .enumerate()
+        {
+            cons[i * 4 + j] = (
+                [-o * ey, o * ex, o * (ex * b * hh - ey * a * hw)],
+                o * (ex * p.1 - ey * p.0),
+            );
+        }
+    }
+
+    let mut best: Option<[f64; 3]> = None;
+    for i in 0..16 {
+        for j in i + 1..16 {
+            for k in j + 1..16 {
+                let m = [
+                    cons[i].0[0],
+                    cons[i].0[1],
+                    cons[i].0[2], //
+                    cons[j].0[0],
+                    cons[j].0[1],
+                    cons[j].0[2], //
+                    cons[k].0[0],
+                    cons[k].0[1],
+                    cons[k].0[2],
+                ];
+                let Some(inv) = invert_3x3(&m) else { continue };
+                let r = [cons[i].1, cons[j].1, cons[k].1];
+                let v: [f64; 3] =
+                    core::array::from_fn(|row| (0..3).map(|c| inv[row * 3 + c] * r[c]).sum());
+                let tol = 1e-9 * (1.0 + v[0].abs() + v[1].abs());
+                let feasible = cons.iter().all(|(c, rhs)| dot3(c, &v) >= rhs - tol);
+                if feasible && v[2] > 0.0 && best.is_none_or(|b| v[2] > b[2]) {
+                    best = Some(v);
+                }
+            }
+        }
+    }
+    let [cx, cy, s] = best?;
+    Some((cx - s * hw, cy - s * hh, s * aspect_w, s * aspect_h))
+}
+
 /// Euclidean distance between two points.
 fn dist_f64(a: (f64, f64), b: (f64, f64)) -> f64 {
     ((a.0 - b.0).powi(2) + (a.1 - b.1).powi(2)).sqrt()
@@ -894,7 +2521,103 @@ pub fn expanded_canvas_inverse(out_w: u32, out_h: u32, angle_rad: f32) -> (u32,
             }
         }
     }
-    best2.unwrap_or((est_w, est_h))
+    best2.unwrap_or((est_w, est_h))
+}
+
+/// Largest axis-aligned rectangle with aspect `aspect_w : aspect_h` inside
+/// a rotated `w × h` frame.
+///
+/// Returns `(crop_w, crop_h)`. With the input's own aspect this equals
+/// [`inscribed_crop_dims()`]. Uses `floor` rounding.
+pub fn inscribed_aspect_dims(
+    w: u32,
+    h: u32,
+    angle_rad: f32,
+    aspect_w: u32,
+    aspect_h: u32,
+) -> (u32, u32) {
+    if w == 0 || h == 0 || aspect_w == 0 || aspect_h == 0 {
+        return (0, 0);
+    }
+    let (sin, cos) = (angle_rad as f64).sin_cos();
+    let (sin, cos) = (sin.abs(), cos.abs());
+    let (aw, ah) = (aspect_w as f64, aspect_h as f64);
+    // Corner (±s·aw/2, ±s·ah/2) must satisfy |u·p| ≤ w/2 and |v·p| ≤ h/2
+    // for the frame axes u = (cos, sin), v = (−sin, cos).
+    let s = (w as f64 / (aw * cos + ah * sin)).min(h as f64 / (aw * sin + ah * cos));
+    (
+        (s * aw + 1e-9).floor().max(1.0) as u32,
+        (s * ah + 1e-9).floor().max(1.0) as u32,
+    )
+}
+
+/// Inverse of [`inscribed_aspect_dims()`] for an input with aspect
+/// `source_w : source_h`: the smallest such input whose inscribed
+/// rectangle (at aspect `out_w : out_h`) is at least `out_w × out_h`.
+pub fn inscribed_aspect_inverse(
+    out_w: u32,
+    out_h: u32,
+    angle_rad: f32,
+    source_w: u32,
+    source_h: u32,
+) -> (u32, u32) {
+    if out_w == 0 || out_h == 0 || source_w == 0 || source_h == 0 {
+        return (0, 0);
+    }
+    let ratio = source_h as f64 / source_w as f64;
+    let input = |sw: u32| (sw, ((sw as f64 * ratio).round() as u32).max(1));
+    let fits = |sw: u32| {
+        let (iw, ih) = input(sw);
+        let (cw, ch) = inscribed_aspect_dims(iw, ih, angle_rad, out_w, out_h);
+        cw >= out_w && ch >= out_h
+    };
+    // Grow an upper bound, then binary-search the smallest width that fits.
+    let mut hi = out_w.max((out_h as f64 / ratio).ceil() as u32).max(1);
+    while !fits(hi) {
+        if hi > u32::MAX / 2 {
+            return input(hi);
+        }
+        hi *= 2;
+    }
+    let mut lo = 0;
+    while hi - lo > 1 {
+        let mid = lo + (hi - lo) / 2;
+        if fits(mid) { hi = mid } else { lo = mid }
+    }
+    input(hi)
+}
+
+/// Where an `out_w × out_h` inscribed rectangle sits inside a rotated
+/// `w × h` frame: its centre's offset from the frame's centre.
+///
+/// The rectangle can slide wherever every corner stays inside the frame;
+/// the offset is the feasible point closest to `gravity`, placed on the
+/// rotated frame's bounding box.
+fn inscribed_aspect_offset(
+    w: u32,
+    h: u32,
+    angle_rad: f32,
+    out_w: u32,
+    out_h: u32,
+    gravity: Gravity,
+) -> (f64, f64) {
+    let Gravity::Percentage(gx, gy) = gravity else {
+        return (0.0, 0.0);
+    };
+    let (sin, cos) = (angle_rad as f64).sin_cos();
+    let (fw, fh) = (w as f64, h as f64);
+    let (rw, rh) = (out_w as f64, out_h as f64);
+    // Room left along each frame axis.
+    let slack_u = (fw - cos.abs() * rw - sin.abs() * rh).max(0.0) / 2.0;
+    let slack_v = (fh - sin.abs() * rw - cos.abs() * rh).max(0.0) / 2.0;
+    let (bb_w, bb_h) = (
+        fw * cos.abs() + fh * sin.abs(),
+        fw * sin.abs() + fh * cos.abs(),
+    );
+    let target = ((gx as f64 - 0.5) * bb_w, (gy as f64 - 0.5) * bb_h);
+    let a = (cos * target.0 + sin * target.1).clamp(-slack_u, slack_u);
+    let b = (-sin * target.0 + cos * target.1).clamp(-slack_v, slack_v);
+    (a * cos - b * sin, a * sin + b * cos)
 }
 
 #[cfg(test)]
@@ -978,6 +2701,94 @@ mod tests {
         assert!(h > 800, "h={h}");
     }
 
+    #[test]
+    fn inscribed_aspect_own_ratio_matches_inscribed_crop() {
+        for (w, h) in [(1000, 800), (800, 1000), (4000, 300), (640, 640)] {
+            for deg in [0.5f32, 3.0, 12.0, 30.0, 44.0] {
+                let a = inscribed_aspect_dims(w, h, deg.to_radians(), w, h);
+                let b = inscribed_crop_dims(w, h, deg.to_radians());
+                assert!(
+                    a.0.abs_diff(b.0) <= 1 && a.1.abs_diff(b.1) <= 1,
+                    "{w}×{h} @ {deg}°: {a:?} vs {b:?}"
+                );
+            }
+        }
+    }
+
+    /// Output corners of `effect` map inside a `w × h` source.
+    fn corners_inside(effect: &RotateEffect, w: u32, h: u32) -> bool {
+        let (ow, oh) = effect.forward(w, h).unwrap();
+        [
+            (0.0, 0.0),
+            (ow as f32, 0.0),
+            (ow as f32, oh as f32),
+            (0.0, oh as f32),
+        ]
+        .iter()
+        .all(|&(x, y)| {
+            let (sx, sy) = effect.inverse_point(x, y, w, h).unwrap();
+            (-0.01..=w as f32 + 0.01).contains(&sx) && (-0.01..=h as f32 + 0.01).contains(&sy)
+        })
+    }
+
+    #[test]
+    fn inscribed_aspect_custom_ratio() {
+        let effect = RotateEffect::from_degrees(
+            5.0,
+            RotateMode::InscribedAspect {
+                aspect: Some((1, 1)),
+                gravity: Gravity::Center,
+            },
+        );
+        let (w, h) = effect.forward(1200, 800).unwrap();
+        assert_eq!(w, h);
+        assert!(corners_inside(&effect, 1200, 800));
+        // Maximal: the next size up no longer fits.
+        let s = (h + 2) as f64;
+        let (sin, cos) = 5f64.to_radians().sin_cos();
+        assert!(s * (sin + cos) > 800.0);
+        // Only a caller ratio makes the inverse ambiguous.
+        assert_eq!(effect.inverse(w, h), None);
+    }
+
+    #[test]
+    fn inscribed_aspect_gravity_slides() {
+        let mode = |gravity| RotateMode::InscribedAspect {
+            aspect: Some((1, 1)),
+            gravity,
+        };
+        let centre = RotateEffect::from_degrees(4.0, mode(Gravity::Center));
+        let left = RotateEffect::from_degrees(4.0, mode(Gravity::Percentage(0.0, 0.5)));
+        let right = RotateEffect::from_degrees(4.0, mode(Gravity::Percentage(1.0, 0.5)));
+        let (w, h) = centre.forward(1600, 900).unwrap();
+        assert_eq!(left.forward(1600, 900), Some((w, h)));
+
+        let mid = |e: &RotateEffect| {
+            e.inverse_point(w as f32 / 2.0, h as f32 / 2.0, 1600, 900)
+                .unwrap()
+        };
+        assert!(dist(mid(&centre), (800.0, 450.0)) < 0.01);
+        assert!(mid(&left).0 < 700.0 && mid(&right).0 > 900.0);
+        for e in [&left, &right] {
+            assert!(corners_inside(e, 1600, 900));
+            let p = e.forward_point(mid(e).0, mid(e).1, 1600, 900).unwrap();
+            assert!(dist(p, (w as f32 / 2.0, h as f32 / 2.0)) < 0.01);
+        }
+    }
+
+    #[test]
+    fn inscribed_aspect_inverse_smallest_fit() {
+        let angle = 7f32.to_radians();
+        for (ow, oh) in [(500, 500), (1600, 900), (300, 1000)] {
+            let (sw, sh) = inscribed_aspect_inverse(ow, oh, angle, 3, 2);
+            let (cw, ch) = inscribed_aspect_dims(sw, sh, angle, ow, oh);
+            assert!(cw >= ow && ch >= oh, "{ow}×{oh} → {sw}×{sh} → {cw}×{ch}");
+            let smaller = ((sw - 1), ((sw - 1) as f64 * 2.0 / 3.0).round() as u32);
+            let (cw, ch) = inscribed_aspect_dims(smaller.0, smaller.1, angle, ow, oh);
+            assert!(cw < ow || ch < oh);
+        }
+    }
+
     #[test]
     fn pad_effect_percent() {
         let effect = PadEffect::percent(0.1, CanvasColor::Transparent);
@@ -1007,10 +2818,58 @@ mod tests {
         assert!(effect.forward_point(100.0, 100.0, 1000, 800).is_none());
         assert!(effect.inverse_point(100.0, 100.0, 1000, 800).is_none());
         // Estimated dims available via explicit opt-in method.
+        assert_eq!(effect.kind(1000, 800), EffectKind::AnalysisBarrier);
         let (ew, eh) = effect.estimated_dims(1000, 800);
         assert_eq!((ew, eh), (900, 720));
     }
 
+    #[test]
+    fn degenerate_warp_is_invalid_not_barrier() {
+        // The w row crosses zero inside the frame: the warped source
+        // straddles the horizon line.
+        let warp = WarpEffect::new_f64(
+            [1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.002, 0.0, 1.0],
+            ResolutionPolicy::PreserveInput,
+        )
+        .with_coverage(WarpCoverage::Expand {
+            color: CanvasColor::Transparent,
+        });
+        assert!(warp.forward(800, 600).is_none());
+        assert_eq!(warp.kind(800, 600), EffectKind::Invalid);
+        // Away from the horizon the same matrix is fine.
+        assert_eq!(warp.kind(200, 600), EffectKind::Geometric);
+
+        let mut matrix = [1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0];
+        matrix[2] = f64::NAN;
+        let warp = WarpEffect::new_f64(matrix, ResolutionPolicy::PreserveInput);
+        assert_eq!(warp.kind(800, 600), EffectKind::Invalid);
+    }
+
+    #[test]
+    fn seam_carve_dims_known_mapping_unknown() {
+        let effect = SeamCarveEffect::new(600, 500);
+        assert_eq!(effect.forward(1000, 800), Some((600, 500)));
+        assert_eq!(effect.kind(1000, 800), EffectKind::ContentMapped);
+        assert!(effect.forward_point(100.0, 100.0, 1000, 800).is_none());
+        assert!(effect.inverse_point(100.0, 100.0, 1000, 800).is_none());
+        assert!(effect.inverse(600, 500).is_none());
+        assert!(effect.warp_matrix(1000, 800).is_none());
+
+        let wide = SeamCarveEffect::width_only(700);
+        assert_eq!(wide.forward(1000, 800), Some((700, 800)));
+        assert_eq!(wide.scaled(0.5, 0.5).forward(500, 400), Some((350, 400)));
+        assert_eq!(
+            RotateEffect::from_degrees(
+                5.0,
+                RotateMode::Expand {
+                    color: CanvasColor::Transparent
+                }
+            )
+            .kind(1000, 800),
+            EffectKind::Geometric
+        );
+    }
+
     #[test]
     fn expand_effect() {
         let effect = ExpandEffect {
@@ -1668,6 +3527,677 @@ mod tests {
         );
     }
 
+    // ── Fusion ──
+
+    #[test]
+    fn effect_orientation_cardinal_rotations() {
+        let expand = RotateMode::Expand {
+            color: CanvasColor::Transparent,
+        };
+        let cases = [
+            (0.0, RotateMode::InscribedCrop, Some(Orientation::Identity)),
+            (90.0, expand, Some(Orientation::Rotate90)),
+            (
+                180.0,
+                RotateMode::InscribedCrop,
+                Some(Orientation::Rotate180),
+            ),
+            (-90.0, expand, Some(Orientation::Rotate270)),
+            // Keeps w × h, so corners are lost: not an orientation.
+            (90.0, RotateMode::CropToOriginal, None),
+            (10.0, expand, None),
+        ];
+        for (deg, mode, expected) in cases {
+            let effect = RotateEffect::from_degrees(deg, mode);
+            assert_eq!(
+                effect_orientation(&effect, 800, 600),
+                expected,
+                "{deg}° {mode:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn effect_orientation_flip_warp() {
+        let flip = WarpEffect::new_f64(
+            [-1.0, 0.0, 800.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0],
+            ResolutionPolicy::PreserveInput,
+        );
+        assert_eq!(
+            effect_orientation(&flip, 800, 600),
+            Some(Orientation::FlipH)
+        );
+        // Barriers are never orientations.
+        let trim = TrimEffect {
+            estimated_margin_percent: 0.0,
+        };
+        assert_eq!(effect_orientation(&trim, 800, 600), None);
+    }
+
+    #[test]
+    fn rotate_warp_matrix_matches_inverse_point() {
+        let effect = RotateEffect::from_degrees(
+            17.0,
+            RotateMode::Expand {
+                color: CanvasColor::white(),
+            },
+        );
+        let m = effect.warp_matrix(800, 600).unwrap();
+        for (x, y) in [(0.0, 0.0), (123.0, 45.0), (700.0, 900.0)] {
+            let (mx, my) = apply_projective(&m, x as f64, y as f64);
+            let p = effect.inverse_point(x, y, 800, 600).unwrap();
+            assert!(dist((mx as f32, my as f32), p) < 0.01);
+        }
+        assert_eq!(effect.fill_color(), Some(CanvasColor::white()));
+    }
+
+    #[test]
+    fn fused_matches_chain() {
+        let rotate = RotateEffect::from_degrees(8.0, RotateMode::InscribedCrop);
+        let warp = WarpEffect::new_f64(
+            [1.0, 0.1, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0002, 1.0],
+            ResolutionPolicy::MatchArea,
+        )
+        .with_coverage(WarpCoverage::Expand {
+            color: CanvasColor::black(),
+        });
+        let fused = FusedEffect::new(alloc::vec![
+            Box::new(rotate) as Box<dyn DimensionEffect>,
+            Box::new(warp.clone()),
+        ]);
+        let mid = rotate.forward(1000, 800).unwrap();
+        let out = warp.forward(mid.0, mid.1).unwrap();
+        assert_eq!(fused.forward(1000, 800), Some(out));
+        assert_eq!(fused.fill_color(), Some(CanvasColor::black()));
+
+        // The single matrix agrees with chained point mapping.
+        let m = fused.warp_matrix(1000, 800).unwrap();
+        for (x, y) in [(0.0, 0.0), (out.0 as f32, 0.0), (100.0, 250.0)] {
+            let (mx, my) = apply_projective(&m, x as f64, y as f64);
+            let chained = fused.inverse_point(x, y, 1000, 800).unwrap();
+            assert!(dist((mx as f32, my as f32), chained) < 0.05, "{x},{y}");
+            let back = fused
+                .forward_point(chained.0, chained.1, 1000, 800)
+                .unwrap();
+            assert!(dist(back, (x, y)) < 0.05);
+        }
+        let half = fused.scaled(0.5, 0.5).forward(500, 400).unwrap();
+        assert!(half.0.abs_diff(out.0 / 2) <= 2 && half.1.abs_diff(out.1 / 2) <= 2);
+    }
+
+    // ── WarpEffect coverage ──
+
+    /// Rotation by `deg` about the centre of a `w × h` image, as an
+    /// output → source warp matrix.
+    fn rotation_warp(deg: f64, w: f64, h: f64) -> [f64; 9] {
+        let (sin, cos) = deg.to_radians().sin_cos();
+        let (cx, cy) = (w / 2.0, h / 2.0);
+        [
+            cos,
+            -sin,
+            cx - cos * cx + sin * cy,
+            sin,
+            cos,
+            cy - sin * cx - cos * cy,
+            0.0,
+            0.0,
+            1.0,
+        ]
+    }
+
+    #[test]
+    fn warp_coverage_matches_rotate_modes() {
+        let m = rotation_warp(20.0, 1000.0, 600.0);
+        let warp = |coverage| {
+            WarpEffect::new_f64(m, ResolutionPolicy::PreserveInput).with_coverage(coverage)
+        };
+        let close = |a: (u32, u32), b: (u32, u32)| a.0.abs_diff(b.0) <= 1 && a.1.abs_diff(b.1) <= 1;
+
+        let expand = warp(WarpCoverage::Expand {
+            color: CanvasColor::Transparent,
+        })
+        .forward(1000, 600)
+        .unwrap();
+        let expected = expanded_canvas_dims(1000, 600, 20f32.to_radians());
+        assert!(close(expand, expected), "{expand:?} vs {expected:?}");
+
+        let inscribed = warp(WarpCoverage::InscribedCrop)
+            .forward(1000, 600)
+            .unwrap();
+        let expected = inscribed_crop_dims(1000, 600, 20f32.to_radians());
+        assert!(close(inscribed, expected), "{inscribed:?} vs {expected:?}");
+
+        assert_eq!(
+            warp(WarpCoverage::CropToOriginal).forward(1000, 600),
+            Some((1000, 600))
+        );
+    }
+
+    #[test]
+    fn warp_coverage_default_is_crop_to_original() {
+        let warp = WarpEffect::new_f64(
+            rotation_warp(10.0, 800.0, 600.0),
+            ResolutionPolicy::MatchArea,
+        );
+        assert_eq!(warp.coverage, WarpCoverage::CropToOriginal);
+        assert_eq!(
+            warp.coverage_window(800, 600),
+            Some((0.0, 0.0, 800.0, 600.0))
+        );
+    }
+
+    #[test]
+    fn warp_expand_contains_all_source_corners() {
+        let m = compute_test_homography(
+            &[(0.0, 0.0), (1000.0, 0.0), (1000.0, 800.0), (0.0, 800.0)],
+            &[(80.0, 40.0), (950.0, 0.0), (1000.0, 800.0), (0.0, 760.0)],
+        )
+        .unwrap();
+        let warp = WarpEffect::new_f64(m, ResolutionPolicy::MatchArea).with_coverage(
+            WarpCoverage::Expand {
+                color: CanvasColor::white(),
+            },
+        );
+        let (ow, oh) = warp.forward(1000, 800).unwrap();
+        let corners = [(0.0, 0.0), (1000.0, 0.0), (1000.0, 800.0), (0.0, 800.0)];
+        let mapped = corners.map(|(x, y)| warp.forward_point(x, y, 1000, 800).unwrap());
+        for p in mapped {
+            assert!(p.0 > -0.01 && p.0 < ow as f32 + 0.01, "{p:?} in {ow}×{oh}");
+            assert!(p.1 > -0.01 && p.1 < oh as f32 + 0.01, "{p:?} in {ow}×{oh}");
+        }
+        // The bounding box is tight: some corner touches each edge.
+        assert!(mapped.iter().any(|p| p.0.abs() < 0.01));
+        assert!(mapped.iter().any(|p| p.1.abs() < 0.01));
+        for (c, p) in corners.iter().zip(mapped.iter()) {
+            let back = warp.inverse_point(p.0, p.1, 1000, 800).unwrap();
+            assert!(dist(back, *c) < 0.05, "{c:?} → {p:?} → {back:?}");
+        }
+    }
+
+    #[test]
+    fn warp_inscribed_stays_inside_source() {
+        let m = compute_test_homography(
+            &[(0.0, 0.0), (1000.0, 0.0), (1000.0, 800.0), (0.0, 800.0)],
+            &[
+                (-60.0, 30.0),
+                (1040.0, -20.0),
+                (980.0, 850.0),
+                (30.0, 790.0),
+            ],
+        )
+        .unwrap();
+        let warp = WarpEffect::new_f64(m, ResolutionPolicy::PreserveInput)
+            .with_coverage(WarpCoverage::InscribedCrop);
+        let (ow, oh) = warp.forward(1000, 800).unwrap();
+        assert!(ow < 1000 && oh < 800, "{ow}×{oh}");
+        assert!((ow as f64 / oh as f64 - 1.25).abs() < 0.01);
+        let mut touching = 0;
+        for (x, y) in [
+            (0.0, 0.0),
+            (ow as f32, 0.0),
+            (ow as f32, oh as f32),
+            (0.0, oh as f32),
+        ] {
+            let (sx, sy) = warp.inverse_point(x, y, 1000, 800).unwrap();
+            assert!(
+                sx > -0.5 && sx < 1000.5 && sy > -0.5 && sy < 800.5,
+                "({sx}, {sy})"
+            );
+            if !(0.5..=999.5).contains(&sx) || !(0.5..=799.5).contains(&sy) {
+                touching += 1;
+            }
+            let back = warp.forward_point(sx, sy, 1000, 800).unwrap();
+            assert!(dist(back, (x, y)) < 0.05);
+        }
+        assert!(touching >= 1, "inscribed rect should touch the source edge");
+    }
+
+    #[test]
+    fn warp_coverage_inverse_roundtrip() {
+        let m = rotation_warp(15.0, 1200.0, 900.0);
+        for coverage in [
+            WarpCoverage::InscribedCrop,
+            WarpCoverage::Expand {
+                color: CanvasColor::Transparent,
+            },
+        ] {
+            let warp =
+                WarpEffect::new_f64(m, ResolutionPolicy::PreserveInput).with_coverage(coverage);
+            let out = warp.forward(1200, 900).unwrap();
+            let (iw, ih) = warp.inverse(out.0, out.1).unwrap();
+            let again = warp.forward(iw, ih).unwrap();
+            assert!(
+                again.0.abs_diff(out.0) <= 2 && again.1.abs_diff(out.1) <= 2,
+                "{coverage:?}: {out:?} → {:?} → {again:?}",
+                (iw, ih)
+            );
+        }
+    }
+
+    #[test]
+    fn warp_coverage_survives_scaling() {
+        let warp = WarpEffect::new_f64(
+            rotation_warp(12.0, 1000.0, 800.0),
+            ResolutionPolicy::PreserveInput,
+        )
+        .with_coverage(WarpCoverage::InscribedCrop);
+        let full = warp.forward(1000, 800).unwrap();
+        let half = warp.scaled(0.5, 0.5).forward(500, 400).unwrap();
+        assert!(half.0.abs_diff(full.0 / 2) <= 1 && half.1.abs_diff(full.1 / 2) <= 1);
+    }
+
+    #[test]
+    fn warp_coverage_horizon_rejected() {
+        // Strong perspective: part of the source maps past the horizon.
+        let m = [1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.002, 1.0];
+        let warp = WarpEffect::new_f64(m, ResolutionPolicy::PreserveInput).with_coverage(
+            WarpCoverage::Expand {
+                color: CanvasColor::Transparent,
+            },
+        );
+        assert_eq!(warp.forward(1000, 800), None);
+    }
+
+    // ── Lens distortion ──
+
+    fn barrel() -> LensEffect {
+        LensEffect::new(LensModel::radial(-0.25, 0.05, 0.0), 900.0, (500.0, 400.0))
+    }
+
+    #[test]
+    fn lens_zero_coefficients_is_identity() {
+        let lens = LensEffect::new(LensModel::radial(0.0, 0.0, 0.0), 800.0, (500.0, 400.0));
+        assert_eq!(lens.forward(1000, 800), Some((1000, 800)));
+        let p = lens.forward_point(123.0, 456.0, 1000, 800).unwrap();
+        assert!(dist(p, (123.0, 456.0)) < 1e-3);
+    }
+
+    #[test]
+    fn lens_point_roundtrip_exact() {
+        let models = [
+            LensModel::BrownConrady {
+                k1: -0.3,
+                k2: 0.1,
+                k3: -0.01,
+                p1: 0.002,
+                p2: -0.001,
+            },
+            LensModel::radial(0.15, 0.02, 0.0),
+            LensModel::Fisheye {
+                k1: -0.05,
+                k2: 0.01,
+                k3: 0.0,
+                k4: 0.0,
+            },
+        ];
+        for model in models {
+            let lens = LensEffect::new(model, 700.0, (510.0, 390.0));
+            for (x, y) in [(0.0, 0.0), (1000.0, 0.0), (250.0, 600.0), (510.0, 390.0)] {
+                let out = lens.forward_point(x, y, 1000, 800).unwrap();
+                let back = lens.inverse_point(out.0, out.1, 1000, 800).unwrap();
+                assert!(dist(back, (x, y)) < 1e-3, "{model:?} ({x}, {y}) → {back:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn lens_default_barrel_shrinks_pincushion_grows() {
+        // The input's own frame at the source's average density.
+        let (w, h) = barrel().forward(1000, 800).unwrap();
+        assert!(w < 1000 && h < 800, "{w}×{h}");
+        let pincushion = LensEffect::new(LensModel::radial(0.2, 0.0, 0.0), 900.0, (500.0, 400.0));
+        let (w, h) = pincushion.forward(1000, 800).unwrap();
+        assert!(w > 1000 && h > 800, "{w}×{h}");
+    }
+
+    #[test]
+    fn lens_barrel_expand_window_grows() {
+        let lens = barrel()
+            .with_policy(ResolutionPolicy::PreserveInput)
+            .with_coverage(WarpCoverage::Expand {
+                color: CanvasColor::black(),
+            });
+        let (w, h) = lens.forward(1000, 800).unwrap();
+        assert!(w > 1000 && h > 800, "{w}×{h}");
+        // Every source corner lands on the canvas, and the box is tight.
+        let corners = [(0.0, 0.0), (1000.0, 0.0), (1000.0, 800.0), (0.0, 800.0)];
+        let mapped = corners.map(|(x, y)| lens.forward_point(x, y, 1000, 800).unwrap());
+        for (x, y) in mapped {
+            assert!((-0.5..=w as f32 + 0.5).contains(&x) && (-0.5..=h as f32 + 0.5).contains(&y));
+        }
+        assert!(mapped.iter().any(|p| p.0.abs() < 0.5));
+        assert_eq!(lens.fill_color(), Some(CanvasColor::black()));
+    }
+
+    #[test]
+    fn lens_pincushion_inscribed_window_shrinks() {
+        let lens = LensEffect::new(LensModel::radial(0.2, 0.0, 0.0), 900.0, (500.0, 400.0))
+            .with_policy(ResolutionPolicy::PreserveInput)
+            .with_coverage(WarpCoverage::InscribedCrop);
+        let (w, h) = lens.forward(1000, 800).unwrap();
+        assert!(w < 1000 && h < 800, "{w}×{h}");
+        assert!((w as f64 / h as f64 - 1.25).abs() < 0.01);
+        for (x, y) in [
+            (0.0, 0.0),
+            (w as f32, 0.0),
+            (w as f32, h as f32),
+            (0.0, h as f32),
+        ] {
+            let (sx, sy) = lens.inverse_point(x, y, 1000, 800).unwrap();
+            assert!((-0.5..=1000.5).contains(&sx) && (-0.5..=800.5).contains(&sy));
+        }
+    }
+
+    #[test]
+    fn invalid_lens_is_invalid_not_barrier() {
+        let mut lens = barrel();
+        lens.fx = 0.0;
+        assert!(!lens.is_valid());
+        assert_eq!(lens.forward(1000, 800), None);
+        assert_eq!(lens.kind(1000, 800), EffectKind::Invalid);
+        lens.fx = f64::NAN;
+        assert_eq!(lens.kind(1000, 800), EffectKind::Invalid);
+
+        // A fisheye whose border folds back cannot be undistorted in full.
+        let fisheye = LensEffect::new(
+            LensModel::Fisheye {
+                k1: -0.5,
+                k2: 0.0,
+                k3: 0.0,
+                k4: 0.0,
+            },
+            300.0,
+            (500.0, 400.0),
+        )
+        .with_coverage(WarpCoverage::Expand {
+            color: CanvasColor::black(),
+        });
+        assert!(fisheye.is_valid());
+        assert_eq!(fisheye.forward(1000, 800), None);
+        assert_eq!(fisheye.kind(1000, 800), EffectKind::Invalid);
+        assert_eq!(barrel().kind(1000, 800), EffectKind::Geometric);
+    }
+
+    #[test]
+    fn lens_policies_order_by_size() {
+        let dims = |policy| barrel().with_policy(policy).forward(1000, 800).unwrap();
+        let narrow = dims(ResolutionPolicy::MatchNarrow);
+        let area = dims(ResolutionPolicy::MatchArea);
+        let wide = dims(ResolutionPolicy::MatchWide);
+        assert!(
+            narrow.0 <= area.0 && area.0 <= wide.0,
+            "{narrow:?} {area:?} {wide:?}"
+        );
+        assert!(narrow.0 < wide.0);
+        assert_eq!(dims(ResolutionPolicy::PreserveInput), (1000, 800));
+        assert_eq!(dims(ResolutionPolicy::Custom(500, 400)), (500, 400));
+    }
+
+    #[test]
+    fn lens_inverse_and_scaling() {
+        let lens = barrel().with_coverage(WarpCoverage::Expand {
+            color: CanvasColor::Transparent,
+        });
+        let out = lens.forward(1000, 800).unwrap();
+        let (iw, ih) = lens.inverse(out.0, out.1).unwrap();
+        assert!(iw.abs_diff(1000) <= 2 && ih.abs_diff(800) <= 2, "{iw}×{ih}");
+
+        let half = lens.scaled(0.5, 0.5).forward(500, 400).unwrap();
+        assert!(half.0.abs_diff(out.0 / 2) <= 1 && half.1.abs_diff(out.1 / 2) <= 1);
+        // Lens effects are not matrix-expressible, so they never fuse.
+        assert!(lens.warp_matrix(1000, 800).is_none());
+    }
+
+    // ── Equirectangular viewport ──
+
+    #[test]
+    fn equirect_view_centre_follows_yaw_and_pitch() {
+        let cases = [
+            (0.0, 0.0, (2048.0, 1024.0)),
+            (90.0, 0.0, (3072.0, 1024.0)),
+            (-90.0, 30.0, (1024.0, 1024.0 - 2048.0 / 6.0)),
+        ];
+        for (yaw, pitch, expected) in cases {
+            let view = EquirectViewEffect::from_degrees(yaw, pitch, 15.0, 90.0, 16.0 / 9.0);
+            let (w, h) = view.forward(4096, 2048).unwrap();
+            let p = view
+                .inverse_point(w as f32 / 2.0, h as f32 / 2.0, 4096, 2048)
+                .unwrap();
+            assert!(dist(p, expected) < 0.01, "yaw {yaw} pitch {pitch}: {p:?}");
+        }
+    }
+
+    #[test]
+    fn equirect_view_point_roundtrip() {
+        let view = EquirectViewEffect::from_degrees(170.0, -20.0, 10.0, 100.0, 4.0 / 3.0);
+        let (w, h) = view.forward(4096, 2048).unwrap();
+        for (x, y) in [(0.0, 0.0), (w as f32, h as f32), (100.0, 700.0)] {
+            let src = view.inverse_point(x, y, 4096, 2048).unwrap();
+            assert!((0.0..=4096.0).contains(&src.0), "{src:?}");
+            let back = view.forward_point(src.0, src.1, 4096, 2048).unwrap();
+            assert!(dist(back, (x, y)) < 0.05, "({x}, {y}) → {src:?} → {back:?}");
+        }
+        // The view looks at yaw 170°; the opposite side is behind the camera.
+        assert_eq!(view.forward_point(1100.0, 1024.0, 4096, 2048), None);
+    }
+
+    #[test]
+    fn equirect_view_policies() {
+        let dims = |policy| {
+            EquirectViewEffect::from_degrees(0.0, 0.0, 0.0, 90.0, 16.0 / 9.0)
+                .with_policy(policy)
+                .forward(4096, 2048)
+                .unwrap()
+        };
+        // 4096 px over 360° ≈ 651.9 px/rad; tan(45°) = 1.
+        assert_eq!(dims(ResolutionPolicy::MatchWide), (1304, 733));
+        let area = dims(ResolutionPolicy::MatchArea);
+        let narrow = dims(ResolutionPolicy::MatchNarrow);
+        assert_eq!(area.0, (1303.8f64 * 0.5f64.sqrt()).round() as u32);
+        assert_eq!(narrow.0, 652);
+        assert_eq!(dims(ResolutionPolicy::PreserveInput), (4096, 2304));
+        assert_eq!(dims(ResolutionPolicy::Custom(640, 480)), (640, 480));
+    }
+
+    #[test]
+    fn equirect_view_inverse_and_validation() {
+        let view = EquirectViewEffect::from_degrees(0.0, 0.0, 0.0, 75.0, 1.5);
+        let out = view.forward(8000, 4000).unwrap();
+        let (iw, ih) = view.inverse(out.0, out.1).unwrap();
+        assert!(
+            iw.abs_diff(8000) <= 8 && ih.abs_diff(4000) <= 4,
+            "{iw}×{ih}"
+        );
+        assert_eq!(view.forward(0, 4000), None);
+        let too_wide = EquirectViewEffect::from_degrees(0.0, 0.0, 0.0, 180.0, 1.5);
+        assert_eq!(too_wide.forward(8000, 4000), None);
+        assert_eq!(too_wide.kind(8000, 4000), EffectKind::Invalid);
+        let nan = EquirectViewEffect::from_degrees(f64::NAN, 0.0, 0.0, 90.0, 1.5);
+        assert!(!nan.is_valid());
+        assert_eq!(nan.kind(8000, 4000), EffectKind::Invalid);
+        assert_eq!(view.kind(8000, 4000), EffectKind::Geometric);
+    }
+
+    // ── Rotated crop ──
+
+    #[test]
+    fn rotated_crop_dims_independent_of_input() {
+        let c = RotatedCropEffect::from_degrees((400.0, 300.0), 250.4, 120.6, 17.0);
+        assert_eq!(c.forward(800, 600), Some((250, 121)));
+        assert_eq!(c.forward(4000, 3000), Some((250, 121)));
+        assert_eq!(c.inverse(250, 121), None);
+    }
+
+    #[test]
+    fn rotated_crop_point_roundtrip_and_corners() {
+        let c = RotatedCropEffect::from_degrees((400.0, 300.0), 200.0, 100.0, 30.0);
+        for &(x, y) in &[(0.0, 0.0), (200.0, 0.0), (37.5, 81.0), (100.0, 50.0)] {
+            let (sx, sy) = c.inverse_point(x, y, 800, 600).unwrap();
+            let (bx, by) = c.forward_point(sx, sy, 800, 600).unwrap();
+            assert!(dist((x, y), (bx, by)) < 1e-3, "({x},{y}) -> ({bx},{by})");
+        }
+        // The output centre is the rectangle centre; the top-right corner
+        // sits half a width along the rotated x axis.
+        let centre = c.inverse_point(100.0, 50.0, 800, 600).unwrap();
+        assert!(dist(centre, (400.0, 300.0)) < 1e-3);
+        let (tl, tr) = (
+            c.inverse_point(0.0, 0.0, 800, 600).unwrap(),
+            c.inverse_point(200.0, 0.0, 800, 600).unwrap(),
+        );
+        let a = 30f32.to_radians();
+        assert!(
+            dist(
+                (tr.0 - tl.0, tr.1 - tl.1),
+                (200.0 * a.cos(), 200.0 * a.sin())
+            ) < 1e-3
+        );
+    }
+
+    #[test]
+    fn rotated_crop_warp_matrix_fills() {
+        let c = RotatedCropEffect::from_degrees((10.0, 10.0), 40.0, 40.0, 45.0)
+            .with_color(CanvasColor::white());
+        assert_eq!(c.fill_color(), Some(CanvasColor::white()));
+        let m = c.warp_matrix(100, 100).unwrap();
+        assert_eq!(&m[6..], &[0.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn rotated_crop_cardinal_matches_mapping() {
+        let (w, h) = (800, 600);
+        for k in 0..4 {
+            let c = RotatedCropEffect::from_degrees((300.0, 250.0), 160.0, 90.0, 90.0 * k as f32);
+            let (o, region) = c.cardinal(w, h).unwrap();
+            let oriented = o.transform_dimensions(w, h);
+            let (left, top) = (region.left.pixels as f32, region.top.pixels as f32);
+            assert_eq!(region.right.pixels - region.left.pixels, 160);
+            assert_eq!(region.bottom.pixels - region.top.pixels, 90);
+            for &(x, y) in &[(0.0, 0.0), (160.0, 0.0), (40.0, 70.0), (160.0, 90.0)] {
+                let via_effect = c.inverse_point(x, y, w, h).unwrap();
+                let via_region = o.inverse().transform_point_to_display(
+                    left + x,
+                    top + y,
+                    oriented.width,
+                    oriented.height,
+                );
+                assert!(
+                    dist(via_effect, via_region) < 1e-2,
+                    "k={k} ({x},{y}): {via_effect:?} vs {via_region:?}"
+                );
+            }
+        }
+        let tilted = RotatedCropEffect::from_degrees((300.0, 250.0), 160.0, 90.0, 1.0);
+        assert!(tilted.cardinal(w, h).is_none());
+    }
+
+    #[test]
+    fn rotated_crop_scaled_uniform() {
+        let c = RotatedCropEffect::from_degrees((400.0, 300.0), 200.0, 100.0, 30.0);
+        let s = c.scaled(0.5, 0.5);
+        assert_eq!(s.forward(400, 300), Some((100, 50)));
+        let p = s.inverse_point(50.0, 25.0, 400, 300).unwrap();
+        assert!(dist(p, (200.0, 150.0)) < 1e-3);
+    }
+
+    // ── Quad-to-rectangle perspective correction ──
+
+    /// Project a `w × h` rectangle tilted about x then y, seen by a pinhole
+    /// camera with focal `f` centred on a `img_w × img_h` image.
+    fn project_rect(w: f64, h: f64, tilt_x_deg: f64, tilt_y_deg: f64, f: f64) -> [(f32, f32); 4] {
+        let (ax, ay) = (tilt_x_deg.to_radians(), tilt_y_deg.to_radians());
+        let corners = [
+            (-w / 2.0, -h / 2.0),
+            (w / 2.0, -h / 2.0),
+            (w / 2.0, h / 2.0),
+            (-w / 2.0, h / 2.0),
+        ];
+        corners.map(|(x, y)| {
+            // Rotate about x, then y, then push 1000 units away.
+            let (y1, z1) = (y * ax.cos(), y * ax.sin());
+            let (x2, z2) = (x * ay.cos() + z1 * ay.sin(), -x * ay.sin() + z1 * ay.cos());
+            let z = z2 + 1000.0;
+            ((f * x2 / z + 500.0) as f32, (f * y1 / z + 400.0) as f32)
+        })
+    }
+
+    #[test]
+    fn quad_full_frame_is_identity() {
+        let quad = [(0.0, 0.0), (1000.0, 0.0), (1000.0, 800.0), (0.0, 800.0)];
+        let effect =
+            WarpEffect::from_quad(1000, 800, quad, None, ResolutionPolicy::MatchArea).unwrap();
+        assert_eq!(effect.forward(1000, 800), Some((1000, 800)));
+        for (i, v) in effect.matrix.iter().enumerate() {
+            let expected = if i % 4 == 0 { 1.0 } else { 0.0 };
+            assert!((v - expected).abs() < 1e-9, "m[{i}] = {v}");
+        }
+        let aspect = quad_aspect_ratio(quad, 1000, 800, None).unwrap();
+        assert!((aspect - 1.25).abs() < 1e-9);
+    }
+
+    #[test]
+    fn quad_aspect_recovers_true_ratio() {
+        let quad = project_rect(450.0, 300.0, 35.0, 20.0, 800.0);
+        let estimated = quad_aspect_ratio(quad, 1000, 800, None).unwrap();
+        assert!((estimated - 1.5).abs() < 0.01, "estimated {estimated}");
+        let hinted = quad_aspect_ratio(quad, 1000, 800, Some(800.0)).unwrap();
+        assert!((hinted - 1.5).abs() < 0.01, "hinted {hinted}");
+    }
+
+    #[test]
+    fn quad_corners_map_to_output_corners() {
+        let quad = project_rect(450.0, 300.0, 30.0, -15.0, 900.0);
+        let effect =
+            WarpEffect::from_quad(1000, 800, quad, Some(900.0), ResolutionPolicy::MatchArea)
+                .unwrap();
+        let (ow, oh) = effect.forward(1000, 800).unwrap();
+        assert!(((ow as f64 / oh as f64) - 1.5).abs() < 0.02, "{ow}×{oh}");
+        let corners = [
+            (0.0, 0.0),
+            (ow as f32, 0.0),
+            (ow as f32, oh as f32),
+            (0.0, oh as f32),
+        ];
+        for (c, q) in corners.iter().zip(quad.iter()) {
+            let p = effect.inverse_point(c.0, c.1, 1000, 800).unwrap();
+            assert!(dist(p, *q) < 0.01, "{c:?} → {p:?}, expected {q:?}");
+            let back = effect.forward_point(q.0, q.1, 1000, 800).unwrap();
+            assert!(dist(back, *c) < 0.01, "{q:?} → {back:?}, expected {c:?}");
+        }
+    }
+
+    #[test]
+    fn quad_policies_order_by_size() {
+        let quad = project_rect(450.0, 300.0, 40.0, 0.0, 800.0);
+        let dims = |policy| {
+            WarpEffect::from_quad(1000, 800, quad, None, policy)
+                .unwrap()
+                .forward(1000, 800)
+                .unwrap()
+        };
+        let narrow = dims(ResolutionPolicy::MatchNarrow);
+        let area = dims(ResolutionPolicy::MatchArea);
+        let wide = dims(ResolutionPolicy::MatchWide);
+        assert!(
+            narrow.0 < area.0 && area.0 < wide.0,
+            "{narrow:?} {area:?} {wide:?}"
+        );
+        assert_eq!(dims(ResolutionPolicy::PreserveInput), (1000, 800));
+        assert_eq!(dims(ResolutionPolicy::Custom(600, 400)), (600, 400));
+    }
+
+    #[test]
+    fn quad_degenerate_rejected() {
+        let collinear = [(0.0, 0.0), (100.0, 0.0), (200.0, 0.0), (300.0, 0.0)];
+        assert!(
+            WarpEffect::from_quad(1000, 800, collinear, None, ResolutionPolicy::MatchArea)
+                .is_none()
+        );
+        let nan = [(f32::NAN, 0.0), (100.0, 0.0), (100.0, 100.0), (0.0, 100.0)];
+        assert!(WarpEffect::from_quad(1000, 800, nan, None, ResolutionPolicy::MatchArea).is_none());
+        let ok = [(0.0, 0.0), (100.0, 0.0), (100.0, 100.0), (0.0, 100.0)];
+        assert!(WarpEffect::from_quad(0, 800, ok, None, ResolutionPolicy::MatchArea).is_none());
+    }
+
     /// Helper: compute 3×3 homography from 4 point correspondences (DLT).
     /// Mirrors zenfilters' compute_homography but in f64 for test precision.
     fn compute_test_homography(src: &[(f64, f64); 4], dst: &[(f64, f64); 4]) -> Option<[f64; 9]> {
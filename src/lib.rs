@@ -7,8 +7,11 @@
 //! - [`constraint`] — Constraint modes (Fit, Within, FitCrop, etc.) and layout computation
 //! - [`orientation`] — EXIF orientation, D4 dihedral group, coordinate transforms
 //! - [`plan`] — Command pipeline, decoder negotiation, two-phase layout planning
+//! - [`pdf`] — PDF page box, `/Rotate`, and `UserUnit` render planning
 //! - [`svg`] — SVG visualization of layout pipeline steps (requires `svg` feature)
+//! - [`saliency`] — Built-in saliency heatmaps for smart cropping (requires `saliency` feature)
 //! - [`riapi`] — RIAPI query string parsing (`?w=800&h=600&mode=crop`) (requires `riapi` feature)
+//! - [`xmp`] — Lightroom / Camera Raw XMP crop settings import
 
 #![cfg_attr(not(feature = "std"), no_std)]
 #![forbid(unsafe_code)]
@@ -22,13 +25,17 @@ mod float_math;
 pub mod constraint;
 pub mod dimension;
 pub mod orientation;
+pub mod pdf;
 pub mod plan;
 #[cfg(feature = "riapi")]
 pub mod riapi;
+#[cfg(feature = "saliency")]
+pub mod saliency;
 #[cfg(feature = "smart-crop")]
 pub mod smart_crop;
 #[cfg(feature = "svg")]
 pub mod svg;
+pub mod xmp;
 // #[cfg(feature = "zennode")]
 // pub mod zennode_defs;
 
@@ -37,14 +44,16 @@ pub use constraint::{
     CanvasColor, Constraint, ConstraintMode, Gravity, Layout, LayoutError, Rect, Size, SourceCrop,
 };
 pub use dimension::{
-    DimensionEffect, ExpandEffect, PadEffect, ResolutionPolicy, RotateEffect, RotateMode,
-    TrimEffect, WarpEffect, expanded_canvas_dims, expanded_canvas_inverse, inscribed_crop_dims,
-    inscribed_crop_inverse, warp_output_dims,
+    BarrierResolution, DimensionEffect, EffectKind, EquirectViewEffect, ExpandEffect, FusedEffect,
+    LensEffect, LensModel, PadEffect, ResolutionPolicy, ResolvedBarrier, RotateEffect, RotateMode,
+    RotatedCropEffect, SeamCarveEffect, TrimEffect, WarpCoverage, WarpEffect, expanded_canvas_dims,
+    expanded_canvas_inverse, inscribed_aspect_dims, inscribed_aspect_inverse, inscribed_crop_dims,
+    inscribed_crop_inverse, quad_aspect_ratio, warp_output_dims,
 };
 pub use orientation::Orientation;
 pub use plan::{
     Align, CodecLayout, Command, DecoderOffer, DecoderRequest, FlipAxis, IdealLayout, LayoutPlan,
     OutputLimits, Padding, Pipeline, PlaneLayout, Region, RegionCoord, ResolvedEffect, Rotation,
-    Subsampling, compute_layout, compute_layout_sequential,
+    SourceVariant, Subsampling, VariantChoice, Viewport, compute_layout, compute_layout_sequential,
 };
 pub use whereat::{At, ResultAtExt};
@@ -198,6 +198,29 @@ impl Orientation {
             Self::Transverse => Rect::new(sw - ry - rh, sh - rx - rw, rh, rw),
         }
     }
+
+    /// Transform a point from source coordinates to display coordinates.
+    ///
+    /// Coordinates are continuous (edge-based): a `w × h` source spans
+    /// `[0, w] × [0, h]`, so corners map to corners exactly.
+    pub fn transform_point_to_display(
+        self,
+        x: f32,
+        y: f32,
+        source_w: u32,
+        source_h: u32,
+    ) -> (f32, f32) {
+        let (sw, sh) = (source_w as f32, source_h as f32);
+        let (rotation, flip) = self.decompose();
+        // Rotate clockwise by `rotation` quarters, then optionally flip.
+        let (rx, ry, rw) = match rotation {
+            0 => (x, y, sw),
+            1 => (sh - y, x, sh),
+            2 => (sw - x, sh - y, sw),
+            _ => (y, sw - x, sh),
+        };
+        if flip { (rw - rx, ry) } else { (rx, ry) }
+    }
 }
 
 #[cfg(test)]
@@ -522,6 +545,22 @@ mod tests {
         }
     }
 
+    #[test]
+    fn transform_point_matches_pixel_centers() {
+        // Continuous mapping of a pixel center must land on the center of
+        // the pixel that forward_map_point reports.
+        let (sw, sh) = (4u32, 3u32);
+        for &o in &ALL {
+            for y in 0..sh {
+                for x in 0..sw {
+                    let (dx, dy) = forward_map_point(o, x, y, sw, sh);
+                    let p = o.transform_point_to_display(x as f32 + 0.5, y as f32 + 0.5, sw, sh);
+                    assert_eq!(p, (dx as f32 + 0.5, dy as f32 + 0.5), "{o:?} ({x},{y})");
+                }
+            }
+        }
+    }
+
     /// Forward-map a source pixel to display coordinates.
     /// Verified against zenjpeg coeff_transform.rs:89-97.
     fn forward_map_point(o: Orientation, x: u32, y: u32, w: u32, h: u32) -> (u32, u32) {
@@ -0,0 +1,538 @@
+//! PDF page box and `/Rotate` geometry planning.
+//!
+//! A PDF page carries up to five boxes in default user space (points,
+//! 1/72 inch, y-up): MediaBox, CropBox, BleedBox, TrimBox, ArtBox. Together
+//! with `/Rotate` and `UserUnit` they determine what a viewer shows. This
+//! module resolves the effective box, sizes the raster, and produces the
+//! same [`IdealLayout`] + [`DecoderRequest`] pair as [`Pipeline::plan()`],
+//! with the PDF renderer in the decoder role.
+//!
+//! ```text
+//!     MediaBox ──► effective box ──► raster (unrotated) ──► /Rotate ──► constraint
+//!                  (clipped per       source_size            Orientation   canvas
+//!                   PDF 32000 §14.11.2)
+//! ```
+//!
+//! # Example
+//!
+//! ```
+//! use zenlayout::pdf::{PageBox, PdfPage, PdfRect, PdfSizing};
+//! use zenlayout::{DecoderOffer, Size};
+//!
+//! // US Letter, displayed landscape via /Rotate 90, rendered at 150 DPI.
+//! let page = PdfPage::new(PdfRect::new(0.0, 0.0, 612.0, 792.0)).with_rotate(90);
+//! let plan = page.plan(PageBox::Crop, PdfSizing::Dpi(150.0)).unwrap();
+//!
+//! assert_eq!(plan.source_size, Size::new(1275, 1650));
+//! assert_eq!(plan.ideal.layout.canvas, Size::new(1650, 1275));
+//!
+//! let offer = DecoderOffer::full_decode(1275, 1650);
+//! let final_plan = plan.ideal.finalize(&plan.request, &offer);
+//! assert!(final_plan.resize_is_identity);
+//! ```
+
+use crate::constraint::{Constraint, LayoutError, Rect, Size};
+#[allow(unused_imports)]
+use crate::float_math::Float;
+use crate::orientation::Orientation;
+use crate::plan::{DecoderRequest, IdealLayout, Pipeline};
+use whereat::{At, at};
+
+/// Points per inch in PDF default user space.
+const POINTS_PER_INCH: f64 = 72.0;
+
+/// A rectangle in PDF default user space (points, y-up).
+///
+/// Corners may be given in any order; accessors normalize them.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct PdfRect {
+    /// Lower-left x.
+    pub llx: f32,
+    /// Lower-left y.
+    pub lly: f32,
+    /// Upper-right x.
+    pub urx: f32,
+    /// Upper-right y.
+    pub ury: f32,
+}
+
+impl PdfRect {
+    /// Create from PDF array order `[llx lly urx ury]`.
+    pub const fn new(llx: f32, lly: f32, urx: f32, ury: f32) -> Self {
+        Self { llx, lly, urx, ury }
+    }
+
+    /// Width in points.
+    pub fn width(&self) -> f32 {
+        (self.urx - self.llx).abs()
+    }
+
+    /// Height in points.
+    pub fn height(&self) -> f32 {
+        (self.ury - self.lly).abs()
+    }
+
+    /// Same rectangle with `llx ≤ urx` and `lly ≤ ury`.
+    pub fn normalized(&self) -> Self {
+        Self {
+            llx: self.llx.min(self.urx),
+            lly: self.lly.min(self.ury),
+            urx: self.llx.max(self.urx),
+            ury: self.lly.max(self.ury),
+        }
+    }
+
+    /// Intersection of two rectangles, or `None` if they don't overlap.
+    pub fn intersect(&self, other: &Self) -> Option<Self> {
+        let (a, b) = (self.normalized(), other.normalized());
+        let r = Self {
+            llx: a.llx.max(b.llx),
+            lly: a.lly.max(b.lly),
+            urx: a.urx.min(b.urx),
+            ury: a.ury.min(b.ury),
+        };
+        (r.llx < r.urx && r.lly < r.ury).then_some(r)
+    }
+
+    fn is_finite(&self) -> bool {
+        self.llx.is_finite() && self.lly.is_finite() && self.urx.is_finite() && self.ury.is_finite()
+    }
+}
+
+/// Which page boundary to render.
+#[non_exhaustive]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum PageBox {
+    /// Full physical medium.
+    Media,
+    /// Visible region in viewers. The usual choice.
+    #[default]
+    Crop,
+    /// Production region including bleed.
+    Bleed,
+    /// Finished page after trimming.
+    Trim,
+    /// Meaningful content as intended by the creator.
+    Art,
+}
+
+/// How to size the rendered page.
+#[non_exhaustive]
+#[derive(Clone, Debug, PartialEq)]
+pub enum PdfSizing {
+    /// Render at a fixed resolution. The canvas is the rotated box at this DPI.
+    Dpi(f32),
+    /// Fit the displayed (rotated) page into a constraint.
+    ///
+    /// The page's natural size is one pixel per point (72 DPI, scaled by
+    /// `UserUnit`), so never-upscale modes such as [`Within`](crate::ConstraintMode::Within)
+    /// cap output at 72 DPI. Combine with a constraint in a fit mode to
+    /// render directly at the target size.
+    Constrain(Constraint),
+}
+
+/// Page geometry from a PDF page dictionary.
+#[non_exhaustive]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct PdfPage {
+    /// `/MediaBox` (required).
+    pub media_box: PdfRect,
+    /// `/CropBox`. Defaults to the MediaBox.
+    pub crop_box: Option<PdfRect>,
+    /// `/BleedBox`. Defaults to the CropBox.
+    pub bleed_box: Option<PdfRect>,
+    /// `/TrimBox`. Defaults to the CropBox.
+    pub trim_box: Option<PdfRect>,
+    /// `/ArtBox`. Defaults to the CropBox.
+    pub art_box: Option<PdfRect>,
+    /// `/Rotate` in degrees clockwise. Must be a multiple of 90; other
+    /// values are ignored (treated as 0), matching common viewers.
+    pub rotate: i32,
+    /// `/UserUnit` — size of a user-space unit in multiples of 1/72 inch.
+    /// Non-positive or non-finite values fall back to 1.0.
+    pub user_unit: f32,
+}
+
+impl PdfPage {
+    /// Page with only a MediaBox.
+    pub fn new(media_box: PdfRect) -> Self {
+        Self {
+            media_box,
+            crop_box: None,
+            bleed_box: None,
+            trim_box: None,
+            art_box: None,
+            rotate: 0,
+            user_unit: 1.0,
+        }
+    }
+
+    /// Set `/CropBox`.
+    pub fn with_crop_box(mut self, r: PdfRect) -> Self {
+        self.crop_box = Some(r);
+        self
+    }
+
+    /// Set `/BleedBox`.
+    pub fn with_bleed_box(mut self, r: PdfRect) -> Self {
+        self.bleed_box = Some(r);
+        self
+    }
+
+    /// Set `/TrimBox`.
+    pub fn with_trim_box(mut self, r: PdfRect) -> Self {
+        self.trim_box = Some(r);
+        self
+    }
+
+    /// Set `/ArtBox`.
+    pub fn with_art_box(mut self, r: PdfRect) -> Self {
+        self.art_box = Some(r);
+        self
+    }
+
+    /// Set `/Rotate` in degrees clockwise.
+    pub fn with_rotate(mut self, degrees: i32) -> Self {
+        self.rotate = degrees;
+        self
+    }
+
+    /// Set `/UserUnit`.
+    pub fn with_user_unit(mut self, user_unit: f32) -> Self {
+        self.user_unit = user_unit;
+        self
+    }
+
+    /// Orientation implied by `/Rotate`.
+    pub fn orientation(&self) -> Orientation {
+        if self.rotate % 90 != 0 {
+            return Orientation::Identity;
+        }
+        match self.rotate.rem_euclid(360) {
+            90 => Orientation::Rotate90,
+            180 => Orientation::Rotate180,
+            270 => Orientation::Rotate270,
+            _ => Orientation::Identity,
+        }
+    }
+
+    /// Effective rectangle for a page box, in default user space.
+    ///
+    /// Applies the defaults and clipping from PDF 32000-1 §14.11.2: the
+    /// CropBox is clipped to the MediaBox; Bleed/Trim/Art default to the
+    /// CropBox and are clipped to it. Returns `None` if the result is empty.
+    pub fn effective_box(&self, which: PageBox) -> Option<PdfRect> {
+        let media = self.media_box.normalized();
+        if !(media.width() > 0.0 && media.height() > 0.0) {
+            return None;
+        }
+        let crop = match self.crop_box {
+            Some(c) => c.intersect(&media),
+            None => Some(media),
+        };
+        match which {
+            PageBox::Media => Some(media),
+            PageBox::Crop => crop,
+            PageBox::Bleed => self.bleed_box.map_or(crop, |b| b.intersect(&crop?)),
+            PageBox::Trim => self.trim_box.map_or(crop, |b| b.intersect(&crop?)),
+            PageBox::Art => self.art_box.map_or(crop, |b| b.intersect(&crop?)),
+        }
+    }
+
+    /// Pixel dimensions of a page box at `dpi`, before `/Rotate`.
+    ///
+    /// Returns `None` if the box is empty.
+    pub fn size_at_dpi(&self, which: PageBox, dpi: f32) -> Option<Size> {
+        let r = self.effective_box(which)?;
+        let scale = self.pixels_per_unit(dpi);
+        Some(Size::new(
+            (r.width() as f64 * scale).round().max(1.0) as u32,
+            (r.height() as f64 * scale).round().max(1.0) as u32,
+        ))
+    }
+
+    fn unit(&self) -> f64 {
+        if self.user_unit.is_finite() && self.user_unit > 0.0 {
+            self.user_unit as f64
+        } else {
+            1.0
+        }
+    }
+
+    fn pixels_per_unit(&self, dpi: f32) -> f64 {
+        self.unit() * dpi as f64 / POINTS_PER_INCH
+    }
+
+    /// Plan rendering of a page box.
+    ///
+    /// The raster is the chosen box, unrotated, y-down — what a PDF renderer
+    /// produces with rotation disabled. `/Rotate` becomes the plan's
+    /// orientation; renderers that rotate while rasterizing report it via
+    /// [`DecoderOffer::with_orientation_applied`](crate::DecoderOffer::with_orientation_applied).
+    #[track_caller]
+    pub fn plan(&self, which: PageBox, sizing: PdfSizing) -> Result<PdfPagePlan, At<LayoutError>> {
+        if !self.media_box.is_finite()
+            || [self.crop_box, self.bleed_box, self.trim_box, self.art_box]
+                .iter()
+                .flatten()
+                .any(|r| !r.is_finite())
+        {
+            return Err(at!(LayoutError::NonFiniteFloat));
+        }
+        let clip = self
+            .effective_box(which)
+            .ok_or_else(|| at!(LayoutError::ZeroRegionDimension))?;
+
+        let dpi = match &sizing {
+            PdfSizing::Dpi(dpi) => {
+                if !dpi.is_finite() {
+                    return Err(at!(LayoutError::NonFiniteFloat));
+                }
+                if *dpi <= 0.0 {
+                    return Err(at!(LayoutError::ZeroTargetDimension));
+                }
+                *dpi
+            }
+            PdfSizing::Constrain(_) => POINTS_PER_INCH as f32,
+        };
+        let source_size = self
+            .size_at_dpi(which, dpi)
+            .ok_or_else(|| at!(LayoutError::ZeroRegionDimension))?;
+
+        let orientation = self.orientation();
+        let mut pipeline = Pipeline::new(source_size.width, source_size.height);
+        pipeline = match orientation {
+            Orientation::Rotate90 => pipeline.rotate_90(),
+            Orientation::Rotate180 => pipeline.rotate_180(),
+            Orientation::Rotate270 => pipeline.rotate_270(),
+            _ => pipeline,
+        };
+        if let PdfSizing::Constrain(c) = sizing {
+            pipeline = pipeline.constrain(c);
+        }
+        let (ideal, request) = pipeline.plan()?;
+
+        Ok(PdfPagePlan {
+            page_box: which,
+            clip,
+            source_size,
+            ideal,
+            request,
+        })
+    }
+}
+
+/// Result of [`PdfPage::plan()`].
+#[non_exhaustive]
+#[derive(Clone, Debug)]
+pub struct PdfPagePlan {
+    /// Which box was rendered.
+    pub page_box: PageBox,
+    /// Effective box in default user space — the full raster extent.
+    pub clip: PdfRect,
+    /// Raster dimensions of `clip` before `/Rotate` (the "full decode" size).
+    pub source_size: Size,
+    /// Layout, as from [`Pipeline::plan()`].
+    pub ideal: IdealLayout,
+    /// Request for the renderer, as from [`Pipeline::plan()`].
+    pub request: DecoderRequest,
+}
+
+impl PdfPagePlan {
+    /// User-space rectangle the renderer should rasterize into
+    /// `request.target_size`.
+    ///
+    /// Equal to [`clip`](Self::clip) unless the constraint cropped the page
+    /// (e.g. [`FitCrop`](crate::ConstraintMode::FitCrop)), in which case the
+    /// request's crop is mapped back from raster pixels to points. Rendering
+    /// this rectangle directly at the target size makes the resize an identity.
+    pub fn render_rect(&self) -> PdfRect {
+        let Some(crop) = self.request.crop else {
+            return self.clip;
+        };
+        self.raster_to_user(crop)
+    }
+
+    /// Map a rectangle in raster pixels (pre-rotation, y-down) to user space.
+    pub fn raster_to_user(&self, r: Rect) -> PdfRect {
+        let sx = self.clip.width() as f64 / self.source_size.width.max(1) as f64;
+        let sy = self.clip.height() as f64 / self.source_size.height.max(1) as f64;
+        let x0 = self.clip.llx as f64 + r.x as f64 * sx;
+        let x1 = self.clip.llx as f64 + (r.x + r.width) as f64 * sx;
+        // Raster y grows downward from the top edge (ury).
+        let y_top = self.clip.ury as f64 - r.y as f64 * sy;
+        let y_bottom = self.clip.ury as f64 - (r.y + r.height) as f64 * sy;
+        PdfRect::new(x0 as f32, y_bottom as f32, x1 as f32, y_top as f32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constraint::ConstraintMode;
+    use crate::plan::DecoderOffer;
+
+    const LETTER: PdfRect = PdfRect::new(0.0, 0.0, 612.0, 792.0);
+
+    #[test]
+    fn rect_normalizes_corners() {
+        let r = PdfRect::new(612.0, 792.0, 0.0, 0.0);
+        assert_eq!(r.normalized(), LETTER);
+        assert_eq!(r.width(), 612.0);
+        assert_eq!(r.height(), 792.0);
+    }
+
+    #[test]
+    fn crop_box_defaults_to_media() {
+        let page = PdfPage::new(LETTER);
+        assert_eq!(page.effective_box(PageBox::Crop), Some(LETTER));
+        assert_eq!(page.effective_box(PageBox::Trim), Some(LETTER));
+    }
+
+    #[test]
+    fn boxes_clip_per_spec() {
+        let page = PdfPage::new(LETTER)
+            .with_crop_box(PdfRect::new(-50.0, 36.0, 576.0, 900.0))
+            .with_trim_box(PdfRect::new(18.0, 18.0, 594.0, 774.0));
+        // CropBox clipped to MediaBox.
+        assert_eq!(
+            page.effective_box(PageBox::Crop),
+            Some(PdfRect::new(0.0, 36.0, 576.0, 792.0))
+        );
+        // TrimBox clipped to the effective CropBox.
+        assert_eq!(
+            page.effective_box(PageBox::Trim),
+            Some(PdfRect::new(18.0, 36.0, 576.0, 774.0))
+        );
+        // Bleed and Art default to the CropBox.
+        assert_eq!(
+            page.effective_box(PageBox::Bleed),
+            page.effective_box(PageBox::Crop)
+        );
+        // Media ignores the CropBox.
+        assert_eq!(page.effective_box(PageBox::Media), Some(LETTER));
+    }
+
+    #[test]
+    fn disjoint_box_rejected() {
+        let page = PdfPage::new(LETTER).with_crop_box(PdfRect::new(700.0, 0.0, 800.0, 100.0));
+        assert!(page.effective_box(PageBox::Crop).is_none());
+        assert!(page.plan(PageBox::Crop, PdfSizing::Dpi(72.0)).is_err());
+        // The MediaBox stands on its own.
+        assert_eq!(page.effective_box(PageBox::Media), Some(LETTER));
+
+        // An empty MediaBox leaves every box empty.
+        let page = PdfPage::new(PdfRect::new(0.0, 0.0, 612.0, 0.0));
+        assert!(page.effective_box(PageBox::Crop).is_none());
+        assert!(page.effective_box(PageBox::Art).is_none());
+        assert!(page.size_at_dpi(PageBox::Crop, 72.0).is_none());
+    }
+
+    #[test]
+    fn rotate_maps_to_orientation() {
+        let cases = [
+            (0, Orientation::Identity),
+            (90, Orientation::Rotate90),
+            (180, Orientation::Rotate180),
+            (270, Orientation::Rotate270),
+            (360, Orientation::Identity),
+            (-90, Orientation::Rotate270),
+            (450, Orientation::Rotate90),
+            (45, Orientation::Identity),
+        ];
+        for (deg, expected) in cases {
+            assert_eq!(
+                PdfPage::new(LETTER).with_rotate(deg).orientation(),
+                expected,
+                "{deg}"
+            );
+        }
+    }
+
+    #[test]
+    fn dpi_sizing_with_user_unit() {
+        let page = PdfPage::new(LETTER).with_user_unit(2.0);
+        assert_eq!(
+            page.size_at_dpi(PageBox::Crop, 72.0),
+            Some(Size::new(1224, 1584))
+        );
+        let plan = page.plan(PageBox::Crop, PdfSizing::Dpi(300.0)).unwrap();
+        assert_eq!(plan.source_size, Size::new(5100, 6600));
+        assert_eq!(plan.request.target_size, Size::new(5100, 6600));
+        assert!(plan.request.crop.is_none());
+    }
+
+    #[test]
+    fn rotated_page_constrained_in_display_space() {
+        // Landscape display (792×612 pt) fit into 800×800 → 800×618.
+        let page = PdfPage::new(LETTER).with_rotate(270);
+        let plan = page
+            .plan(
+                PageBox::Crop,
+                PdfSizing::Constrain(Constraint::new(ConstraintMode::Fit, 800, 800)),
+            )
+            .unwrap();
+        assert_eq!(plan.source_size, Size::new(612, 792));
+        assert_eq!(plan.ideal.orientation, Orientation::Rotate270);
+        assert_eq!(plan.ideal.layout.resize_to, Size::new(800, 618));
+        // Renderer rotates while rasterizing at the target size: nothing left to do
+        // beyond the resize the renderer already honored.
+        let offer =
+            DecoderOffer::full_decode(800, 618).with_orientation_applied(Orientation::Rotate270);
+        let lp = plan.ideal.finalize(&plan.request, &offer);
+        assert!(lp.remaining_orientation.is_identity());
+        assert!(lp.resize_is_identity);
+    }
+
+    #[test]
+    fn fit_crop_maps_back_to_user_space() {
+        // Square thumbnail of a Letter page: crop top/bottom equally.
+        let page = PdfPage::new(LETTER);
+        let plan = page
+            .plan(
+                PageBox::Crop,
+                PdfSizing::Constrain(Constraint::new(ConstraintMode::FitCrop, 200, 200)),
+            )
+            .unwrap();
+        let r = plan.render_rect();
+        assert_eq!((r.llx, r.urx), (0.0, 612.0));
+        assert_eq!(r.height(), 612.0);
+        assert_eq!(r.lly, 90.0);
+        assert_eq!(r.ury, 702.0);
+    }
+
+    #[test]
+    fn render_rect_is_clip_without_crop() {
+        let page = PdfPage::new(LETTER).with_crop_box(PdfRect::new(36.0, 36.0, 576.0, 756.0));
+        let plan = page.plan(PageBox::Crop, PdfSizing::Dpi(144.0)).unwrap();
+        assert_eq!(plan.render_rect(), plan.clip);
+        assert_eq!(plan.source_size, Size::new(1080, 1440));
+    }
+
+    #[test]
+    fn invalid_inputs_rejected() {
+        let page = PdfPage::new(PdfRect::new(0.0, 0.0, f32::NAN, 792.0));
+        assert_eq!(
+            *page
+                .plan(PageBox::Crop, PdfSizing::Dpi(72.0))
+                .unwrap_err()
+                .error(),
+            LayoutError::NonFiniteFloat
+        );
+        let page = PdfPage::new(LETTER);
+        assert_eq!(
+            *page
+                .plan(PageBox::Crop, PdfSizing::Dpi(0.0))
+                .unwrap_err()
+                .error(),
+            LayoutError::ZeroTargetDimension
+        );
+        assert_eq!(
+            *page
+                .plan(PageBox::Crop, PdfSizing::Dpi(f32::INFINITY))
+                .unwrap_err()
+                .error(),
+            LayoutError::NonFiniteFloat
+        );
+    }
+}
@@ -232,15 +232,12 @@ pub enum Command {
     Constrain(Constraint),
     /// Add padding around the image.
     Pad(Padding),
-    /// A dimension-changing geometric effect (arbitrary rotation, etc.)
-    ///
-    /// Processed in sequence order — can appear before or after [`Constrain`].
-    /// Order matters: crop then rotate ≠ rotate then crop.
     /// A dimension-changing spatial effect (arbitrary rotation, padding, etc.)
     ///
-    /// Processed in sequence order by [`compute_layout_sequential()`].
     /// Effects before the [`Constrain`](Self::Constrain) command adjust the
     /// effective source dimensions; effects after adjust the output canvas.
+    /// [`compute_layout_sequential()`] processes them in sequence order;
+    /// [`compute_layout()`] places them in fixed pre- and post-resize slots.
     /// The planner resolves concrete input/output dims for each effect and
     /// stores them in [`IdealLayout::effects`].
     Effect(alloc::boxed::Box<dyn crate::dimension::DimensionEffect>),
@@ -258,12 +255,25 @@ pub struct ResolvedEffect {
     pub input_dims: Size,
     /// Output dimensions after this effect.
     pub output_dims: Size,
-    /// Index in the original command sequence.
+    /// Index in the original command sequence. For a
+    /// [`FusedEffect`](crate::FusedEffect), the index of its first part.
     pub command_index: usize,
     /// Whether this effect is before the Constrain/resize step.
     pub before_resize: bool,
 }
 
+impl ResolvedEffect {
+    /// What the planner knew about this effect at its input dims.
+    ///
+    /// [`ContentMapped`](crate::EffectKind::ContentMapped) effects have
+    /// exact dims but no point mapping, so coordinates (focus points,
+    /// overlays) cannot be carried across them.
+    pub fn kind(&self) -> crate::dimension::EffectKind {
+        self.effect
+            .kind(self.input_dims.width, self.input_dims.height)
+    }
+}
+
 /// Result of the first phase of layout planning.
 #[derive(Clone, Debug, Default)]
 #[non_exhaustive]
@@ -284,11 +294,6 @@ pub struct IdealLayout {
     /// Empty when no [`Command::Effect`] commands were used.
     /// The execution engine reads these to insert materialization
     /// barriers and spatial transforms.
-    /// Resolved dimension effects in pipeline order.
-    ///
-    /// Empty when no [`Command::Effect`] commands were used.
-    /// The execution engine reads these to insert materialization
-    /// barriers and spatial transforms.
     pub effects: Vec<ResolvedEffect>,
 }
 
@@ -362,6 +367,61 @@ impl DecoderRequest {
         self.crop = Some(crop);
         self
     }
+
+    /// Crop as fractions of the source, for resolution-independent renderers.
+    ///
+    /// SVG and PDF renderers address their content in their own units
+    /// (viewBox, points), not in the nominal pixel size passed to the
+    /// planner. Multiply the viewport by the renderer's extent to get the
+    /// region to rasterize. Without a crop the viewport is the full source.
+    pub fn viewport(&self, source_w: u32, source_h: u32) -> Viewport {
+        let (sw, sh) = (source_w.max(1) as f64, source_h.max(1) as f64);
+        match self.crop {
+            Some(r) => Viewport {
+                x: r.x as f64 / sw,
+                y: r.y as f64 / sh,
+                width: r.width as f64 / sw,
+                height: r.height as f64 / sh,
+            },
+            None => Viewport::FULL,
+        }
+    }
+
+    /// Pixel size a renderer should rasterize the viewport to, given the
+    /// orientation it applies while rendering.
+    ///
+    /// Equals `target_size` when the renderer applies the full requested
+    /// orientation; axes are swapped when the remaining orientation does.
+    pub fn render_size(&self, orientation_applied: Orientation) -> Size {
+        let remaining = orientation_applied.inverse().compose(self.orientation);
+        remaining.transform_dimensions(self.target_size.width, self.target_size.height)
+    }
+}
+
+/// A crop viewport in fractions of the source extent (0.0–1.0).
+///
+/// Returned by [`DecoderRequest::viewport()`]. Coordinates are in
+/// pre-orientation source space, origin top-left.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Viewport {
+    /// Left edge as a fraction of source width.
+    pub x: f64,
+    /// Top edge as a fraction of source height.
+    pub y: f64,
+    /// Width as a fraction of source width.
+    pub width: f64,
+    /// Height as a fraction of source height.
+    pub height: f64,
+}
+
+impl Viewport {
+    /// The full source.
+    pub const FULL: Self = Self {
+        x: 0.0,
+        y: 0.0,
+        width: 1.0,
+        height: 1.0,
+    };
 }
 
 /// What the decoder actually did.
@@ -374,6 +434,10 @@ pub struct DecoderOffer {
     pub crop_applied: Option<Rect>,
     /// Orientation the decoder applied.
     pub orientation_applied: Orientation,
+    /// The decoder is resolution-independent and rasterized `crop_applied`
+    /// (or the full source) directly at `dimensions`, rather than at source
+    /// pixel scale. See [`DecoderOffer::rendered()`].
+    pub scaled: bool,
 }
 
 impl DecoderOffer {
@@ -383,9 +447,40 @@ impl DecoderOffer {
             dimensions: Size::new(w, h),
             crop_applied: None,
             orientation_applied: Orientation::Identity,
+            scaled: false,
+        }
+    }
+
+    /// Offer from a resolution-independent renderer (SVG, PDF) that honored
+    /// the request: it rasterized [`DecoderRequest::viewport()`] at
+    /// [`DecoderRequest::render_size()`].
+    ///
+    /// [`IdealLayout::finalize()`] then needs no trim and no resize.
+    ///
+    /// ```
+    /// use zenlayout::{DecoderOffer, Pipeline};
+    ///
+    /// let (ideal, request) = Pipeline::new(612, 792).fit_crop(200, 200).plan().unwrap();
+    /// let offer = DecoderOffer::rendered(&request, request.orientation);
+    /// let plan = ideal.finalize(&request, &offer);
+    /// assert!(plan.resize_is_identity);
+    /// assert!(plan.trim.is_none());
+    /// ```
+    pub fn rendered(request: &DecoderRequest, orientation_applied: Orientation) -> Self {
+        Self {
+            dimensions: request.render_size(orientation_applied),
+            crop_applied: request.crop,
+            orientation_applied,
+            scaled: true,
         }
     }
 
+    /// Mark the output as rendered at `dimensions` rather than source scale.
+    pub fn with_scaled(mut self, scaled: bool) -> Self {
+        self.scaled = scaled;
+        self
+    }
+
     /// Set the orientation the decoder applied.
     pub fn with_orientation_applied(mut self, orientation: Orientation) -> Self {
         self.orientation_applied = orientation;
@@ -929,7 +1024,9 @@ enum SourceRegion {
 ///
 /// **Last-setter-wins**: calling the same category of method twice replaces
 /// the previous value (standard builder pattern). Orientation is the
-/// exception — orientation commands always compose algebraically.
+/// exception — orientation commands always compose algebraically — and
+/// [`effect`](Self::effect)s accumulate. Call [`sequential`](Self::sequential)
+/// to evaluate calls in order instead.
 ///
 /// # Example
 ///
@@ -956,6 +1053,17 @@ pub struct Pipeline {
     constraint: Option<Constraint>,
     padding: Option<Padding>,
     limits: Option<OutputLimits>,
+    pre_effects: Vec<alloc::boxed::Box<dyn crate::dimension::DimensionEffect>>,
+    post_effects: Vec<alloc::boxed::Box<dyn crate::dimension::DimensionEffect>>,
+    /// Recorded commands once [`sequential()`](Self::sequential) is called.
+    sequence: Option<Vec<Command>>,
+    /// Smart crop that places the constraint's crop at plan time.
+    #[cfg(feature = "smart-crop")]
+    smart_gravity: Option<SmartStep>,
+    /// Mode that fits the smart-placed crop; see
+    /// [`smart_final_mode()`](Self::smart_final_mode).
+    #[cfg(feature = "smart-crop")]
+    smart_final_mode: Option<ConstraintMode>,
 }
 
 impl Pipeline {
@@ -969,9 +1077,104 @@ impl Pipeline {
             constraint: None,
             padding: None,
             limits: None,
+            pre_effects: Vec::new(),
+            post_effects: Vec::new(),
+            sequence: None,
+            #[cfg(feature = "smart-crop")]
+            smart_gravity: None,
+            #[cfg(feature = "smart-crop")]
+            smart_final_mode: None,
         }
     }
 
+    /// Switch to sequential evaluation: every later builder call is recorded
+    /// as a [`Command`] and [`plan()`](Self::plan) uses
+    /// [`compute_layout_sequential()`] instead of the fixed pipeline.
+    ///
+    /// Calls made before this one are converted to commands in fixed
+    /// pipeline order. In sequential mode crops compose, the last constraint
+    /// wins, and operations after the constraint act on the output canvas.
+    ///
+    /// ```
+    /// use zenlayout::{Pipeline, Size};
+    ///
+    /// // Crop, then crop again inside the first crop.
+    /// let (ideal, _) = Pipeline::new(800, 600)
+    ///     .sequential()
+    ///     .crop_pixels(100, 100, 600, 400)
+    ///     .crop_pixels(50, 50, 500, 300)
+    ///     .plan()
+    ///     .unwrap();
+    /// assert_eq!(ideal.layout.canvas, Size::new(500, 300));
+    /// ```
+    pub fn sequential(mut self) -> Self {
+        if self.sequence.is_some() {
+            return self;
+        }
+        let mut commands = Vec::new();
+        if !self.orientation.is_identity() {
+            commands.push(Command::AutoOrient(self.orientation.to_exif()));
+        }
+        match &self.source_region {
+            Some(SourceRegion::Crop(c)) => commands.push(Command::Crop(*c)),
+            Some(SourceRegion::Region(r)) => commands.push(Command::Region(*r)),
+            None => {}
+        }
+        commands.extend(self.pre_effects.drain(..).map(Command::Effect));
+        if let Some(c) = &self.constraint {
+            commands.push(Command::Constrain(c.clone()));
+        }
+        if let Some(p) = self.padding {
+            commands.push(Command::Pad(p));
+        }
+        commands.extend(self.post_effects.drain(..).map(Command::Effect));
+        self.sequence = Some(commands);
+        self
+    }
+
+    /// Record a command when in sequential mode.
+    fn record(&mut self, command: impl FnOnce() -> Command) {
+        if let Some(seq) = &mut self.sequence {
+            seq.push(command());
+        }
+    }
+
+    /// Add a dimension-changing effect (arbitrary rotation, warp, trim, …).
+    ///
+    /// In the fixed pipeline, effects added before any constraint go in the
+    /// pre-resize slot and transform the cropped source; effects added after
+    /// a constraint go in the post-resize slot and transform the canvas.
+    /// Effects accumulate (they are not last-setter-wins). See
+    /// [`compute_layout()`] for the slot order. In
+    /// [sequential](Self::sequential) mode the effect is recorded in call order.
+    ///
+    /// For fixed-pipeline plans, [`ResolvedEffect::command_index`] counts
+    /// effects in the order they were added.
+    ///
+    /// ```
+    /// use zenlayout::{Pipeline, RotateEffect, RotateMode};
+    ///
+    /// let (ideal, _) = Pipeline::new(4000, 3000)
+    ///     .effect(RotateEffect::from_degrees(2.0, RotateMode::InscribedCrop))
+    ///     .fit(800, 800)
+    ///     .plan()
+    ///     .unwrap();
+    /// assert_eq!(ideal.effects.len(), 1);
+    /// assert!(ideal.effects[0].before_resize);
+    /// ```
+    pub fn effect(mut self, effect: impl crate::dimension::DimensionEffect + 'static) -> Self {
+        let effect: alloc::boxed::Box<dyn crate::dimension::DimensionEffect> =
+            alloc::boxed::Box::new(effect);
+        if let Some(seq) = &mut self.sequence {
+            seq.push(Command::Effect(effect));
+        } else if self.constraint.is_none() {
+            self.pre_effects.push(effect);
+        } else {
+            self.post_effects.push(effect);
+        }
+        self
+    }
+
     /// Apply EXIF orientation correction (value 1-8). Invalid values are ignored.
     ///
     /// Orientation commands always compose algebraically into a single source
@@ -981,6 +1184,7 @@ impl Pipeline {
         if let Some(o) = Orientation::from_exif(exif) {
             self.orientation = self.orientation.compose(o);
         }
+        self.record(|| Command::AutoOrient(exif));
         self
     }
 
@@ -988,6 +1192,7 @@ impl Pipeline {
     /// commands into a single source transform (see [`auto_orient`](Self::auto_orient)).
     pub fn rotate_90(mut self) -> Self {
         self.orientation = self.orientation.compose(Orientation::Rotate90);
+        self.record(|| Command::Rotate(Rotation::Rotate90));
         self
     }
 
@@ -995,6 +1200,7 @@ impl Pipeline {
     /// into a single source transform (see [`auto_orient`](Self::auto_orient)).
     pub fn rotate_180(mut self) -> Self {
         self.orientation = self.orientation.compose(Orientation::Rotate180);
+        self.record(|| Command::Rotate(Rotation::Rotate180));
         self
     }
 
@@ -1002,6 +1208,7 @@ impl Pipeline {
     /// commands into a single source transform (see [`auto_orient`](Self::auto_orient)).
     pub fn rotate_270(mut self) -> Self {
         self.orientation = self.orientation.compose(Orientation::Rotate270);
+        self.record(|| Command::Rotate(Rotation::Rotate270));
         self
     }
 
@@ -1009,6 +1216,7 @@ impl Pipeline {
     /// into a single source transform (see [`auto_orient`](Self::auto_orient)).
     pub fn flip_h(mut self) -> Self {
         self.orientation = self.orientation.compose(Orientation::FlipH);
+        self.record(|| Command::Flip(FlipAxis::Horizontal));
         self
     }
 
@@ -1016,6 +1224,7 @@ impl Pipeline {
     /// into a single source transform (see [`auto_orient`](Self::auto_orient)).
     pub fn flip_v(mut self) -> Self {
         self.orientation = self.orientation.compose(Orientation::FlipV);
+        self.record(|| Command::Flip(FlipAxis::Vertical));
         self
     }
 
@@ -1042,6 +1251,7 @@ impl Pipeline {
     /// Replaces any previous crop or region.
     pub fn crop(mut self, source_crop: SourceCrop) -> Self {
         self.source_region = Some(SourceRegion::Crop(source_crop));
+        self.record(|| Command::Crop(source_crop));
         self
     }
 
@@ -1050,9 +1260,66 @@ impl Pipeline {
     /// Replaces any previous crop or region.
     pub fn region(mut self, region: Region) -> Self {
         self.source_region = Some(SourceRegion::Region(region));
+        self.record(|| Command::Region(region));
         self
     }
 
+    /// Crop an oriented rectangle (see [`RotatedCropEffect`](crate::RotatedCropEffect)),
+    /// given in post-orientation coordinates.
+    ///
+    /// When the angle is a multiple of 90° and only orientation commands
+    /// precede this call, the crop is planned losslessly as an orientation
+    /// plus a [`Region`] (replacing any previous crop or region). Otherwise
+    /// it is added as an effect whose coordinates are relative to the content
+    /// left by any crop or region before it. In the fixed pipeline that
+    /// effect always goes in the pre-resize slot, even after a constraint,
+    /// since the rectangle is given in source pixels.
+    ///
+    /// ```
+    /// use zenlayout::{Pipeline, RotatedCropEffect, Size};
+    ///
+    /// let (ideal, _) = Pipeline::new(800, 600)
+    ///     .rotated_crop(RotatedCropEffect::from_degrees((400.0, 300.0), 200.0, 100.0, 90.0))
+    ///     .plan()
+    ///     .unwrap();
+    /// assert!(ideal.effects.is_empty());
+    /// assert_eq!(ideal.layout.canvas, Size::new(200, 100));
+    /// ```
+    pub fn rotated_crop(mut self, crop: crate::dimension::RotatedCropEffect) -> Self {
+        let only_orientation = match &self.sequence {
+            Some(seq) => seq.iter().all(|c| {
+                matches!(
+                    c,
+                    Command::AutoOrient(_) | Command::Rotate(_) | Command::Flip(_)
+                )
+            }),
+            None => {
+                self.source_region.is_none()
+                    && self.pre_effects.is_empty()
+                    && self.constraint.is_none()
+                    && self.padding.is_none()
+                    && self.post_effects.is_empty()
+            }
+        };
+        let oriented = self
+            .orientation
+            .transform_dimensions(self.source_w, self.source_h);
+        match crop.cardinal(oriented.width, oriented.height) {
+            Some((orientation, region)) if only_orientation => {
+                if !orientation.is_identity() {
+                    self.orientation = self.orientation.compose(orientation);
+                    self.record(|| Command::AutoOrient(orientation.to_exif()));
+                }
+                self.region(region)
+            }
+            _ if self.sequence.is_none() => {
+                self.pre_effects.push(alloc::boxed::Box::new(crop));
+                self
+            }
+            _ => self.effect(crop),
+        }
+    }
+
     /// Define a viewport from pixel edge coordinates (left, top, right, bottom).
     ///
     /// Uses edge coordinates, not origin + size. A viewport from
@@ -1161,6 +1428,7 @@ impl Pipeline {
     /// If the [`Constraint`] has its own [`source_crop`](Constraint::source_crop),
     /// that crop composes with (nests inside) any pipeline-level crop or region.
     pub fn constrain(mut self, constraint: Constraint) -> Self {
+        self.record(|| Command::Constrain(constraint.clone()));
         self.constraint = Some(constraint);
         self
     }
@@ -1170,6 +1438,7 @@ impl Pipeline {
     /// Replaces any previous padding.
     pub fn pad(mut self, padding: Padding) -> Self {
         self.padding = Some(padding);
+        self.record(|| Command::Pad(padding));
         self
     }
 
@@ -1195,6 +1464,205 @@ impl Pipeline {
         self.pad(Padding::uniform(amount, color))
     }
 
+    /// Place the crop of a [`FitCrop`](ConstraintMode::FitCrop),
+    /// [`WithinCrop`](ConstraintMode::WithinCrop) or
+    /// [`AspectCrop`](ConstraintMode::AspectCrop) constraint with smart crop
+    /// instead of a fixed [`Gravity`](crate::Gravity).
+    ///
+    /// `input` is in percentages of the source image as decoded, before
+    /// orientation. At plan time its regions and heatmap are carried through
+    /// orientation, the crop or region, pre-resize effects and the
+    /// constraint's own [`source_crop`](Constraint::source_crop), then a
+    /// smart crop at the constraint's aspect ratio sets the gravity. The
+    /// target aspect is the constraint's; the rest of `config` applies. In
+    /// [sequential](Self::sequential) mode it places the last constraint.
+    ///
+    /// With [`CropMode::Maximal`](crate::smart_crop::CropMode::Maximal) or
+    /// [`Balanced`](crate::smart_crop::CropMode::Balanced) the subject is
+    /// zoomed instead: that crop becomes the constraint's source crop,
+    /// whatever its mode, and the mode then fits that crop.
+    ///
+    /// The constraint is left alone when it does not crop, or when an effect
+    /// cannot map points (see
+    /// [`SmartCropInput::through_effect`](crate::smart_crop::SmartCropInput::through_effect)).
+    ///
+    /// Replaces any previous smart gravity or [pending
+    /// detection](Self::await_detection).
+    ///
+    /// ```
+    /// use zenlayout::Pipeline;
+    /// use zenlayout::smart_crop::{CropConfig, FocusRect, SmartCropInput};
+    ///
+    /// // A face near the right edge of a landscape photo.
+    /// let input = SmartCropInput {
+    ///     focus_regions: vec![FocusRect { x1: 80.0, y1: 30.0, x2: 95.0, y2: 60.0, weight: 1.0 }],
+    ///     ..Default::default()
+    /// };
+    /// let (ideal, _) = Pipeline::new(1600, 900)
+    ///     .fit_crop(400, 400)
+    ///     .smart_gravity(input, CropConfig::default())
+    ///     .plan()
+    ///     .unwrap();
+    /// let crop = ideal.layout.source_crop.unwrap();
+    /// assert_eq!(crop.x + crop.width, 1600);
+    /// ```
+    #[cfg(feature = "smart-crop")]
+    pub fn smart_gravity(
+        mut self,
+        input: crate::smart_crop::SmartCropInput,
+        config: crate::smart_crop::CropConfig,
+    ) -> Self {
+        self.smart_gravity = Some(SmartStep {
+            input,
+            config,
+            pending: None,
+        });
+        self
+    }
+
+    /// Like [`smart_gravity()`](Self::smart_gravity), but the inputs are
+    /// still to come from `detection`.
+    ///
+    /// The pipeline reports the detection through
+    /// [`pending_detection()`](Self::pending_detection) until
+    /// [`resolve_detection()`](Self::resolve_detection) supplies its
+    /// output. Planning while it is pending uses the constraint's own
+    /// gravity.
+    ///
+    /// ```
+    /// use zenlayout::Pipeline;
+    /// use zenlayout::smart_crop::{CropConfig, Detection, FocusRect, SmartCropInput};
+    ///
+    /// let pipeline = Pipeline::new(1600, 900)
+    ///     .fit_crop(400, 400)
+    ///     .await_detection(Detection::Faces, CropConfig::default());
+    /// assert_eq!(pipeline.pending_detection(), Some(Detection::Faces));
+    ///
+    /// // Run the face detector, then hand its output back.
+    /// let faces = SmartCropInput {
+    ///     focus_regions: vec![FocusRect { x1: 5.0, y1: 30.0, x2: 15.0, y2: 50.0, weight: 0.9 }],
+    ///     ..Default::default()
+    /// };
+    /// let pipeline = pipeline.resolve_detection(faces);
+    /// assert_eq!(pipeline.pending_detection(), None);
+    /// let (ideal, _) = pipeline.plan().unwrap();
+    /// assert_eq!(ideal.layout.source_crop.unwrap().x, 0);
+    /// ```
+    #[cfg(feature = "smart-crop")]
+    pub fn await_detection(
+        mut self,
+        detection: crate::smart_crop::Detection,
+        config: crate::smart_crop::CropConfig,
+    ) -> Self {
+        self.smart_gravity = Some(SmartStep {
+            input: crate::smart_crop::SmartCropInput::default(),
+            config,
+            pending: Some(detection),
+        });
+        self
+    }
+
+    /// Fit the crop placed by [`smart_gravity()`](Self::smart_gravity) or a
+    /// resolved detection with `mode` instead of the constraint's own mode.
+    ///
+    /// The placed crop window becomes the constraint's
+    /// [`source_crop`](Constraint::source_crop) and `mode` then fits it to
+    /// the target, e.g. [`Fit`](ConstraintMode::Fit) keeps the whole window
+    /// rather than cropping it again. Without a placement the constraint is
+    /// unchanged.
+    ///
+    /// ```
+    /// use zenlayout::{ConstraintMode, Pipeline, Size};
+    /// use zenlayout::smart_crop::{CropConfig, FocusRect, SmartCropInput};
+    ///
+    /// let input = SmartCropInput {
+    ///     focus_regions: vec![FocusRect { x1: 80.0, y1: 30.0, x2: 95.0, y2: 60.0, weight: 1.0 }],
+    ///     ..Default::default()
+    /// };
+    /// let (ideal, _) = Pipeline::new(1600, 900)
+    ///     .fit_crop(400, 300)
+    ///     .smart_gravity(input, CropConfig::default())
+    ///     .smart_final_mode(ConstraintMode::Within)
+    ///     .plan()
+    ///     .unwrap();
+    /// let crop = ideal.layout.source_crop.unwrap();
+    /// assert_eq!((crop.x + crop.width, crop.width, crop.height), (1600, 1200, 900));
+    /// assert_eq!(ideal.layout.resize_to, Size::new(400, 300));
+    /// ```
+    #[cfg(feature = "smart-crop")]
+    pub fn smart_final_mode(mut self, mode: ConstraintMode) -> Self {
+        self.smart_final_mode = Some(mode);
+        self
+    }
+
+    /// The detection [`await_detection()`](Self::await_detection) is
+    /// waiting on, if any.
+    #[cfg(feature = "smart-crop")]
+    pub fn pending_detection(&self) -> Option<crate::smart_crop::Detection> {
+        self.smart_gravity.as_ref().and_then(|s| s.pending)
+    }
+
+    /// Supply the output of a pending detection, in the same coordinates as
+    /// [`smart_gravity()`](Self::smart_gravity) takes.
+    ///
+    /// Without a pending detection this is `smart_gravity(input, config)`
+    /// with the config already set, or the default one.
+    #[cfg(feature = "smart-crop")]
+    pub fn resolve_detection(mut self, input: crate::smart_crop::SmartCropInput) -> Self {
+        let config = self
+            .smart_gravity
+            .take()
+            .map(|s| s.config)
+            .unwrap_or_default();
+        self.smart_gravity(input, config)
+    }
+
+    /// Resolve a pending detection with the built-in saliency estimator.
+    ///
+    /// `pixels` is a small downsampled copy of the source as stored, before
+    /// orientation; see [`estimate_saliency()`](crate::saliency::estimate_saliency).
+    /// Stands in for any detection, faces included, when no detector is
+    /// available. A buffer that does not match `width × height` leaves the
+    /// pipeline unchanged.
+    ///
+    /// ```
+    /// use zenlayout::Pipeline;
+    /// use zenlayout::saliency::{PixelFormat, SaliencyConfig};
+    /// use zenlayout::smart_crop::{CropConfig, Detection};
+    ///
+    /// // 64×36 thumbnail of a 1600×900 source, subject on the left.
+    /// let mut thumb = vec![90u8; 64 * 36];
+    /// for y in 12..24 {
+    ///     for x in 4..14 {
+    ///         thumb[y * 64 + x] = 230;
+    ///     }
+    /// }
+    /// let pipeline = Pipeline::new(1600, 900)
+    ///     .fit_crop(400, 400)
+    ///     .await_detection(Detection::Saliency, CropConfig::default())
+    ///     .resolve_saliency(&thumb, 64, 36, PixelFormat::Luma8, &SaliencyConfig::default());
+    /// assert_eq!(pipeline.pending_detection(), None);
+    /// let (ideal, _) = pipeline.plan().unwrap();
+    /// assert_eq!(ideal.layout.source_crop.unwrap().x, 0);
+    /// ```
+    #[cfg(feature = "saliency")]
+    pub fn resolve_saliency(
+        self,
+        pixels: &[u8],
+        width: u32,
+        height: u32,
+        format: crate::saliency::PixelFormat,
+        config: &crate::saliency::SaliencyConfig,
+    ) -> Self {
+        match crate::saliency::estimate_saliency(pixels, width, height, format, config) {
+            Some(heatmap) => self.resolve_detection(crate::smart_crop::SmartCropInput {
+                heatmap: Some(heatmap),
+                ..Default::default()
+            }),
+            None => self,
+        }
+    }
+
     /// Apply safety limits after layout computation.
     ///
     /// See [`OutputLimits`] for details on max/min/align behavior.
@@ -1205,15 +1673,41 @@ impl Pipeline {
 
     /// Compute the ideal layout and decoder request.
     ///
-    /// Processes the pipeline in fixed order: orient → crop/region → constrain → pad → limits.
-    /// For sequential command evaluation, use [`compute_layout_sequential()`] directly.
+    /// Processes the pipeline in fixed order: orient → crop/region →
+    /// pre-resize effects → constrain → pad → post-resize effects → limits.
+    /// After [`sequential()`](Self::sequential), the recorded commands are
+    /// evaluated with [`compute_layout_sequential()`] instead.
     #[track_caller]
-    pub fn plan(self) -> Result<(IdealLayout, DecoderRequest), At<LayoutError>> {
+    pub fn plan(
+        #[cfg_attr(not(feature = "smart-crop"), allow(unused_mut))] mut self,
+    ) -> Result<(IdealLayout, DecoderRequest), At<LayoutError>> {
+        #[cfg(feature = "smart-crop")]
+        self.apply_smart_gravity();
+        if let Some(commands) = &self.sequence {
+            return compute_layout_sequential(
+                commands,
+                self.source_w,
+                self.source_h,
+                self.limits.as_ref(),
+            );
+        }
         let (crop, region) = match self.source_region {
             Some(SourceRegion::Crop(c)) => (Some(c), None),
             Some(SourceRegion::Region(r)) => (None, Some(r)),
             None => (None, None),
         };
+        let pre: Vec<(usize, &dyn crate::dimension::DimensionEffect)> = self
+            .pre_effects
+            .iter()
+            .enumerate()
+            .map(|(i, e)| (i, e.as_ref()))
+            .collect();
+        let post: Vec<(usize, &dyn crate::dimension::DimensionEffect)> = self
+            .post_effects
+            .iter()
+            .enumerate()
+            .map(|(i, e)| (pre.len() + i, e.as_ref()))
+            .collect();
         plan_from_parts(
             self.source_w,
             self.source_h,
@@ -1223,8 +1717,282 @@ impl Pipeline {
             self.constraint.as_ref(),
             self.padding,
             self.limits.as_ref(),
+            &pre,
+            &post,
         )
     }
+
+    /// Resolve [`smart_gravity()`](Self::smart_gravity) into the constraint
+    /// it places.
+    #[cfg(feature = "smart-crop")]
+    fn apply_smart_gravity(&mut self) {
+        let Some(SmartStep {
+            input,
+            config,
+            pending: None,
+        }) = self.smart_gravity.take()
+        else {
+            return;
+        };
+        let (sw, sh) = (self.source_w, self.source_h);
+        let final_mode = self.smart_final_mode;
+        if let Some(commands) = &mut self.sequence {
+            let placement =
+                sequential_smart_placement(commands, sw, sh, &input, &config, final_mode.is_some());
+            let last = commands
+                .iter_mut()
+                .rev()
+                .find(|c| matches!(c, Command::Constrain(_)));
+            if let Some(p) = placement
+                && let Some(Command::Constrain(c)) = last
+            {
+                p.apply(c, final_mode);
+            }
+            return;
+        }
+        let Some(constraint) = &self.constraint else {
+            return;
+        };
+        let oriented = self.orientation.transform_dimensions(sw, sh);
+        let (ow, oh) = (oriented.width as i32, oriented.height as i32);
+        let region = match self.source_region {
+            Some(SourceRegion::Crop(c)) => Some(c.to_region()),
+            Some(SourceRegion::Region(r)) => Some(r),
+            None => None,
+        };
+        let effects = self
+            .pre_effects
+            .iter()
+            .map(|e| FrameStep::Effect(e.as_ref()));
+        let mut steps = Vec::new();
+        match region {
+            Some(reg) if !self.pre_effects.is_empty() => {
+                // As in the planner: effects see the visible content, and the
+                // region's padding goes back around their output.
+                let (l, t, r, b) = reg.resolve(oriented.width, oriented.height);
+                let (cl, ct) = (l.clamp(0, ow), t.clamp(0, oh));
+                let (cr, cb) = (r.clamp(cl, ow), b.clamp(ct, oh));
+                steps.push(FrameStep::Region(Region {
+                    left: RegionCoord::px(cl),
+                    top: RegionCoord::px(ct),
+                    right: RegionCoord::px(cr),
+                    bottom: RegionCoord::px(cb),
+                    color: reg.color,
+                }));
+                steps.extend(effects);
+                steps.push(FrameStep::Region(Region {
+                    left: RegionCoord::px(l - cl),
+                    top: RegionCoord::px(t - ct),
+                    right: RegionCoord::pct_px(1.0, r - cr),
+                    bottom: RegionCoord::pct_px(1.0, b - cb),
+                    color: reg.color,
+                }));
+            }
+            Some(reg) => steps.push(FrameStep::Region(reg)),
+            None => steps.extend(effects),
+        }
+        let placement = smart_placement(
+            &input,
+            &config,
+            sw,
+            sh,
+            self.orientation,
+            &steps,
+            constraint,
+            final_mode.is_some(),
+        );
+        if let (Some(p), Some(c)) = (placement, &mut self.constraint) {
+            p.apply(c, final_mode);
+        }
+    }
+}
+
+/// Smart crop recorded by [`Pipeline::smart_gravity()`] or
+/// [`Pipeline::await_detection()`].
+#[cfg(feature = "smart-crop")]
+#[derive(Clone, Debug)]
+struct SmartStep {
+    input: crate::smart_crop::SmartCropInput,
+    config: crate::smart_crop::CropConfig,
+    /// Detection whose output replaces `input`; nothing is placed until then.
+    pending: Option<crate::smart_crop::Detection>,
+}
+
+/// What smart crop changes on the constraint it places.
+#[cfg(feature = "smart-crop")]
+enum Placement {
+    /// Where the constraint's own crop window goes.
+    Gravity(crate::constraint::Gravity),
+    /// A zoomed crop for the constraint to fit.
+    SourceCrop(SourceCrop),
+}
+
+#[cfg(feature = "smart-crop")]
+impl Placement {
+    fn apply(self, constraint: &mut Constraint, final_mode: Option<ConstraintMode>) {
+        match self {
+            Self::Gravity(g) => constraint.gravity = g,
+            Self::SourceCrop(crop) => constraint.source_crop = Some(crop),
+        }
+        if let Some(mode) = final_mode {
+            constraint.mode = mode;
+        }
+    }
+}
+
+/// One change of coordinate frame between the oriented source and the
+/// constraint's input.
+#[cfg(feature = "smart-crop")]
+enum FrameStep<'a> {
+    /// A viewport resolved against the current frame.
+    Region(Region),
+    /// A dimension effect applied to the current frame.
+    Effect(&'a dyn crate::dimension::DimensionEffect),
+}
+
+/// How smart crop places `constraint`, after carrying `input` through
+/// `orientation` and `steps` into the constraint's input frame.
+///
+/// With `fixed_window`, a gravity placement is returned as the crop window
+/// it positions instead. `None` when the constraint does not crop or a step
+/// cannot map points.
+#[cfg(feature = "smart-crop")]
+#[allow(clippy::too_many_arguments)]
+fn smart_placement(
+    input: &crate::smart_crop::SmartCropInput,
+    config: &crate::smart_crop::CropConfig,
+    source_w: u32,
+    source_h: u32,
+    orientation: Orientation,
+    steps: &[FrameStep<'_>],
+    constraint: &Constraint,
+    fixed_window: bool,
+) -> Option<Placement> {
+    let oriented = orientation.transform_dimensions(source_w, source_h);
+    let mut view = input.oriented(orientation, source_w, source_h);
+    let (mut w, mut h) = (oriented.width, oriented.height);
+    for step in steps {
+        match *step {
+            FrameStep::Region(reg) => {
+                let (l, t, r, b) = reg.resolve(w, h);
+                if r <= l || b <= t {
+                    return None;
+                }
+                let to = ((r - l) as u32, (b - t) as u32);
+                view = view.reframed(l, t, (w, h), to);
+                (w, h) = to;
+            }
+            FrameStep::Effect(effect) => {
+                let (nw, nh) = effect.forward(w, h)?;
+                view = view.through_effect(effect, w, h)?;
+                (w, h) = (nw, nh);
+            }
+        }
+    }
+    let mut origin = (0, 0);
+    if let Some(sc) = &constraint.source_crop {
+        let crop = sc.resolve(w, h);
+        view = view.cropped(crop, w, h);
+        (w, h) = (crop.width, crop.height);
+        origin = (crop.x, crop.y);
+    }
+    if config.mode != crate::smart_crop::CropMode::Minimal {
+        let (tw, th) = match (constraint.width, constraint.height) {
+            (Some(tw), Some(th)) if tw > 0 && th > 0 => (tw, th),
+            _ => (w, h),
+        };
+        let zoom = crate::smart_crop::CropConfig {
+            target_aspect: crate::smart_crop::AspectRatio { w: tw, h: th },
+            ..config.clone()
+        };
+        let r = view.compute_crop(w, h, &zoom)?;
+        let crop = Rect::new(origin.0 + r.x, origin.1 + r.y, r.width, r.height);
+        return Some(Placement::SourceCrop(SourceCrop::Pixels(crop)));
+    }
+    let window = constraint.crop_window(w, h)?;
+    let gravity = view.crop_gravity(w, h, window, config)?;
+    if !fixed_window {
+        return Some(Placement::Gravity(gravity));
+    }
+    let (x, y) = crate::constraint::gravity_offset(w, h, window.width, window.height, &gravity);
+    let crop = Rect::new(
+        origin.0 + x as u32,
+        origin.1 + y as u32,
+        window.width,
+        window.height,
+    );
+    Some(Placement::SourceCrop(SourceCrop::Pixels(crop)))
+}
+
+/// [`smart_placement`] for the last constraint of a command sequence,
+/// following [`compute_layout_sequential()`]: orientations fuse, effects and
+/// crops before the first constraint shape its input in command order, and a
+/// rotation after the last constraint swaps its target.
+#[cfg(feature = "smart-crop")]
+fn sequential_smart_placement(
+    commands: &[Command],
+    source_w: u32,
+    source_h: u32,
+    input: &crate::smart_crop::SmartCropInput,
+    config: &crate::smart_crop::CropConfig,
+    fixed_window: bool,
+) -> Option<Placement> {
+    let mut orientation = Orientation::Identity;
+    let mut post_orientation = Orientation::Identity;
+    let mut steps = Vec::new();
+    let mut constraint: Option<&Constraint> = None;
+    for cmd in commands {
+        let o = match cmd {
+            Command::AutoOrient(exif) => Orientation::from_exif(*exif),
+            Command::Rotate(Rotation::Rotate90) => Some(Orientation::Rotate90),
+            Command::Rotate(Rotation::Rotate180) => Some(Orientation::Rotate180),
+            Command::Rotate(Rotation::Rotate270) => Some(Orientation::Rotate270),
+            Command::Flip(FlipAxis::Horizontal) => Some(Orientation::FlipH),
+            Command::Flip(FlipAxis::Vertical) => Some(Orientation::FlipV),
+            _ => None,
+        };
+        if let Some(o) = o {
+            orientation = orientation.compose(o);
+            post_orientation = post_orientation.compose(o);
+            continue;
+        }
+        let before = constraint.is_none();
+        match cmd {
+            Command::Crop(sc) if before => steps.push(FrameStep::Region(sc.to_region())),
+            Command::Region(r) if before => steps.push(FrameStep::Region(*r)),
+            Command::Pad(p)
+                if before && !steps.iter().any(|s| matches!(s, FrameStep::Region(_))) =>
+            {
+                steps.push(FrameStep::Region(Region {
+                    left: RegionCoord::px(-(p.left as i32)),
+                    top: RegionCoord::px(-(p.top as i32)),
+                    right: RegionCoord::pct_px(1.0, p.right as i32),
+                    bottom: RegionCoord::pct_px(1.0, p.bottom as i32),
+                    color: p.color,
+                }))
+            }
+            Command::Effect(e) if before => steps.push(FrameStep::Effect(e.as_ref())),
+            Command::Constrain(c) => {
+                constraint = Some(c);
+                post_orientation = Orientation::Identity;
+            }
+            _ => {}
+        }
+    }
+    let mut constraint = constraint?.clone();
+    if post_orientation.swaps_axes() {
+        (constraint.width, constraint.height) = (constraint.height, constraint.width);
+    }
+    smart_placement(
+        input,
+        config,
+        source_w,
+        source_h,
+        orientation,
+        &steps,
+        &constraint,
+        fixed_window,
+    )
 }
 
 impl IdealLayout {
@@ -1235,6 +2003,103 @@ impl IdealLayout {
         finalize(self, request, offer)
     }
 
+    /// Continue planning once an analysis barrier's result is known.
+    ///
+    /// Effects whose [`forward()`](crate::DimensionEffect::forward) returns
+    /// `None` (e.g. [`TrimEffect`](crate::TrimEffect)) stop the planner from
+    /// predicting anything downstream: they are recorded in
+    /// [`effects`](Self::effects) with `output_dims == input_dims`. After the
+    /// execution engine's `Analyze` step, pass the result here together with
+    /// the barrier's [`ResolvedEffect::command_index`]. The barrier command
+    /// is replaced by a [`ResolvedBarrier`](crate::ResolvedBarrier) and the
+    /// commands are re-planned with [`compute_layout_sequential()`], so every
+    /// downstream constraint, region and effect sees the real dimensions.
+    ///
+    /// `commands`, `source_w`, `source_h` and `limits` must be the ones this
+    /// layout was planned from. A [`BarrierResolution::Rect`](crate::BarrierResolution::Rect)
+    /// is clamped to the barrier's input dimensions.
+    ///
+    /// # Errors
+    ///
+    /// [`LayoutError::NotABarrier`] if `command_index` does not name an
+    /// unresolved barrier in this layout; [`LayoutError::ZeroRegionDimension`]
+    /// if the resolution is empty.
+    ///
+    /// ```
+    /// use zenlayout::{
+    ///     BarrierResolution, Command, Constraint, ConstraintMode, Rect, Size, TrimEffect,
+    ///     compute_layout_sequential,
+    /// };
+    ///
+    /// let commands = [
+    ///     Command::Effect(Box::new(TrimEffect { estimated_margin_percent: 0.05 })),
+    ///     Command::Constrain(Constraint::new(ConstraintMode::Fit, 400, 400)),
+    /// ];
+    /// let (ideal, _) = compute_layout_sequential(&commands, 1000, 800, None).unwrap();
+    /// let barrier = ideal.effects[0].command_index;
+    ///
+    /// // Analysis found content at (100, 100) sized 800×400.
+    /// let (ideal, request) = ideal
+    ///     .resume_after_barrier(
+    ///         &commands,
+    ///         1000,
+    ///         800,
+    ///         None,
+    ///         barrier,
+    ///         BarrierResolution::Rect(Rect::new(100, 100, 800, 400)),
+    ///     )
+    ///     .unwrap();
+    /// assert_eq!(ideal.effects[0].output_dims, Size::new(800, 400));
+    /// assert_eq!(request.target_size, Size::new(400, 200));
+    /// ```
+    #[track_caller]
+    pub fn resume_after_barrier(
+        &self,
+        commands: &[Command],
+        source_w: u32,
+        source_h: u32,
+        limits: Option<&OutputLimits>,
+        command_index: usize,
+        resolution: crate::dimension::BarrierResolution,
+    ) -> Result<(IdealLayout, DecoderRequest), At<LayoutError>> {
+        use crate::dimension::{BarrierResolution, ResolvedBarrier};
+
+        let barrier = self
+            .effects
+            .iter()
+            .find(|e| e.command_index == command_index)
+            .filter(|e| e.kind() == crate::dimension::EffectKind::AnalysisBarrier)
+            .ok_or_else(|| at!(LayoutError::NotABarrier))?;
+        if !matches!(commands.get(command_index), Some(Command::Effect(_))) {
+            return Err(at!(LayoutError::NotABarrier));
+        }
+
+        let input = barrier.input_dims;
+        let resolution = match resolution {
+            BarrierResolution::Dims(s) => {
+                if s.width == 0 || s.height == 0 {
+                    return Err(at!(LayoutError::ZeroRegionDimension));
+                }
+                BarrierResolution::Dims(s)
+            }
+            BarrierResolution::Rect(r) => {
+                let x = r.x.min(input.width);
+                let y = r.y.min(input.height);
+                let w = r.width.min(input.width - x);
+                let h = r.height.min(input.height - y);
+                if w == 0 || h == 0 {
+                    return Err(at!(LayoutError::ZeroRegionDimension));
+                }
+                BarrierResolution::Rect(Rect::new(x, y, w, h))
+            }
+        };
+
+        let mut resumed: Vec<Command> = commands.to_vec();
+        resumed[command_index] =
+            Command::Effect(alloc::boxed::Box::new(ResolvedBarrier { resolution }));
+        compute_layout_sequential(&resumed, source_w, source_h, limits)
+    }
+
     /// Derive an `(IdealLayout, DecoderRequest)` for a secondary plane that must
     /// stay spatially locked with the primary plane.
     ///
@@ -1262,6 +2127,14 @@ impl IdealLayout {
     /// secondary plane always covers at least the full spatial extent of the
     /// primary crop. The renderer handles any sub-pixel offset.
     ///
+    /// Everything downstream of the resize — canvas, placement, padding,
+    /// content size, and post-resize effects — is scaled by the ratio of
+    /// secondary to primary output size, rounding to nearest. The canvas is
+    /// rebuilt from the scaled margins around the content, so the content
+    /// and padding always fit inside it. Effects are
+    /// rescaled with [`DimensionEffect::scaled()`](crate::DimensionEffect::scaled)
+    /// and their dimensions re-derived at the secondary scale.
+    ///
     /// # Example
     ///
     /// ```
@@ -1309,7 +2182,44 @@ impl IdealLayout {
 
         // Compute the oriented secondary source dimensions.
         let sec_o = self.orientation.transform_dimensions(ss_w, ss_h);
-        let (sec_ow, sec_oh) = (sec_o.width, sec_o.height);
+        let pri_o = self.orientation.transform_dimensions(ps_w, ps_h);
+        let pre_scale_x = sec_o.width as f64 / pri_o.width.max(1) as f64;
+        let pre_scale_y = sec_o.height as f64 / pri_o.height.max(1) as f64;
+
+        // Walk pre-resize effects at secondary scale. The layout's source is
+        // the post-effect size, so the crop must be scaled against that. The
+        // first effect sees the decoder crop, or a scaled copy of whatever
+        // the primary's first effect saw (e.g. a padded crop).
+        let oriented_crop = |c: Option<Rect>, full: Size| {
+            c.map_or(full, |c| {
+                self.orientation.transform_dimensions(c.width, c.height)
+            })
+        };
+        let pri_content = oriented_crop(self.source_crop, pri_o);
+        let mut current = match self.effects.iter().find(|e| e.before_resize) {
+            Some(e) if e.input_dims != pri_content => Size::new(
+                (e.input_dims.width as f64 * pre_scale_x).round().max(1.0) as u32,
+                (e.input_dims.height as f64 * pre_scale_y).round().max(1.0) as u32,
+            ),
+            _ => oriented_crop(secondary_crop, sec_o),
+        };
+        let mut effects = Vec::with_capacity(self.effects.len());
+        for e in self.effects.iter().filter(|e| e.before_resize) {
+            let effect = e.effect.scaled(pre_scale_x, pre_scale_y);
+            let input_dims = current;
+            let output_dims = effect
+                .forward(input_dims.width, input_dims.height)
+                .map_or(input_dims, |(w, h)| Size::new(w, h));
+            effects.push(ResolvedEffect {
+                effect,
+                input_dims,
+                output_dims,
+                command_index: e.command_index,
+                before_resize: true,
+            });
+            current = output_dims;
+        }
+        let (sec_ow, sec_oh) = (current.width, current.height);
 
         // Scale the layout's source crop (in post-orientation coords) with round-outward.
         // Use the oriented scale factors.
@@ -1321,12 +2231,6 @@ impl IdealLayout {
             .source_crop
             .map(|crop| scale_rect_outward(crop, orient_scale_x, orient_scale_y, sec_ow, sec_oh));
 
-        // Effective source after crop in oriented space.
-        let (eff_w, eff_h) = match &secondary_layout_crop {
-            Some(r) => (r.width, r.height),
-            None => (sec_ow, sec_oh),
-        };
-
         // Compute target dimensions for the secondary plane.
         let (target_w, target_h) = match secondary_target {
             Some(t) => (t.width, t.height),
@@ -1339,26 +2243,92 @@ impl IdealLayout {
             }
         };
 
+        // Everything after the resize (canvas, placement, padding, post-resize
+        // effects) scales with the output ratio.
+        let out_scale_x = target_w as f64 / self.layout.resize_to.width.max(1) as f64;
+        let out_scale_y = target_h as f64 / self.layout.resize_to.height.max(1) as f64;
+        let scale_len = |v: u32, s: f64| (v as f64 * s).round() as u32;
+        let scale_size = |sz: Size| {
+            Size::new(
+                scale_len(sz.width, out_scale_x).max(1),
+                scale_len(sz.height, out_scale_y).max(1),
+            )
+        };
+
+        // Post-resize effects chain like the primary's: an effect that took
+        // the resized image or the previous effect's output keeps doing so.
+        let mut prev = (self.layout.resize_to, Size::new(target_w, target_h));
+        for e in self.effects.iter().filter(|e| !e.before_resize) {
+            let effect = e.effect.scaled(out_scale_x, out_scale_y);
+            let input_dims = if e.input_dims == prev.0 {
+                prev.1
+            } else {
+                scale_size(e.input_dims)
+            };
+            let output_dims = effect
+                .forward(input_dims.width, input_dims.height)
+                .map_or(input_dims, |(w, h)| Size::new(w, h));
+            prev = (e.output_dims, output_dims);
+            effects.push(ResolvedEffect {
+                effect,
+                input_dims,
+                output_dims,
+                command_index: e.command_index,
+                before_resize: false,
+            });
+        }
+        // Keep pipeline order: pre-resize effects were collected first but
+        // the primary may interleave them by command index.
+        effects.sort_by_key(|e| e.command_index);
+
+        // Scale the margins on each side of the content rather than the
+        // canvas as a whole, so placement + resize_to and the padding still
+        // fit the canvas after rounding.
+        let (px, py) = self.layout.placement;
+        let place_x = (px as f64 * out_scale_x).round() as i64;
+        let place_y = (py as f64 * out_scale_y).round() as i64;
+        let margin = |canvas: u32, place: i32, resize: u32, s: f64| {
+            ((canvas as i64 - place as i64 - resize as i64) as f64 * s).round() as i64
+        };
+        let right = margin(
+            self.layout.canvas.width,
+            px,
+            self.layout.resize_to.width,
+            out_scale_x,
+        );
+        let bottom = margin(
+            self.layout.canvas.height,
+            py,
+            self.layout.resize_to.height,
+            out_scale_y,
+        );
         let sec_layout = Layout {
             source: Size::new(sec_ow, sec_oh),
             source_crop: secondary_layout_crop,
             resize_to: Size::new(target_w, target_h),
-            canvas: Size::new(target_w, target_h),
-            placement: (0, 0),
-            canvas_color: CanvasColor::default(),
+            canvas: Size::new(
+                (place_x + target_w as i64 + right).max(1) as u32,
+                (place_y + target_h as i64 + bottom).max(1) as u32,
+            ),
+            placement: (place_x as i32, place_y as i32),
+            canvas_color: self.layout.canvas_color,
         };
 
-        // Effective source is the crop region (or full secondary if no crop).
-        // resize_is_identity will be computed by finalize().
-        let _ = (eff_w, eff_h);
+        let padding = self.padding.map(|p| Padding {
+            top: scale_len(p.top, out_scale_y),
+            right: scale_len(p.right, out_scale_x),
+            bottom: scale_len(p.bottom, out_scale_y),
+            left: scale_len(p.left, out_scale_x),
+            color: p.color,
+        });
 
         let sec_ideal = IdealLayout {
             orientation: self.orientation,
             layout: sec_layout,
             source_crop: secondary_crop,
-            padding: None, // secondary planes don't get padded
-            content_size: None,
-            effects: Vec::new(),
+            padding,
+            content_size: self.content_size.map(scale_size),
+            effects,
         };
 
         let sec_request = DecoderRequest {
@@ -1367,10 +2337,198 @@ impl IdealLayout {
             orientation: self.orientation,
         };
 
-        (sec_ideal, sec_request)
+        (sec_ideal, sec_request)
+    }
+
+    /// Pick which stored resolution of a multi-resolution source to decode.
+    ///
+    /// Containers such as ICO/ICNS, HEIF (thumbnails), EXIF, pyramidal TIFF
+    /// and JPEG XL (previews) hold several renditions of the same image.
+    /// This picks the smallest variant whose pixels over the source crop
+    /// are at least `min_oversampling` times the resize target on both
+    /// axes. Ties in area go to the higher [`SourceVariant::quality`]. If no
+    /// variant is large enough, the one with the most pixels is chosen and
+    /// [`VariantChoice::sufficient`] is false.
+    ///
+    /// `primary_source` is the pre-orientation size the layout was planned
+    /// against. Variants must cover the same spatial extent; ones whose
+    /// aspect ratio differs from the primary by more than rounding are
+    /// skipped. Returns `None` if no variant is usable.
+    ///
+    /// The returned layout keeps the resize target, canvas, placement,
+    /// padding and effects; only the source and crop are rescaled (rounding
+    /// outward, as in [`derive_secondary()`](Self::derive_secondary)).
+    ///
+    /// ```
+    /// use zenlayout::{Pipeline, Size, SourceVariant};
+    ///
+    /// let (ideal, _) = Pipeline::new(4000, 3000).fit(400, 300).plan().unwrap();
+    /// let variants = [
+    ///     SourceVariant::new(4000, 3000, 100),
+    ///     SourceVariant::new(1024, 768, 100),
+    ///     SourceVariant::new(160, 120, 50),
+    /// ];
+    /// let choice = ideal
+    ///     .select_variant(Size::new(4000, 3000), &variants, 2.0)
+    ///     .unwrap();
+    /// assert_eq!(choice.index, 1);
+    /// assert_eq!(choice.request.target_size, Size::new(400, 300));
+    /// ```
+    pub fn select_variant(
+        &self,
+        primary_source: Size,
+        variants: &[SourceVariant],
+        min_oversampling: f32,
+    ) -> Option<VariantChoice> {
+        let (ps_w, ps_h) = (primary_source.width.max(1), primary_source.height.max(1));
+        let crop = self.source_crop.unwrap_or(Rect::new(0, 0, ps_w, ps_h));
+        // Resize target in pre-orientation axes.
+        let target = self
+            .orientation
+            .transform_dimensions(self.layout.resize_to.width, self.layout.resize_to.height);
+        let min_over = if min_oversampling.is_finite() {
+            min_oversampling.max(0.0) as f64
+        } else {
+            1.0
+        };
+
+        let oversampling = |v: &SourceVariant| -> f64 {
+            let ox =
+                crop.width as f64 * v.size.width as f64 / ps_w as f64 / target.width.max(1) as f64;
+            let oy = crop.height as f64 * v.size.height as f64
+                / ps_h as f64
+                / target.height.max(1) as f64;
+            ox.min(oy)
+        };
+        let area = |v: &SourceVariant| v.size.width as u64 * v.size.height as u64;
+
+        let mut best: Option<(usize, f64, bool)> = None;
+        for (i, v) in variants.iter().enumerate() {
+            if v.size.width == 0 || v.size.height == 0 {
+                continue;
+            }
+            // Same extent → same aspect, within one pixel of rounding.
+            let expected_h = v.size.width as f64 * ps_h as f64 / ps_w as f64;
+            let expected_w = v.size.height as f64 * ps_w as f64 / ps_h as f64;
+            if (v.size.height as f64 - expected_h).abs() > 1.0
+                && (v.size.width as f64 - expected_w).abs() > 1.0
+            {
+                continue;
+            }
+            let over = oversampling(v);
+            let ok = over >= min_over;
+            let better = match best {
+                None => true,
+                Some((bi, _, b_ok)) => {
+                    let b = &variants[bi];
+                    match (ok, b_ok) {
+                        (true, false) => true,
+                        (false, true) => false,
+                        // Both sufficient: smallest wins. Neither: largest wins.
+                        _ => {
+                            let (a, ba) = (area(v), area(b));
+                            if a == ba {
+                                v.quality > b.quality
+                            } else {
+                                (a < ba) == ok
+                            }
+                        }
+                    }
+                }
+            };
+            if better {
+                best = Some((i, over, ok));
+            }
+        }
+
+        let (index, oversampling, sufficient) = best?;
+        let v = variants[index].size;
+        let (ideal, request) = self.rescale_source(primary_source, v);
+        Some(VariantChoice {
+            index,
+            ideal,
+            request,
+            oversampling,
+            sufficient,
+        })
+    }
+
+    /// Same layout against a differently sized copy of the source.
+    fn rescale_source(&self, primary_source: Size, source: Size) -> (IdealLayout, DecoderRequest) {
+        let scale_x = source.width as f64 / primary_source.width.max(1) as f64;
+        let scale_y = source.height as f64 / primary_source.height.max(1) as f64;
+        let source_crop = self
+            .source_crop
+            .map(|crop| scale_rect_outward(crop, scale_x, scale_y, source.width, source.height));
+
+        let oriented = self
+            .orientation
+            .transform_dimensions(source.width, source.height);
+        let orient_scale_x = oriented.width as f64 / self.layout.source.width.max(1) as f64;
+        let orient_scale_y = oriented.height as f64 / self.layout.source.height.max(1) as f64;
+
+        let mut ideal = self.clone();
+        ideal.source_crop = source_crop;
+        ideal.layout.source = oriented;
+        ideal.layout.source_crop = self.layout.source_crop.map(|crop| {
+            scale_rect_outward(
+                crop,
+                orient_scale_x,
+                orient_scale_y,
+                oriented.width,
+                oriented.height,
+            )
+        });
+
+        let request = DecoderRequest {
+            crop: source_crop,
+            target_size: self.layout.resize_to,
+            orientation: self.orientation,
+        };
+        (ideal, request)
+    }
+}
+
+/// One stored resolution of a multi-resolution source.
+///
+/// See [`IdealLayout::select_variant()`].
+#[non_exhaustive]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SourceVariant {
+    /// Pixel dimensions (pre-orientation).
+    pub size: Size,
+    /// Relative quality rank; higher is better. Breaks ties between
+    /// variants of equal size (e.g. a PNG icon entry over a 16-color BMP one).
+    pub quality: u32,
+}
+
+impl SourceVariant {
+    /// Create a variant.
+    pub const fn new(width: u32, height: u32, quality: u32) -> Self {
+        Self {
+            size: Size::new(width, height),
+            quality,
+        }
     }
 }
 
+/// Result of [`IdealLayout::select_variant()`].
+#[non_exhaustive]
+#[derive(Clone, Debug)]
+pub struct VariantChoice {
+    /// Index into the variant slice.
+    pub index: usize,
+    /// Layout rescaled to the chosen variant.
+    pub ideal: IdealLayout,
+    /// Decoder request for the chosen variant.
+    pub request: DecoderRequest,
+    /// Achieved oversampling: variant pixels over the crop divided by the
+    /// resize target, on the tighter axis.
+    pub oversampling: f64,
+    /// Whether `oversampling` meets the requested minimum.
+    pub sufficient: bool,
+}
+
 /// Scale a rect from one coordinate space to another, rounding outward.
 ///
 /// Round `v` to the nearest multiple of `n`. Ties round up.
@@ -1403,6 +2561,22 @@ fn scale_rect_outward(rect: Rect, scale_x: f64, scale_y: f64, max_w: u32, max_h:
 /// First-wins: only the first `Crop`/`Region`, `Constrain`, and `Pad` are used;
 /// later duplicates are ignored.
 ///
+/// Effects are not first-wins; every [`Command::Effect`] is used, in command
+/// order, in one of two fixed slots:
+///
+/// ```text
+///     orient → crop/region → [pre-resize effects] → constrain → pad → [post-resize effects] → limits
+/// ```
+///
+/// Effects listed before the first `Constrain` go in the pre-resize slot;
+/// effects after it go in the post-resize slot. Without a constraint, all
+/// effects are pre-resize. Pre-resize effects transform the cropped
+/// content (a region's padding is added around the transformed content),
+/// and the constraint sees their output. When any are present, `layout`
+/// describes the post-effect image: `layout.source` is the effects' output
+/// and `layout.source_crop` is a crop of it, while
+/// [`IdealLayout::source_crop`] remains the decoder crop.
+///
 /// For a friendlier API, see [`Pipeline`].
 #[track_caller]
 pub fn compute_layout(
@@ -1416,8 +2590,10 @@ pub fn compute_layout(
     let mut region: Option<Region> = None;
     let mut constraint: Option<&Constraint> = None;
     let mut padding: Option<Padding> = None;
+    let mut pre_effects: Vec<(usize, &dyn crate::dimension::DimensionEffect)> = Vec::new();
+    let mut post_effects: Vec<(usize, &dyn crate::dimension::DimensionEffect)> = Vec::new();
 
-    for cmd in commands {
+    for (cmd_idx, cmd) in commands.iter().enumerate() {
         match cmd {
             Command::AutoOrient(exif) => {
                 if let Some(o) = Orientation::from_exif(*exif) {
@@ -1459,10 +2635,13 @@ pub fn compute_layout(
                     padding = Some(*p);
                 }
             }
-            Command::Effect(_) => {
-                // Effects are handled by compute_layout_sequential.
-                // In first-wins mode (compute_layout), effects are ignored
-                // because there's no defined ordering.
+            Command::Effect(e) => {
+                // Slot is decided by position relative to the first constraint.
+                if constraint.is_none() {
+                    pre_effects.push((cmd_idx, e.as_ref()));
+                } else {
+                    post_effects.push((cmd_idx, e.as_ref()));
+                }
             }
         }
     }
@@ -1476,6 +2655,8 @@ pub fn compute_layout(
         constraint,
         padding,
         limits,
+        &pre_effects,
+        &post_effects,
     )
 }
 
@@ -1487,7 +2668,23 @@ pub fn compute_layout(
 /// - Crop/region commands before the first constraint compose sequentially
 /// - Last constraint wins
 /// - Post-constraint crop/region/pad adjusts the output canvas
+/// - Effects before the first constraint run before the resize, and the
+///   constraint sees their output; effects between two constraints are
+///   discarded along with the other post-constraint operations
 /// - Limits are applied once at the end
+/// - Adjacent matrix-expressible effects (see
+///   [`DimensionEffect::warp_matrix()`](crate::DimensionEffect::warp_matrix))
+///   fuse into one [`FusedEffect`](crate::FusedEffect), so they resample once,
+///   unless a crop separates them or an earlier one clips what a later one
+///   would fill back in
+/// - Cardinal rotations and flips at the start of the pre-constraint effects
+///   fold into the orientation and disappear from [`IdealLayout::effects`]
+///   (unless a crop or region precedes them)
+/// - Pre-constraint crops and regions keep their place among the effects:
+///   those before the first effect crop the content the effects see and
+///   become the decoder crop; those between effects are recorded as
+///   zero-angle [`RotatedCropEffect`](crate::RotatedCropEffect)s; those after
+///   the last effect frame its output for the constraint
 ///
 /// For a friendlier builder API, see [`Pipeline`].
 #[track_caller]
@@ -1501,7 +2698,7 @@ pub fn compute_layout_sequential(
 
     // Phase 1: Partition commands into pre-constrain and post-constrain groups.
     let mut orientation = Orientation::Identity;
-    let mut pre_regions: Vec<Region> = Vec::new();
+    let mut pre_regions: Vec<(usize, Region)> = Vec::new();
     let mut constraint: Option<&Constraint> = None;
     let mut post_ops: Vec<(usize, &Command)> = Vec::new();
     let mut saw_constrain = false;
@@ -1547,14 +2744,14 @@ pub fn compute_layout_sequential(
                 if saw_constrain {
                     post_ops.push((cmd_idx, cmd));
                 } else {
-                    pre_regions.push(sc.to_region());
+                    pre_regions.push((cmd_idx, sc.to_region()));
                 }
             }
             Command::Region(r) => {
                 if saw_constrain {
                     post_ops.push((cmd_idx, cmd));
                 } else {
-                    pre_regions.push(*r);
+                    pre_regions.push((cmd_idx, *r));
                 }
             }
             Command::Constrain(c) => {
@@ -1565,19 +2762,21 @@ pub fn compute_layout_sequential(
                 constraint = Some(c); // last wins
                 saw_constrain = true;
                 post_ops.clear(); // reset post-ops on each new constrain
-                pre_effects.clear(); // pre-effects reset with new constrain
             }
             Command::Pad(p) => {
                 if saw_constrain || !pre_regions.is_empty() {
                     post_ops.push((cmd_idx, cmd));
                 } else {
-                    pre_regions.push(Region {
-                        left: RegionCoord::px(-(p.left as i32)),
-                        top: RegionCoord::px(-(p.top as i32)),
-                        right: RegionCoord::pct_px(1.0, p.right as i32),
-                        bottom: RegionCoord::pct_px(1.0, p.bottom as i32),
-                        color: p.color,
-                    });
+                    pre_regions.push((
+                        cmd_idx,
+                        Region {
+                            left: RegionCoord::px(-(p.left as i32)),
+                            top: RegionCoord::px(-(p.top as i32)),
+                            right: RegionCoord::pct_px(1.0, p.right as i32),
+                            bottom: RegionCoord::pct_px(1.0, p.bottom as i32),
+                            color: p.color,
+                        },
+                    ));
                 }
             }
             Command::Effect(e) => {
@@ -1613,43 +2812,133 @@ pub fn compute_layout_sequential(
         return Err(at!(LayoutError::ZeroSourceDimension));
     }
 
-    // Phase 2: Compose pre-constrain regions into a single effective region.
+    // Leading effects that amount to a cardinal rotation or flip are free
+    // as part of the orientation, unless a crop comes before them.
+    let first_region = pre_regions.first().map_or(usize::MAX, |&(idx, _)| idx);
+    while let Some(&(idx, effect)) = pre_effects.first() {
+        if idx > first_region {
+            break;
+        }
+        let dims = orientation.transform_dimensions(source_w, source_h);
+        let Some(o) = crate::dimension::effect_orientation(effect, dims.width, dims.height) else {
+            break;
+        };
+        orientation = orientation.compose(o);
+        pre_effects.remove(0);
+    }
+
+    // Phase 2: Compose pre-constrain regions and walk pre-constrain effects.
     let oriented = orientation.transform_dimensions(source_w, source_h);
     let (ow, oh) = (oriented.width, oriented.height);
 
-    let effective_region = if pre_regions.is_empty() {
-        None
-    } else {
-        // Compose regions sequentially: each region operates on the effective
-        // source from the previous step. The first region operates on the
-        // full oriented source. Subsequent regions treat the previous region's
-        // viewport as their source coordinate system.
-        let mut composed = pre_regions[0];
-        for next in &pre_regions[1..] {
-            composed = compose_regions(composed, *next, ow, oh);
-        }
-        Some(composed)
+    // Regions before the first effect crop the content the effects see;
+    // later regions act on the effects' output. Each region operates on the
+    // effective source from the previous step: the first on the full
+    // oriented source, later ones in the previous region's viewport.
+    let first_effect = pre_effects.first().map_or(usize::MAX, |&(idx, _)| idx);
+    let compose = |regions: &mut dyn Iterator<Item = Region>, w: u32, h: u32| {
+        let first = regions.next()?;
+        Some(regions.fold(first, |outer, inner| compose_regions(outer, inner, w, h)))
     };
+    let leading = compose(
+        &mut pre_regions
+            .iter()
+            .filter(|&&(idx, _)| idx < first_effect)
+            .map(|&(_, r)| r),
+        ow,
+        oh,
+    );
+
+    let mut resolved_effects: Vec<ResolvedEffect> = Vec::new();
+    let (effective_region, content_crop, (eff_w, eff_h)) = if pre_effects.is_empty() {
+        (leading, None, (ow, oh))
+    } else {
+        // The leading region's overlap with the source is the decoder crop;
+        // any padding it adds becomes the first effect.
+        let (content_crop, content, leading_pad) = match leading {
+            Some(reg) => {
+                let viewport = resolve_region(reg, ow, oh, None)?;
+                let (px, py) = viewport.placement;
+                let pad = (px, py, viewport.canvas) != (0, 0, viewport.resize_to);
+                let idx = pre_regions
+                    .iter()
+                    .rev()
+                    .find(|&&(idx, _)| idx < first_effect)
+                    .map_or(0, |&(idx, _)| idx);
+                let padded = pad.then(|| {
+                    let c = viewport.canvas;
+                    (idx, region_effect(-px, -py, c.width, c.height, reg.color))
+                });
+                (viewport.source_crop, viewport.resize_to, padded)
+            }
+            None => (None, Size::new(ow, oh), None),
+        };
 
-    // Phase 2b: Apply pre-constrain effects to the effective source dims.
-    // Effects change the dimensions the constraint sees. For example, a
-    // pre-constrain inscribed-crop rotation shrinks the source, so the
-    // constraint computes a resize target relative to the smaller canvas.
-    let (eff_w, eff_h) = {
-        let mut cw = ow;
-        let mut ch = oh;
-        for &(_, effect) in &pre_effects {
-            if let Some((nw, nh)) = effect.forward(cw, ch) {
-                cw = nw;
-                ch = nh;
+        // Walk effects in command order. A region followed by another
+        // effect becomes a zero-angle crop effect so it keeps its place.
+        let mut current = content;
+        let mut push = |idx: usize,
+                        effect: &dyn crate::dimension::DimensionEffect,
+                        current: &mut Size|
+         -> Result<(), At<LayoutError>> {
+            let input_dims = *current;
+            let output_dims = plan_effect(effect, input_dims.width, input_dims.height)?
+                .map_or(input_dims, |(w, h)| Size::new(w, h));
+            resolved_effects.push(ResolvedEffect {
+                effect: effect.clone_boxed(),
+                input_dims,
+                output_dims,
+                command_index: idx,
+                before_resize: true,
+            });
+            *current = output_dims;
+            Ok(())
+        };
+        if let Some((idx, effect)) = &leading_pad {
+            push(*idx, effect, &mut current)?;
+        }
+        let mut pending: Option<(usize, Region)> = None;
+        let mut trailing = pre_regions
+            .iter()
+            .filter(|&&(idx, _)| idx > first_effect)
+            .peekable();
+        for &(idx, effect) in &pre_effects {
+            while let Some(&&(ridx, r)) = trailing.peek().filter(|&&&(ridx, _)| ridx < idx) {
+                trailing.next();
+                pending = Some(match pending {
+                    Some((_, outer)) => (
+                        ridx,
+                        compose_regions(outer, r, current.width, current.height),
+                    ),
+                    None => (ridx, r),
+                });
+            }
+            if let Some((ridx, reg)) = pending.take() {
+                let (l, t, r, b) = reg.resolve(current.width, current.height);
+                if r <= l || b <= t {
+                    return Err(at!(LayoutError::ZeroRegionDimension));
+                }
+                let crop = region_effect(l, t, (r - l) as u32, (b - t) as u32, reg.color);
+                push(ridx, &crop, &mut current)?;
             }
-            // Analysis barriers don't change tracked dims.
+            push(idx, effect, &mut current)?;
         }
-        (cw, ch)
+        let trailing = compose(
+            &mut pending
+                .map(|(_, r)| r)
+                .into_iter()
+                .chain(trailing.map(|&(_, r)| r)),
+            current.width,
+            current.height,
+        );
+        (trailing, content_crop, (current.width, current.height))
     };
 
     // Phase 3: Compute layout from effective region + constraint.
-    // The constraint sees post-effect dimensions (eff_w, eff_h).
+    // The constraint sees post-effect dimensions (eff_w, eff_h). For
+    // example, a pre-constrain inscribed-crop rotation shrinks the source,
+    // so the constraint computes a resize target relative to the smaller
+    // canvas.
     let layout = if let Some(reg) = effective_region {
         resolve_region(reg, eff_w, eff_h, constraint)?
     } else if let Some(c) = constraint {
@@ -1668,36 +2957,6 @@ pub fn compute_layout_sequential(
     // Phase 4: Apply post-constrain ops to the canvas and resolve effects.
     let mut layout = layout;
     let mut pad_applied = false;
-    let mut resolved_effects: Vec<ResolvedEffect> = Vec::new();
-
-    // Resolve pre-constrain effects with concrete dims from the forward
-    // walk we already did in Phase 2b.
-    {
-        let mut current = Size::new(ow, oh);
-        for &(idx, effect) in &pre_effects {
-            let input_dims = current;
-            if let Some((out_w, out_h)) = effect.forward(current.width, current.height) {
-                let output_dims = Size::new(out_w, out_h);
-                resolved_effects.push(ResolvedEffect {
-                    effect: effect.clone_boxed(),
-                    input_dims,
-                    output_dims,
-                    command_index: idx,
-                    before_resize: true,
-                });
-                current = output_dims;
-            } else {
-                // Analysis barrier: record with input=output placeholder.
-                resolved_effects.push(ResolvedEffect {
-                    effect: effect.clone_boxed(),
-                    input_dims,
-                    output_dims: input_dims,
-                    command_index: idx,
-                    before_resize: true,
-                });
-            }
-        }
-    }
 
     // Now apply post-constrain ops.
     for &(op_idx, op) in &post_ops {
@@ -1733,7 +2992,9 @@ pub fn compute_layout_sequential(
             Command::Effect(e) => {
                 // Post-constrain effect: apply to canvas dimensions.
                 let input_dims = layout.canvas;
-                if let Some((out_w, out_h)) = e.forward(input_dims.width, input_dims.height) {
+                if let Some((out_w, out_h)) =
+                    plan_effect(e.as_ref(), input_dims.width, input_dims.height)?
+                {
                     let output_dims = Size::new(out_w, out_h);
                     resolved_effects.push(ResolvedEffect {
                         effect: e.clone_boxed(),
@@ -1758,6 +3019,8 @@ pub fn compute_layout_sequential(
         }
     }
 
+    let resolved_effects = fuse_resolved_effects(commands, resolved_effects);
+
     // Build padding record for IdealLayout
     let padding = if pad_applied {
         post_ops.iter().rev().find_map(|(_, op)| match op {
@@ -1782,9 +3045,14 @@ pub fn compute_layout_sequential(
     };
 
     // Phase 6: Transform source crop back to pre-orientation source coordinates.
-    let source_crop_in_source = layout
-        .source_crop
-        .map(|r| orientation.transform_rect_to_source(r, source_w, source_h));
+    // With pre-constrain effects, the layout's crop is in post-effect space
+    // and the decoder crop is the content crop taken before the effects.
+    let source_crop_in_source = if pre_effects.is_empty() {
+        layout.source_crop
+    } else {
+        content_crop
+    }
+    .map(|r| orientation.transform_rect_to_source(r, source_w, source_h));
 
     let ideal = IdealLayout {
         orientation,
@@ -1804,6 +3072,84 @@ pub fn compute_layout_sequential(
     Ok((ideal, request))
 }
 
+/// Merge runs of adjacent matrix-expressible effects into
+/// [`FusedEffect`](crate::FusedEffect)s so each run resamples once.
+///
+/// Effects are adjacent when they sit on the same side of the resize, the
+/// dims chain, and no crop, region or pad command separates them. A crop or
+/// region planned as an effect is never fused, and a part that fills beyond
+/// its input does not join a run in which an earlier part clips.
+fn fuse_resolved_effects(
+    commands: &[Command],
+    effects: Vec<ResolvedEffect>,
+) -> Vec<ResolvedEffect> {
+    // Crops and regions the planner turned into effects stay separate, so
+    // the resample cannot reach past them.
+    let matrix = |e: &ResolvedEffect| {
+        if !matches!(commands.get(e.command_index), Some(Command::Effect(_))) {
+            return None;
+        }
+        e.effect
+            .warp_matrix(e.input_dims.width, e.input_dims.height)
+    };
+    let (i, o) = (
+        |e: &ResolvedEffect| e.input_dims,
+        |e: &ResolvedEffect| e.output_dims,
+    );
+    let clips = |e: &ResolvedEffect, m: &[f64; 9]| {
+        crate::dimension::warp_clips(m, i(e).width, i(e).height, o(e).width, o(e).height)
+    };
+    let fills = |e: &ResolvedEffect, m: &[f64; 9]| {
+        crate::dimension::warp_fills(m, i(e).width, i(e).height, o(e).width, o(e).height)
+    };
+    // Each run, with whether any part so far clips its input. A part that
+    // fills after one that clips would resample the clipped-away pixels.
+    let mut runs: Vec<(Vec<ResolvedEffect>, bool)> = Vec::new();
+    for next in effects {
+        let next_matrix = matrix(&next);
+        let joins = runs.last().is_some_and(|(run, clipped)| {
+            let prev = &run[run.len() - 1];
+            let Some(m) = next_matrix.as_ref() else {
+                return false;
+            };
+            matrix(prev).is_some()
+                && !(*clipped && fills(&next, m))
+                && prev.before_resize == next.before_resize
+                && prev.output_dims == next.input_dims
+                && !commands[prev.command_index + 1..next.command_index]
+                    .iter()
+                    .any(|c| matches!(c, Command::Crop(_) | Command::Region(_) | Command::Pad(_)))
+        });
+        let next_clips = next_matrix.as_ref().is_some_and(|m| clips(&next, m));
+        match runs.last_mut() {
+            Some((run, clipped)) if joins => {
+                *clipped |= next_clips;
+                run.push(next);
+            }
+            _ => runs.push((alloc::vec![next], next_clips)),
+        }
+    }
+    runs.into_iter()
+        .map(|(mut run, _)| {
+            if run.len() == 1 {
+                return run.remove(0);
+            }
+            let first = &run[0];
+            let (input_dims, command_index, before_resize) =
+                (first.input_dims, first.command_index, first.before_resize);
+            let output_dims = run[run.len() - 1].output_dims;
+            let parts = run.into_iter().map(|e| e.effect).collect();
+            ResolvedEffect {
+                effect: alloc::boxed::Box::new(crate::dimension::FusedEffect::new(parts)),
+                input_dims,
+                output_dims,
+                command_index,
+                before_resize,
+            }
+        })
+        .collect()
+}
+
 /// Compose two regions: `outer` defines a viewport, `inner` is relative to
 /// that viewport's coordinate system. Result is in the original source coords.
 fn compose_regions(outer: Region, inner: Region, source_w: u32, source_h: u32) -> Region {
@@ -1837,6 +3183,8 @@ fn plan_from_parts(
     constraint: Option<&Constraint>,
     padding: Option<Padding>,
     limits: Option<&OutputLimits>,
+    pre_effects: &[(usize, &dyn crate::dimension::DimensionEffect)],
+    post_effects: &[(usize, &dyn crate::dimension::DimensionEffect)],
 ) -> Result<(IdealLayout, DecoderRequest), At<LayoutError>> {
     if source_w == 0 || source_h == 0 {
         return Err(at!(LayoutError::ZeroSourceDimension));
@@ -1853,20 +3201,85 @@ fn plan_from_parts(
         region
     };
 
+    let mut resolved_effects: Vec<ResolvedEffect> = Vec::new();
+
     // 2. Compute layout in post-orientation space.
-    let layout = if let Some(reg) = effective_region {
-        resolve_region(reg, ow, oh, constraint)?
+    let (layout, decoder_crop) = if !pre_effects.is_empty() {
+        // Pre-resize slot: effects transform the cropped content, so the
+        // layout is computed against their output.
+        let (content_crop, content, region_pad) = match effective_region {
+            Some(reg) => {
+                let viewport = resolve_region(reg, ow, oh, None)?;
+                let content = viewport.resize_to;
+                let pad = (
+                    viewport.placement.0,
+                    viewport.placement.1,
+                    viewport.canvas.width as i32 - viewport.placement.0 - content.width as i32,
+                    viewport.canvas.height as i32 - viewport.placement.1 - content.height as i32,
+                );
+                (viewport.source_crop, content, Some((pad, reg.color)))
+            }
+            None => (None, Size::new(ow, oh), None),
+        };
+
+        let mut current = content;
+        for &(idx, effect) in pre_effects {
+            let input_dims = current;
+            let output_dims = plan_effect(effect, input_dims.width, input_dims.height)?
+                .map_or(input_dims, |(w, h)| Size::new(w, h));
+            resolved_effects.push(ResolvedEffect {
+                effect: effect.clone_boxed(),
+                input_dims,
+                output_dims,
+                command_index: idx,
+                before_resize: true,
+            });
+            current = output_dims;
+        }
+
+        let (ew, eh) = (current.width, current.height);
+        let layout = match region_pad {
+            Some(((l, t, r, b), color)) if (l, t, r, b) != (0, 0, 0, 0) => resolve_region(
+                Region {
+                    left: RegionCoord::px(-l),
+                    top: RegionCoord::px(-t),
+                    right: RegionCoord::pct_px(1.0, r),
+                    bottom: RegionCoord::pct_px(1.0, b),
+                    color,
+                },
+                ew,
+                eh,
+                constraint,
+            )?,
+            _ => match constraint {
+                Some(c) => c.clone().compute(ew, eh)?,
+                None => Layout {
+                    source: Size::new(ew, eh),
+                    source_crop: None,
+                    resize_to: Size::new(ew, eh),
+                    canvas: Size::new(ew, eh),
+                    placement: (0, 0),
+                    canvas_color: CanvasColor::default(),
+                },
+            },
+        };
+        (layout, Some(content_crop))
+    } else if let Some(reg) = effective_region {
+        (resolve_region(reg, ow, oh, constraint)?, None)
     } else if let Some(c) = constraint {
-        c.clone().compute(ow, oh)?
+        (c.clone().compute(ow, oh)?, None)
     } else {
-        Layout {
-            source: Size::new(ow, oh),
-            source_crop: None,
-            resize_to: Size::new(ow, oh),
-            canvas: Size::new(ow, oh),
-            placement: (0, 0),
-            canvas_color: CanvasColor::default(),
-        }
+        (
+            Layout {
+                source: Size::new(ow, oh),
+                source_crop: None,
+                resize_to: Size::new(ow, oh),
+                canvas: Size::new(ow, oh),
+                placement: (0, 0),
+                canvas_color: CanvasColor::default(),
+            },
+            None,
+        )
     };
 
     // 3. Apply explicit padding if present (additive on existing canvas).
@@ -1887,6 +3300,22 @@ fn plan_from_parts(
         layout
     };
 
+    // 3b. Post-resize slot: effects transform the canvas.
+    let mut layout = layout;
+    for &(idx, effect) in post_effects {
+        let input_dims = layout.canvas;
+        let output_dims = plan_effect(effect, input_dims.width, input_dims.height)?
+            .map_or(input_dims, |(w, h)| Size::new(w, h));
+        resolved_effects.push(ResolvedEffect {
+            effect: effect.clone_boxed(),
+            input_dims,
+            output_dims,
+            command_index: idx,
+            before_resize: false,
+        });
+        layout.canvas = output_dims;
+    }
+
     // 4. Apply mandatory constraints (max/min/align).
     let (layout, content_size) = if let Some(mc) = limits {
         mc.apply(layout)
@@ -1895,8 +3324,10 @@ fn plan_from_parts(
     };
 
     // 5. Transform source crop back to pre-orientation source coordinates.
-    let source_crop_in_source = layout
-        .source_crop
+    // With pre-resize effects, the layout's crop is in post-effect space and
+    // the decoder crop is the content crop taken before the effects.
+    let source_crop_in_source = decoder_crop
+        .unwrap_or(layout.source_crop)
         .map(|r| orientation.transform_rect_to_source(r, source_w, source_h));
 
     let ideal = IdealLayout {
@@ -1905,7 +3336,7 @@ fn plan_from_parts(
         source_crop: source_crop_in_source,
         padding,
         content_size,
-        effects: Vec::new(),
+        effects: resolved_effects,
     };
 
     let request = DecoderRequest {
@@ -1994,9 +3425,17 @@ fn resolve_region(
     if let Some(c) = constraint {
         if is_pure_crop {
             // Pure crop: constraint operates on the overlap (cropped source).
+            // Its own source crop nests inside the overlap.
             let mut builder = c.clone();
             if let Some(sc) = &source_crop {
-                builder = builder.source_crop(SourceCrop::Pixels(*sc));
+                let inner = match &c.source_crop {
+                    Some(own) => {
+                        let r = own.resolve(sc.width, sc.height);
+                        Rect::new(sc.x + r.x, sc.y + r.y, r.width, r.height)
+                    }
+                    None => *sc,
+                };
+                builder = builder.source_crop(SourceCrop::Pixels(inner));
             }
             builder.compute(source_w, source_h)
         } else {
@@ -2063,7 +3502,22 @@ pub(crate) fn finalize(
 
     // 2. Compute trim rect if decoder didn't crop exactly what we asked.
     let (decoder_w, decoder_h) = (offer.dimensions.width, offer.dimensions.height);
-    let trim = compute_trim(&request.crop, &offer.crop_applied, decoder_w, decoder_h);
+    let trim = if offer.scaled {
+        let source = ideal
+            .orientation
+            .inverse()
+            .transform_dimensions(ideal.layout.source.width, ideal.layout.source.height);
+        compute_scaled_trim(
+            &request.crop,
+            &offer.crop_applied,
+            offer.orientation_applied,
+            source,
+            decoder_w,
+            decoder_h,
+        )
+    } else {
+        compute_trim(&request.crop, &offer.crop_applied, decoder_w, decoder_h)
+    };
 
     // 3. Dimensions after trimming.
     let (after_trim_w, after_trim_h) = match &trim {
@@ -2094,6 +3548,45 @@ pub(crate) fn finalize(
     }
 }
 
+/// A region between effects, as an effect: the `width × height` window at
+/// `(left, top)` of its input, filled with `color` outside it.
+fn region_effect(
+    left: i32,
+    top: i32,
+    width: u32,
+    height: u32,
+    color: CanvasColor,
+) -> crate::dimension::RotatedCropEffect {
+    crate::dimension::RotatedCropEffect {
+        center: (
+            left as f32 + width as f32 / 2.0,
+            top as f32 + height as f32 / 2.0,
+        ),
+        width: width as f32,
+        height: height as f32,
+        angle_rad: 0.0,
+        color,
+    }
+}
+
+/// Output dims of a planned effect for a `w × h` input.
+///
+/// `None` is an analysis barrier; an [`Invalid`](crate::EffectKind::Invalid)
+/// effect is an error rather than a barrier.
+fn plan_effect(
+    effect: &dyn crate::dimension::DimensionEffect,
+    w: u32,
+    h: u32,
+) -> Result<Option<(u32, u32)>, At<LayoutError>> {
+    match effect.forward(w, h) {
+        Some(dims) => Ok(Some(dims)),
+        None if effect.kind(w, h) == crate::dimension::EffectKind::Invalid => {
+            Err(at!(LayoutError::InvalidEffect))
+        }
+        None => Ok(None),
+    }
+}
+
 /// Compute trim rect when decoder crop doesn't exactly match request.
 fn compute_trim(
     requested_crop: &Option<Rect>,
@@ -2128,10 +3621,68 @@ fn compute_trim(
     }
 }
 
+/// Compute trim rect for a scaled (resolution-independent) render.
+///
+/// The decoder output covers `applied_crop` (or the full source), oriented
+/// by `orientation_applied` and stretched to `decoder_w × decoder_h`, so the
+/// requested crop is carried into that frame and mapped into output pixels
+/// by that scale.
+fn compute_scaled_trim(
+    requested_crop: &Option<Rect>,
+    applied_crop: &Option<Rect>,
+    orientation_applied: Orientation,
+    source: Size,
+    decoder_w: u32,
+    decoder_h: u32,
+) -> Option<Rect> {
+    let full = Rect::new(0, 0, source.width, source.height);
+    let covered = applied_crop.unwrap_or(full);
+    let req = requested_crop.unwrap_or(full);
+    if req == covered || covered.width == 0 || covered.height == 0 {
+        return None;
+    }
+    // Requested crop relative to the covered area, clipped to it.
+    let x0 = req.x.clamp(covered.x, covered.x + covered.width) - covered.x;
+    let y0 = req.y.clamp(covered.y, covered.y + covered.height) - covered.y;
+    let x1 = (req.x + req.width).clamp(covered.x, covered.x + covered.width) - covered.x;
+    let y1 = (req.y + req.height).clamp(covered.y, covered.y + covered.height) - covered.y;
+    let relative = Rect::new(x0, y0, x1.saturating_sub(x0), y1.saturating_sub(y0));
+    // Into the decoder's oriented frame.
+    let frame = orientation_applied.transform_dimensions(covered.width, covered.height);
+    let req =
+        orientation_applied
+            .inverse()
+            .transform_rect_to_source(relative, frame.width, frame.height);
+
+    let sx = decoder_w as f64 / frame.width as f64;
+    let sy = decoder_h as f64 / frame.height as f64;
+    let x0 = (req.x as f64 * sx).round().max(0.0) as u32;
+    let y0 = (req.y as f64 * sy).round().max(0.0) as u32;
+    let x1 = ((req.x + req.width) as f64 * sx)
+        .round()
+        .clamp(0.0, decoder_w as f64) as u32;
+    let y1 = ((req.y + req.height) as f64 * sy)
+        .round()
+        .clamp(0.0, decoder_h as f64) as u32;
+    let (x0, y0) = (
+        x0.min(decoder_w.saturating_sub(1)),
+        y0.min(decoder_h.saturating_sub(1)),
+    );
+    let tw = x1.saturating_sub(x0).max(1);
+    let th = y1.saturating_sub(y0).max(1);
+    if x0 == 0 && y0 == 0 && tw == decoder_w && th == decoder_h {
+        None
+    } else {
+        Some(Rect::new(x0, y0, tw, th))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::constraint::Gravity;
+    #[cfg(feature = "smart-crop")]
+    use alloc::vec;
 
     // ── No commands ──────────────────────────────────────────────────────
 
@@ -2341,6 +3892,7 @@ mod tests {
             dimensions: Size::new(600, 800),
             crop_applied: None,
             orientation_applied: Orientation::Rotate90,
+            scaled: false,
         };
         let plan = finalize(&ideal, &req, &offer);
 
@@ -2359,6 +3911,7 @@ mod tests {
             dimensions: Size::new(208, 208),
             crop_applied: Some(Rect::new(96, 96, 208, 208)),
             orientation_applied: Orientation::Identity,
+            scaled: false,
         };
         let plan = finalize(&ideal, &req, &offer);
 
@@ -2392,6 +3945,7 @@ mod tests {
             dimensions: Size::new(400, 300),
             crop_applied: Some(Rect::new(0, 0, 400, 300)),
             orientation_applied: Orientation::Identity,
+            scaled: false,
         };
         let plan = finalize(&ideal, &req, &offer);
         assert!(plan.resize_is_identity);
@@ -2443,6 +3997,7 @@ mod tests {
             dimensions: Size::new(300, 400),
             crop_applied: req.crop,
             orientation_applied: Orientation::Rotate90,
+            scaled: false,
         };
         let plan = finalize(&ideal, &req, &offer);
         assert!(plan.resize_is_identity);
@@ -2496,6 +4051,7 @@ mod tests {
             dimensions: Size::new(2000, 1500),
             crop_applied: None,
             orientation_applied: Orientation::Identity,
+            scaled: false,
         };
         let lp = finalize(&ideal, &req, &offer);
 
@@ -2518,6 +4074,7 @@ mod tests {
             dimensions: Size::new(500, 375),
             crop_applied: None,
             orientation_applied: Orientation::Identity,
+            scaled: false,
         };
         let lp = finalize(&ideal, &req, &offer);
         assert!(lp.resize_is_identity);
@@ -2537,6 +4094,7 @@ mod tests {
             dimensions: Size::new(500, 375),
             crop_applied: None,
             orientation_applied: Orientation::Identity,
+            scaled: false,
         };
         let (_, lp) = plan_finalize(
             &[Command::Constrain(Constraint::new(
@@ -2568,6 +4126,7 @@ mod tests {
             dimensions: Size::new(224, 224),
             crop_applied: Some(Rect::new(96, 32, 224, 224)),
             orientation_applied: Orientation::Identity,
+            scaled: false,
         };
         let lp = finalize(&ideal, &req, &offer);
 
@@ -2589,6 +4148,7 @@ mod tests {
             dimensions: Size::new(104, 104),
             crop_applied: Some(Rect::new(48, 48, 104, 104)),
             orientation_applied: Orientation::Identity,
+            scaled: false,
         };
         let lp = finalize(&ideal, &req, &offer);
 
@@ -2608,6 +4168,7 @@ mod tests {
             dimensions: Size::new(104, 104),
             crop_applied: Some(Rect::new(696, 496, 104, 104)),
             orientation_applied: Orientation::Identity,
+            scaled: false,
         };
         let lp = finalize(&ideal, &req, &offer);
 
@@ -2631,6 +4192,7 @@ mod tests {
             dimensions: Size::new(800, 600), // 180° doesn't swap
             crop_applied: None,
             orientation_applied: Orientation::Rotate180,
+            scaled: false,
         };
         let lp = finalize(&ideal, &req, &offer);
 
@@ -2652,6 +4214,7 @@ mod tests {
             dimensions: Size::new(800, 600), // FlipH doesn't swap
             crop_applied: None,
             orientation_applied: Orientation::FlipH,
+            scaled: false,
         };
         let lp = finalize(&ideal, &req, &offer);
 
@@ -2674,6 +4237,7 @@ mod tests {
             dimensions: Size::new(800, 600),
             crop_applied: None,
             orientation_applied: Orientation::FlipH,
+            scaled: false,
         };
         let lp = finalize(&ideal, &req, &offer);
 
@@ -2698,6 +4262,7 @@ mod tests {
             dimensions: Size::new(200, 300),
             crop_applied: req.crop,
             orientation_applied: Orientation::Rotate90,
+            scaled: false,
         };
         let lp = finalize(&ideal, &req, &offer);
 
@@ -2721,6 +4286,7 @@ mod tests {
             dimensions: Size::new(600, 800),
             crop_applied: None,
             orientation_applied: Orientation::Rotate90,
+            scaled: false,
         };
         let lp = finalize(&ideal, &req, &offer);
 
@@ -2747,6 +4313,7 @@ mod tests {
             dimensions: Size::new(source_crop.width, source_crop.height),
             crop_applied: Some(source_crop),
             orientation_applied: Orientation::Identity,
+            scaled: false,
         };
         let lp = finalize(&ideal, &req, &offer);
 
@@ -2804,6 +4371,7 @@ mod tests {
                 dimensions: dims,
                 crop_applied: None,
                 orientation_applied: orientation,
+                scaled: false,
             };
             let lp = finalize(&ideal, &req, &offer);
 
@@ -2907,6 +4475,7 @@ mod tests {
             dimensions: Size::new(750, 1000), // 1/4 prescale + rotation
             crop_applied: None,
             orientation_applied: Orientation::Rotate90,
+            scaled: false,
         };
         let lp = finalize(&ideal, &req, &offer);
 
@@ -2929,6 +4498,7 @@ mod tests {
             dimensions: Size::new(1000, 750), // 1/4 prescale, no rotation
             crop_applied: None,
             orientation_applied: Orientation::Identity,
+            scaled: false,
         };
         let lp = finalize(&ideal, &req, &offer);
 
@@ -2954,6 +4524,7 @@ mod tests {
             dimensions: Size::new(104, 104), // MCU-aligned crop, then 1/2 prescale
             crop_applied: Some(Rect::new(96, 96, 208, 208)),
             orientation_applied: Orientation::Identity,
+            scaled: false,
         };
         let lp = finalize(&ideal, &req, &offer);
 
@@ -3024,6 +4595,7 @@ mod tests {
             dimensions: Size::new(700, 500),
             crop_applied: Some(Rect::new(50, 50, 700, 500)),
             orientation_applied: Orientation::Identity,
+            scaled: false,
         };
         let lp = finalize(&ideal, &req, &offer);
 
@@ -3047,6 +4619,7 @@ mod tests {
             dimensions: Size::new(600, 800), // 270° swaps
             crop_applied: None,
             orientation_applied: Orientation::Rotate270,
+            scaled: false,
         };
         let lp = finalize(&ideal, &req, &offer);
 
@@ -3067,6 +4640,7 @@ mod tests {
             dimensions: Size::new(800, 600), // 180° doesn't swap
             crop_applied: None,
             orientation_applied: Orientation::Rotate180,
+            scaled: false,
         };
         let lp = finalize(&ideal, &req, &offer);
 
@@ -3093,6 +4667,7 @@ mod tests {
             dimensions: Size::new(1000, 100),
             crop_applied: None,
             orientation_applied: Orientation::Rotate90,
+            scaled: false,
         };
         let lp = finalize(&ideal, &req, &offer);
 
@@ -3130,6 +4705,7 @@ mod tests {
             dimensions: Size::new(600, 800), // 270° swaps
             crop_applied: None,
             orientation_applied: Orientation::Rotate270,
+            scaled: false,
         };
         let lp = finalize(&ideal, &req, &offer);
 
@@ -3171,6 +4747,7 @@ mod tests {
             dimensions: target,
             crop_applied: req.crop,
             orientation_applied: Orientation::Rotate270,
+            scaled: false,
         };
         let lp = finalize(&ideal, &req, &offer);
 
@@ -3220,6 +4797,7 @@ mod tests {
             dimensions: Size::new(200, 200),
             crop_applied: Some(Rect::new(100, 100, 200, 200)),
             orientation_applied: Orientation::Identity,
+            scaled: false,
         };
         let lp = finalize(&ideal, &req, &offer);
 
@@ -3239,6 +4817,7 @@ mod tests {
             dimensions: Size::new(800, 600),
             crop_applied: None,
             orientation_applied: Orientation::FlipH,
+            scaled: false,
         };
         let lp = finalize(&ideal, &req, &offer);
         assert_eq!(lp.remaining_orientation, Orientation::Identity);
@@ -3262,6 +4841,7 @@ mod tests {
             dimensions: Size::new(1000, 500),
             crop_applied: None,
             orientation_applied: Orientation::Identity,
+            scaled: false,
         };
         let lp = finalize(&ideal, &req, &offer);
 
@@ -3271,6 +4851,211 @@ mod tests {
         assert!(!lp.resize_is_identity);
     }
 
+    // ── Resolution-independent renderers ─────────────────────────────
+
+    #[test]
+    fn viewport_is_fractional_crop() {
+        let commands = [Command::Crop(SourceCrop::pixels(100, 50, 400, 300))];
+        let (_, req) = compute_layout(&commands, 800, 600, None).unwrap();
+        let vp = req.viewport(800, 600);
+        assert_eq!(vp.x, 0.125);
+        assert_eq!(vp.y, 50.0 / 600.0);
+        assert_eq!(vp.width, 0.5);
+        assert_eq!(vp.height, 0.5);
+
+        let (_, req) = compute_layout(&[], 800, 600, None).unwrap();
+        assert_eq!(req.viewport(800, 600), Viewport::FULL);
+    }
+
+    #[test]
+    fn render_size_follows_applied_orientation() {
+        let (_, req) = Pipeline::new(600, 800)
+            .auto_orient(6)
+            .fit(400, 300)
+            .plan()
+            .unwrap();
+        assert_eq!(req.target_size, Size::new(400, 300));
+        // Renderer rotates while rasterizing → target size as-is.
+        assert_eq!(req.render_size(Orientation::Rotate90), Size::new(400, 300));
+        // Renderer leaves rotation to us → pre-rotation size.
+        assert_eq!(req.render_size(Orientation::Identity), Size::new(300, 400));
+    }
+
+    #[test]
+    fn rendered_offer_is_identity_resize() {
+        let (ideal, req) = Pipeline::new(600, 800)
+            .auto_orient(6)
+            .crop_pixels(100, 100, 400, 400)
+            .fit(1000, 1000)
+            .plan()
+            .unwrap();
+
+        for applied in [Orientation::Identity, Orientation::Rotate90] {
+            let offer = DecoderOffer::rendered(&req, applied);
+            let lp = finalize(&ideal, &req, &offer);
+            assert!(lp.trim.is_none());
+            assert!(lp.resize_is_identity, "{applied:?}");
+            assert_eq!(lp.resize_to, Size::new(1000, 1000));
+        }
+    }
+
+    #[test]
+    fn scaled_full_render_trims_in_output_pixels() {
+        // Renderer ignored the crop but drew the whole source at 2×.
+        let commands = [Command::Crop(SourceCrop::pixels(100, 50, 400, 300))];
+        let (ideal, req) = compute_layout(&commands, 800, 600, None).unwrap();
+        let offer = DecoderOffer::full_decode(1600, 1200).with_scaled(true);
+        let lp = finalize(&ideal, &req, &offer);
+        assert_eq!(lp.trim, Some(Rect::new(200, 100, 800, 600)));
+        assert!(!lp.resize_is_identity);
+        assert_eq!(lp.resize_to, Size::new(400, 300));
+    }
+
+    #[test]
+    fn scaled_rotated_render_trims_in_oriented_pixels() {
+        // Renderer rotated while rasterizing and drew the whole source at 2×.
+        let (ideal, req) = Pipeline::new(800, 600)
+            .auto_orient(6)
+            .crop_pixels(0, 300, 400, 300)
+            .plan()
+            .unwrap();
+        assert_eq!(req.crop, Some(Rect::new(300, 200, 300, 400)));
+        let offer = DecoderOffer::full_decode(1200, 1600)
+            .with_orientation_applied(Orientation::Rotate90)
+            .with_scaled(true);
+        assert_ne!(offer.crop_applied, req.crop);
+        let lp = ideal.finalize(&req, &offer);
+        // The display crop at 2×, in the already-rotated output.
+        assert_eq!(lp.trim, Some(Rect::new(0, 600, 800, 600)));
+        assert_eq!(lp.remaining_orientation, Orientation::Identity);
+        assert_eq!(lp.resize_to, Size::new(400, 300));
+
+        // A block-aligned superset crop, rotated and rendered at 2×.
+        let offer = DecoderOffer {
+            dimensions: Size::new(1000, 800),
+            crop_applied: Some(Rect::new(200, 200, 400, 500)),
+            orientation_applied: Orientation::Rotate90,
+            scaled: true,
+        };
+        let lp = ideal.finalize(&req, &offer);
+        assert_eq!(lp.trim, Some(Rect::new(200, 200, 800, 600)));
+    }
+
+    #[test]
+    fn scaled_render_at_wrong_size_still_resizes() {
+        let (ideal, req) = Pipeline::new(612, 792).fit(300, 300).plan().unwrap();
+        let offer = DecoderOffer::full_decode(232, 300).with_scaled(true);
+        let lp = finalize(&ideal, &req, &offer);
+        assert!(lp.trim.is_none());
+        assert_eq!(lp.resize_is_identity, lp.resize_to == Size::new(232, 300));
+    }
+
+    // ── Variant selection ────────────────────────────────────────────
+
+    #[test]
+    fn variant_smallest_sufficient() {
+        let (ideal, _) = Pipeline::new(4096, 4096).fit(64, 64).plan().unwrap();
+        let variants = [
+            SourceVariant::new(16, 16, 1),
+            SourceVariant::new(256, 256, 1),
+            SourceVariant::new(128, 128, 1),
+            SourceVariant::new(4096, 4096, 1),
+            SourceVariant::new(48, 48, 1),
+        ];
+        let c = ideal
+            .select_variant(Size::new(4096, 4096), &variants, 1.0)
+            .unwrap();
+        assert_eq!(c.index, 2);
+        assert!(c.sufficient);
+        assert_eq!(c.oversampling, 2.0);
+        assert_eq!(c.ideal.layout.source, Size::new(128, 128));
+        assert_eq!(c.ideal.layout.resize_to, Size::new(64, 64));
+
+        let c = ideal
+            .select_variant(Size::new(4096, 4096), &variants, 3.0)
+            .unwrap();
+        assert_eq!(c.index, 1);
+    }
+
+    #[test]
+    fn variant_quality_breaks_ties() {
+        let (ideal, _) = Pipeline::new(256, 256).fit(32, 32).plan().unwrap();
+        let variants = [
+            SourceVariant::new(32, 32, 1),
+            SourceVariant::new(32, 32, 8),
+            SourceVariant::new(32, 32, 4),
+        ];
+        let c = ideal
+            .select_variant(Size::new(256, 256), &variants, 1.0)
+            .unwrap();
+        assert_eq!(c.index, 1);
+    }
+
+    #[test]
+    fn variant_none_sufficient_picks_largest() {
+        let (ideal, _) = Pipeline::new(4000, 3000).fit(2000, 1500).plan().unwrap();
+        let variants = [
+            SourceVariant::new(160, 120, 1),
+            SourceVariant::new(1024, 768, 1),
+        ];
+        let c = ideal
+            .select_variant(Size::new(4000, 3000), &variants, 1.0)
+            .unwrap();
+        assert_eq!(c.index, 1);
+        assert!(!c.sufficient);
+        assert!(c.oversampling < 1.0);
+    }
+
+    #[test]
+    fn variant_aspect_mismatch_skipped() {
+        // Letterboxed EXIF thumbnail of a 3:2 image is 160×120 — not the same extent.
+        let (ideal, _) = Pipeline::new(6000, 4000).fit(100, 100).plan().unwrap();
+        let variants = [
+            SourceVariant::new(160, 120, 1),
+            SourceVariant::new(6000, 4000, 1),
+        ];
+        let c = ideal
+            .select_variant(Size::new(6000, 4000), &variants, 1.0)
+            .unwrap();
+        assert_eq!(c.index, 1);
+        assert!(
+            ideal
+                .select_variant(Size::new(6000, 4000), &variants[..1], 1.0)
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn variant_rescales_crop_and_orientation() {
+        let (ideal, _) = Pipeline::new(4000, 3000)
+            .auto_orient(6)
+            .crop_pixels(0, 0, 1500, 2000)
+            .fit(300, 400)
+            .plan()
+            .unwrap();
+        let variants = [
+            SourceVariant::new(4000, 3000, 1),
+            SourceVariant::new(1000, 750, 1),
+        ];
+        let c = ideal
+            .select_variant(Size::new(4000, 3000), &variants, 1.0)
+            .unwrap();
+        assert_eq!(c.index, 1);
+        assert_eq!(c.ideal.layout.source, Size::new(750, 1000));
+        let crop = c.request.crop.unwrap();
+        // Quarter scale of the pre-orientation crop.
+        let primary = ideal.source_crop.unwrap();
+        assert_eq!(crop.width, primary.width / 4);
+        assert_eq!(crop.height, primary.height / 4);
+        assert_eq!(c.request.orientation, Orientation::Rotate90);
+
+        // Variant decoded as-is → finalize resizes 375×500 to 300×400.
+        let offer = DecoderOffer::full_decode(1000, 750);
+        let lp = finalize(&c.ideal, &c.request, &offer);
+        assert_eq!(lp.trim, Some(crop));
+        assert_eq!(lp.resize_to, Size::new(300, 400));
+    }
+
     // ════════════════════════════════════════════════════════════════════
     // Pipeline builder API
     // ════════════════════════════════════════════════════════════════════
@@ -3660,6 +5445,7 @@ mod tests {
             dimensions: Size::new(2000, 2000),
             crop_applied: sdr_req.crop,
             orientation_applied: Orientation::Rotate90,
+            scaled: false,
         };
         let sdr_plan = sdr.finalize(&sdr_req, &sdr_offer);
 
@@ -3697,6 +5483,7 @@ mod tests {
             dimensions: Size::new(208, 208),
             crop_applied: Some(Rect::new(96, 96, 208, 208)),
             orientation_applied: Orientation::Identity,
+            scaled: false,
         };
         let sdr_plan = sdr.finalize(&sdr_req, &sdr_offer);
 
@@ -3705,6 +5492,7 @@ mod tests {
             dimensions: Size::new(56, 56),
             crop_applied: Some(Rect::new(24, 24, 56, 56)),
             orientation_applied: Orientation::Identity,
+            scaled: false,
         };
         let gm_plan = gm.finalize(&gm_req, &gm_offer);
 
@@ -3724,28 +5512,184 @@ mod tests {
         assert_eq!(gm_trim.width, gm_crop_requested.width);
         assert_eq!(gm_trim.height, gm_crop_requested.height);
 
-        // Both independently correct despite different MCU grids
-        assert!(sdr_plan.resize_is_identity);
-        assert!(gm_plan.resize_is_identity);
+        // Both independently correct despite different MCU grids
+        assert!(sdr_plan.resize_is_identity);
+        assert!(gm_plan.resize_is_identity);
+    }
+
+    #[test]
+    fn secondary_padding_scaled() {
+        let (sdr, _) = Pipeline::new(800, 600)
+            .fit_pad(400, 400)
+            .pad_uniform(10, CanvasColor::white())
+            .plan()
+            .unwrap();
+
+        // SDR has padding
+        assert!(sdr.padding.is_some());
+        assert_eq!(sdr.layout.canvas, Size::new(420, 420)); // 400+20 from explicit pad
+        assert_eq!(sdr.layout.placement, (10, 60));
+
+        let (gm, _) = sdr.derive_secondary(Size::new(800, 600), Size::new(200, 150), None);
+
+        // Gain map: everything at 1/4 scale, still locked to the SDR canvas.
+        assert_eq!(gm.layout.resize_to, Size::new(100, 75));
+        assert_eq!(gm.layout.canvas, Size::new(106, 105));
+        assert_eq!(gm.layout.placement, (3, 15));
+        let p = gm.padding.unwrap();
+        assert_eq!((p.top, p.right, p.bottom, p.left), (3, 3, 3, 3));
+        assert_eq!(p.color, CanvasColor::white());
+        assert_eq!(gm.layout.canvas_color, sdr.layout.canvas_color);
+    }
+
+    #[test]
+    fn secondary_odd_scale_keeps_content_inside_canvas() {
+        let (sdr, _) = Pipeline::new(1001, 777)
+            .fit_pad(333, 333)
+            .pad(Padding::new(7, 9, 11, 5, CanvasColor::white()))
+            .plan()
+            .unwrap();
+
+        for secondary in [Size::new(143, 111), Size::new(429, 333), Size::new(77, 60)] {
+            let (gm, _) = sdr.derive_secondary(Size::new(1001, 777), secondary, None);
+            let l = &gm.layout;
+            let p = gm.padding.unwrap();
+            let (px, py) = (l.placement.0 as u32, l.placement.1 as u32);
+            assert!(
+                px + l.resize_to.width <= l.canvas.width,
+                "{secondary:?}: {l:?}"
+            );
+            assert!(
+                py + l.resize_to.height <= l.canvas.height,
+                "{secondary:?}: {l:?}"
+            );
+            assert!(p.left <= px && p.top <= py, "{secondary:?}: {p:?}");
+            assert!(px + l.resize_to.width + p.right <= l.canvas.width);
+            assert!(py + l.resize_to.height + p.bottom <= l.canvas.height);
+        }
+    }
+
+    #[test]
+    fn secondary_region_padding_placement() {
+        let (sdr, _) = Pipeline::new(800, 600)
+            .region_viewport(-100, -100, 900, 700, CanvasColor::black())
+            .plan()
+            .unwrap();
+        assert_eq!(sdr.layout.canvas, Size::new(1000, 800));
+        assert_eq!(sdr.layout.placement, (100, 100));
+
+        let (gm, _) = sdr.derive_secondary(Size::new(800, 600), Size::new(400, 300), None);
+        assert_eq!(gm.layout.resize_to, Size::new(400, 300));
+        assert_eq!(gm.layout.canvas, Size::new(500, 400));
+        assert_eq!(gm.layout.placement, (50, 50));
+    }
+
+    #[test]
+    fn secondary_pre_resize_effect_rescaled() {
+        use crate::dimension::{RotateEffect, RotateMode};
+        let commands = [Command::Effect(alloc::boxed::Box::new(
+            RotateEffect::from_degrees(5.0, RotateMode::InscribedCrop),
+        ))];
+        let (sdr, _) = compute_layout_sequential(&commands, 800, 600, None).unwrap();
+        assert_eq!(sdr.effects.len(), 1);
+
+        let (gm, _) = sdr.derive_secondary(Size::new(800, 600), Size::new(400, 300), None);
+        assert_eq!(gm.effects.len(), 1);
+
+        // Rotation sees the half-scale source; the layout source follows it.
+        let pre = &gm.effects[0];
+        assert!(pre.before_resize);
+        assert_eq!(pre.command_index, 0);
+        assert_eq!(pre.input_dims, Size::new(400, 300));
+        assert_eq!(gm.layout.source, pre.output_dims);
+        let full = sdr.effects[0].output_dims;
+        assert!((full.width as i32 / 2 - pre.output_dims.width as i32).abs() <= 1);
+        assert!((full.height as i32 / 2 - pre.output_dims.height as i32).abs() <= 1);
+    }
+
+    #[test]
+    fn secondary_post_resize_effect_rescaled() {
+        use crate::dimension::ExpandEffect;
+        let commands = [
+            Command::Constrain(Constraint::new(ConstraintMode::Fit, 400, 400)),
+            Command::Effect(alloc::boxed::Box::new(ExpandEffect {
+                left: 8,
+                top: 8,
+                right: 8,
+                bottom: 8,
+            })),
+        ];
+        let (sdr, _) = compute_layout_sequential(&commands, 800, 600, None).unwrap();
+        assert_eq!(sdr.layout.canvas, Size::new(416, 316));
+
+        let (gm, _) = sdr.derive_secondary(Size::new(800, 600), Size::new(200, 150), None);
+        assert_eq!(gm.layout.resize_to, Size::new(100, 75));
+
+        // Expansion shrinks with the output and matches the scaled canvas.
+        let post = &gm.effects[0];
+        assert!(!post.before_resize);
+        assert_eq!(post.command_index, 1);
+        assert_eq!(post.input_dims, Size::new(100, 75));
+        assert_eq!(post.output_dims, Size::new(104, 79));
+        assert_eq!(gm.layout.canvas, post.output_dims);
+    }
+
+    #[test]
+    fn secondary_pre_resize_effect_sees_crop() {
+        use crate::dimension::{DimensionEffect, RotateEffect, RotateMode};
+        let rotate = RotateEffect::from_degrees(
+            10.0,
+            RotateMode::Expand {
+                color: CanvasColor::Transparent,
+            },
+        );
+        let commands = [
+            Command::Crop(SourceCrop::pixels(0, 0, 500, 400)),
+            Command::Effect(alloc::boxed::Box::new(rotate)),
+            Command::Constrain(Constraint::new(ConstraintMode::Fit, 400, 400)),
+        ];
+        let (sdr, _) = compute_layout_sequential(&commands, 1000, 800, None).unwrap();
+        assert_eq!(sdr.effects[0].input_dims, Size::new(500, 400));
+
+        let (gm, _) = sdr.derive_secondary(Size::new(1000, 800), Size::new(500, 400), None);
+        assert_eq!(gm.source_crop, Some(Rect::new(0, 0, 250, 200)));
+        let pre = &gm.effects[0];
+        assert_eq!(pre.input_dims, Size::new(250, 200));
+        let (w, h) = rotate.forward(250, 200).unwrap();
+        assert_eq!(pre.output_dims, Size::new(w, h));
+        assert_eq!(gm.layout.source, pre.output_dims);
     }
 
     #[test]
-    fn secondary_no_padding() {
-        let (sdr, _) = Pipeline::new(800, 600)
-            .fit_pad(400, 400)
-            .pad_uniform(10, CanvasColor::white())
+    fn secondary_post_resize_effects_chain() {
+        use crate::dimension::PadEffect;
+        let (sdr, _) = Pipeline::new(1001, 777)
+            .fit(401, 301)
+            .effect(PadEffect::pixels(7, CanvasColor::black()))
+            .effect(PadEffect::pixels(7, CanvasColor::black()))
             .plan()
             .unwrap();
+        assert_eq!(sdr.effects.len(), 2);
 
-        // SDR has padding
-        assert!(sdr.padding.is_some());
-        assert_eq!(sdr.layout.canvas, Size::new(420, 420)); // 400+20 from explicit pad
-
-        let (gm, _) = sdr.derive_secondary(Size::new(800, 600), Size::new(200, 150), None);
+        let (gm, _) = sdr.derive_secondary(Size::new(1001, 777), Size::new(333, 250), None);
+        assert_eq!(gm.effects[0].input_dims, gm.layout.resize_to);
+        for pair in gm.effects.windows(2) {
+            assert_eq!(pair[0].output_dims, pair[1].input_dims);
+        }
+    }
 
-        // Gain map: no padding
-        assert!(gm.padding.is_none());
-        assert_eq!(gm.layout.canvas, gm.layout.resize_to);
+    #[test]
+    fn secondary_warp_rescaled() {
+        use crate::dimension::{DimensionEffect, ResolutionPolicy, WarpEffect};
+        // Translate by (40, 20) output→source.
+        let warp = WarpEffect::new(
+            [1.0, 0.0, 40.0, 0.0, 1.0, 20.0, 0.0, 0.0, 1.0],
+            ResolutionPolicy::Custom(800, 600),
+        );
+        let half = warp.scaled(0.5, 0.5);
+        assert_eq!(half.forward(400, 300), Some((400, 300)));
+        let (x, y) = half.inverse_point(0.0, 0.0, 400, 300).unwrap();
+        assert_eq!((x, y), (20.0, 10.0));
     }
 
     #[test]
@@ -3836,6 +5780,7 @@ mod tests {
             dimensions: Size::new(1000, 1500),
             crop_applied: sdr_req.crop,
             orientation_applied: Orientation::Rotate90,
+            scaled: false,
         };
         let sdr_plan = sdr.finalize(&sdr_req, &sdr_offer);
         assert!(sdr_plan.resize_is_identity);
@@ -3846,6 +5791,7 @@ mod tests {
             dimensions: Size::new(gm.layout.resize_to.width, gm.layout.resize_to.height),
             crop_applied: gm_req.crop,
             orientation_applied: Orientation::Rotate90,
+            scaled: false,
         };
         let gm_plan = gm.finalize(&gm_req, &gm_offer);
         assert!(gm_plan.resize_is_identity);
@@ -5134,6 +7080,19 @@ mod tests {
         assert_eq!(crop_layout.layout.resize_to, region_layout.layout.resize_to);
     }
 
+    #[test]
+    fn constraint_source_crop_nests_inside_pipeline_crop() {
+        let c = Constraint::new(ConstraintMode::Fit, 100, 100)
+            .source_crop(SourceCrop::pixels(10, 10, 50, 50));
+        let (ideal, _) = Pipeline::new(800, 600)
+            .crop_pixels(100, 100, 400, 300)
+            .constrain(c)
+            .plan()
+            .unwrap();
+        assert_eq!(ideal.layout.source_crop, Some(Rect::new(110, 110, 50, 50)));
+        assert_eq!(ideal.layout.resize_to, Size::new(100, 100));
+    }
+
     // ── Region zero dimension rejected ─────────────────────────────────
 
     #[test]
@@ -5344,4 +7303,802 @@ mod tests {
         // Canvas should be expanded by 40 in each direction
         assert_eq!(ideal.layout.canvas, Size::new(440, 340));
     }
+
+    #[test]
+    fn sequential_pre_constrain_effect_kept() {
+        use crate::dimension::{RotateEffect, RotateMode};
+        let commands = [
+            Command::Effect(alloc::boxed::Box::new(RotateEffect::from_degrees(
+                5.0,
+                RotateMode::InscribedCrop,
+            ))),
+            Command::Constrain(Constraint::new(ConstraintMode::Fit, 400, 400)),
+        ];
+        let (ideal, _) = compute_layout_sequential(&commands, 800, 600, None).unwrap();
+        assert_eq!(ideal.effects.len(), 1);
+        assert!(ideal.effects[0].before_resize);
+        assert_eq!(ideal.effects[0].command_index, 0);
+        // The constraint sees the shrunken post-rotation source.
+        assert_eq!(ideal.layout.source, ideal.effects[0].output_dims);
+        assert!(ideal.layout.source.width < 800);
+        let without = compute_layout_sequential(&commands[1..], 800, 600, None)
+            .unwrap()
+            .0;
+        assert_ne!(ideal.layout.source, without.layout.source);
+
+        // An effect after the first constraint is dropped by a later one.
+        let commands = [
+            Command::Constrain(Constraint::new(ConstraintMode::Fit, 400, 400)),
+            Command::Effect(alloc::boxed::Box::new(RotateEffect::from_degrees(
+                5.0,
+                RotateMode::InscribedCrop,
+            ))),
+            Command::Constrain(Constraint::new(ConstraintMode::Fit, 400, 400)),
+        ];
+        let (ideal, _) = compute_layout_sequential(&commands, 800, 600, None).unwrap();
+        assert!(ideal.effects.is_empty());
+        assert_eq!(ideal.layout, without.layout);
+    }
+
+    // ── Resuming after analysis barriers ───────────────────────────────
+
+    fn trim_then_fit() -> [Command; 3] {
+        use crate::dimension::{ExpandEffect, TrimEffect};
+        [
+            Command::Effect(alloc::boxed::Box::new(TrimEffect {
+                estimated_margin_percent: 0.05,
+            })),
+            Command::Constrain(Constraint::new(ConstraintMode::Fit, 400, 400)),
+            Command::Effect(alloc::boxed::Box::new(ExpandEffect {
+                left: 10,
+                top: 10,
+                right: 10,
+                bottom: 10,
+            })),
+        ]
+    }
+
+    #[test]
+    fn barrier_recorded_as_passthrough() {
+        let commands = trim_then_fit();
+        let (ideal, _) = compute_layout_sequential(&commands, 1000, 800, None).unwrap();
+        let barrier = &ideal.effects[0];
+        assert_eq!(barrier.command_index, 0);
+        assert_eq!(barrier.input_dims, barrier.output_dims);
+        assert_eq!(ideal.layout.resize_to, Size::new(400, 320));
+    }
+
+    #[test]
+    fn resume_with_rect_replans_downstream() {
+        use crate::dimension::BarrierResolution;
+        let commands = trim_then_fit();
+        let (ideal, _) = compute_layout_sequential(&commands, 1000, 800, None).unwrap();
+        let (resumed, req) = ideal
+            .resume_after_barrier(
+                &commands,
+                1000,
+                800,
+                None,
+                0,
+                BarrierResolution::Rect(Rect::new(100, 200, 800, 400)),
+            )
+            .unwrap();
+
+        let trim = &resumed.effects[0];
+        assert_eq!(trim.input_dims, Size::new(1000, 800));
+        assert_eq!(trim.output_dims, Size::new(800, 400));
+        assert_eq!(
+            trim.effect.forward_point(100.0, 200.0, 1000, 800),
+            Some((0.0, 0.0))
+        );
+        // Constraint and post-resize expansion follow the real size.
+        assert_eq!(resumed.layout.resize_to, Size::new(400, 200));
+        assert_eq!(req.target_size, Size::new(400, 200));
+        assert_eq!(resumed.effects[1].input_dims, Size::new(400, 200));
+        assert_eq!(resumed.layout.canvas, Size::new(420, 220));
+    }
+
+    #[test]
+    fn resume_with_dims_only() {
+        use crate::dimension::BarrierResolution;
+        let commands = trim_then_fit();
+        let (ideal, _) = compute_layout_sequential(&commands, 1000, 800, None).unwrap();
+        let (resumed, _) = ideal
+            .resume_after_barrier(
+                &commands,
+                1000,
+                800,
+                None,
+                0,
+                BarrierResolution::Dims(Size::new(500, 500)),
+            )
+            .unwrap();
+        assert_eq!(resumed.layout.resize_to, Size::new(400, 400));
+    }
+
+    #[test]
+    fn resume_clamps_rect_to_input() {
+        use crate::dimension::BarrierResolution;
+        let commands = trim_then_fit();
+        let (ideal, _) = compute_layout_sequential(&commands, 1000, 800, None).unwrap();
+        let (resumed, _) = ideal
+            .resume_after_barrier(
+                &commands,
+                1000,
+                800,
+                None,
+                0,
+                BarrierResolution::Rect(Rect::new(600, 400, 1000, 1000)),
+            )
+            .unwrap();
+        assert_eq!(resumed.effects[0].output_dims, Size::new(400, 400));
+    }
+
+    #[test]
+    fn resume_rejects_non_barrier() {
+        use crate::dimension::BarrierResolution;
+        let commands = trim_then_fit();
+        let (ideal, _) = compute_layout_sequential(&commands, 1000, 800, None).unwrap();
+        let res = BarrierResolution::Dims(Size::new(10, 10));
+        // Index 2 is an ExpandEffect (plannable); index 1 is not an effect at all.
+        for idx in [1, 2, 7] {
+            let err = ideal
+                .resume_after_barrier(&commands, 1000, 800, None, idx, res)
+                .unwrap_err();
+            assert_eq!(*err.error(), LayoutError::NotABarrier, "{idx}");
+        }
+        let err = ideal
+            .resume_after_barrier(
+                &commands,
+                1000,
+                800,
+                None,
+                0,
+                BarrierResolution::Rect(Rect::new(1000, 0, 10, 10)),
+            )
+            .unwrap_err();
+        assert_eq!(*err.error(), LayoutError::ZeroRegionDimension);
+    }
+
+    #[test]
+    fn content_mapped_effect_plans_through() {
+        use crate::dimension::{EffectKind, SeamCarveEffect};
+        let commands = [
+            Command::Effect(alloc::boxed::Box::new(SeamCarveEffect::width_only(600))),
+            Command::Effect(alloc::boxed::Box::new(tilt_warp())),
+            Command::Constrain(Constraint::new(ConstraintMode::Fit, 300, 300)),
+        ];
+        let (ideal, _) = compute_layout_sequential(&commands, 1000, 800, None).unwrap();
+        // Dims flow through the carve into the constraint, but it is not a
+        // resample the warp can fuse with, and not a barrier to resume.
+        assert_eq!(ideal.effects.len(), 2);
+        assert_eq!(ideal.effects[0].kind(), EffectKind::ContentMapped);
+        assert_eq!(ideal.effects[0].output_dims, Size::new(600, 800));
+        assert_eq!(ideal.effects[1].kind(), EffectKind::Geometric);
+        assert_eq!(ideal.layout.resize_to, Size::new(225, 300));
+        let err = ideal
+            .resume_after_barrier(
+                &commands,
+                1000,
+                800,
+                None,
+                0,
+                crate::dimension::BarrierResolution::Dims(Size::new(10, 10)),
+            )
+            .unwrap_err();
+        assert_eq!(*err.error(), LayoutError::NotABarrier);
+    }
+
+    // ── Effects in the fixed pipeline ──────────────────────────────────
+
+    #[test]
+    fn fixed_effects_fill_pre_and_post_slots() {
+        use crate::dimension::{DimensionEffect, ExpandEffect, RotateEffect, RotateMode};
+        let rotate = RotateEffect::from_degrees(3.0, RotateMode::InscribedCrop);
+        let commands = [
+            Command::Effect(alloc::boxed::Box::new(rotate)),
+            Command::Constrain(Constraint::new(ConstraintMode::Fit, 400, 400)),
+            Command::Effect(alloc::boxed::Box::new(ExpandEffect {
+                left: 5,
+                top: 5,
+                right: 5,
+                bottom: 5,
+            })),
+        ];
+        let (ideal, req) = compute_layout(&commands, 800, 600, None).unwrap();
+        assert_eq!(ideal.effects.len(), 2);
+
+        let pre = &ideal.effects[0];
+        assert!(pre.before_resize);
+        assert_eq!(pre.command_index, 0);
+        assert_eq!(pre.input_dims, Size::new(800, 600));
+        let (rw, rh) = rotate.forward(800, 600).unwrap();
+        assert_eq!(pre.output_dims, Size::new(rw, rh));
+        assert_eq!(ideal.layout.source, pre.output_dims);
+        assert_eq!(ideal.layout.resize_to.width, 400);
+
+        let post = &ideal.effects[1];
+        assert!(!post.before_resize);
+        assert_eq!(post.command_index, 2);
+        assert_eq!(post.input_dims, ideal.layout.resize_to);
+        assert_eq!(ideal.layout.canvas, post.output_dims);
+
+        // No crop: decoder decodes everything.
+        assert!(req.crop.is_none());
+    }
+
+    #[test]
+    fn fixed_pre_effect_after_crop() {
+        use crate::dimension::ExpandEffect;
+        let commands = [
+            Command::Crop(SourceCrop::pixels(100, 100, 400, 300)),
+            Command::Effect(alloc::boxed::Box::new(ExpandEffect {
+                left: 50,
+                top: 0,
+                right: 50,
+                bottom: 0,
+            })),
+            Command::Constrain(Constraint::new(ConstraintMode::Fit, 250, 250)),
+        ];
+        let (ideal, req) = compute_layout(&commands, 800, 600, None).unwrap();
+        // Decoder crop is the user crop in source space.
+        assert_eq!(req.crop, Some(Rect::new(100, 100, 400, 300)));
+        assert_eq!(ideal.source_crop, req.crop);
+        // Effect sees the cropped content; constraint sees the effect output.
+        assert_eq!(ideal.effects[0].input_dims, Size::new(400, 300));
+        assert_eq!(ideal.layout.source, Size::new(500, 300));
+        assert!(ideal.layout.source_crop.is_none());
+        assert_eq!(ideal.layout.resize_to, Size::new(250, 150));
+    }
+
+    #[test]
+    fn fixed_pre_effect_with_region_padding() {
+        use crate::dimension::ExpandEffect;
+        let commands = [
+            Command::Region(Region::padded(20, CanvasColor::white())),
+            Command::Effect(alloc::boxed::Box::new(ExpandEffect {
+                left: 10,
+                top: 10,
+                right: 10,
+                bottom: 10,
+            })),
+        ];
+        let (ideal, _) = compute_layout(&commands, 400, 300, None).unwrap();
+        // Effect transforms the content; region padding wraps the result.
+        assert_eq!(ideal.effects[0].input_dims, Size::new(400, 300));
+        assert_eq!(ideal.layout.resize_to, Size::new(420, 320));
+        assert_eq!(ideal.layout.canvas, Size::new(460, 360));
+        assert_eq!(ideal.layout.placement, (20, 20));
+        assert_eq!(ideal.layout.canvas_color, CanvasColor::white());
+    }
+
+    #[test]
+    fn fixed_without_effects_unchanged() {
+        let commands = [
+            Command::AutoOrient(6),
+            Command::Crop(SourceCrop::pixels(10, 10, 500, 700)),
+            Command::Constrain(Constraint::new(ConstraintMode::FitCrop, 200, 200)),
+        ];
+        let (ideal, _) = compute_layout(&commands, 800, 600, None).unwrap();
+        assert!(ideal.effects.is_empty());
+        let (p, _) = Pipeline::new(800, 600)
+            .auto_orient(6)
+            .crop_pixels(10, 10, 500, 700)
+            .fit_crop(200, 200)
+            .plan()
+            .unwrap();
+        assert_eq!(ideal.layout, p.layout);
+        assert_eq!(ideal.source_crop, p.source_crop);
+    }
+
+    #[test]
+    fn pipeline_effect_slots_follow_constraint() {
+        use crate::dimension::{PadEffect, RotateEffect, RotateMode};
+        let (ideal, _) = Pipeline::new(800, 600)
+            .effect(RotateEffect::from_degrees(-4.0, RotateMode::InscribedCrop))
+            .fit(400, 400)
+            .effect(PadEffect::pixels(8, CanvasColor::black()))
+            .plan()
+            .unwrap();
+        assert_eq!(ideal.effects.len(), 2);
+        assert!(ideal.effects[0].before_resize);
+        assert!(!ideal.effects[1].before_resize);
+        assert_eq!(ideal.effects[1].command_index, 1);
+        let rt = ideal.layout.resize_to;
+        assert_eq!(
+            ideal.layout.canvas,
+            Size::new(rt.width + 16, rt.height + 16)
+        );
+
+        // Same result through compute_layout.
+        let commands = [
+            Command::Effect(alloc::boxed::Box::new(RotateEffect::from_degrees(
+                -4.0,
+                RotateMode::InscribedCrop,
+            ))),
+            Command::Constrain(Constraint::new(ConstraintMode::Fit, 400, 400)),
+            Command::Effect(alloc::boxed::Box::new(PadEffect::pixels(
+                8,
+                CanvasColor::black(),
+            ))),
+        ];
+        let (fixed, _) = compute_layout(&commands, 800, 600, None).unwrap();
+        assert_eq!(fixed.layout, ideal.layout);
+    }
+
+    #[test]
+    fn pipeline_sequential_matches_free_function() {
+        use crate::dimension::ExpandEffect;
+        let expand = ExpandEffect {
+            left: 4,
+            top: 4,
+            right: 4,
+            bottom: 4,
+        };
+        let (ideal, req) = Pipeline::new(800, 600)
+            .auto_orient(6)
+            .sequential()
+            .crop_pixels(0, 0, 500, 700)
+            .fit(300, 300)
+            .effect(expand)
+            .crop_pixels(0, 0, 100, 100)
+            .plan()
+            .unwrap();
+
+        let commands = [
+            Command::AutoOrient(6),
+            Command::Crop(SourceCrop::pixels(0, 0, 500, 700)),
+            Command::Constrain(Constraint::new(ConstraintMode::Fit, 300, 300)),
+            Command::Effect(alloc::boxed::Box::new(expand)),
+            Command::Crop(SourceCrop::pixels(0, 0, 100, 100)),
+        ];
+        let (expected, expected_req) =
+            compute_layout_sequential(&commands, 800, 600, None).unwrap();
+        assert_eq!(ideal.layout, expected.layout);
+        assert_eq!(ideal.orientation, expected.orientation);
+        assert_eq!(req, expected_req);
+        assert_eq!(ideal.effects.len(), 1);
+        assert_eq!(ideal.effects[0].command_index, 3);
+        assert_eq!(ideal.layout.canvas, Size::new(100, 100));
+    }
+
+    #[test]
+    fn pipeline_sequential_converts_earlier_calls() {
+        let (a, _) = Pipeline::new(800, 600)
+            .auto_orient(8)
+            .crop_pixels(10, 20, 300, 400)
+            .fit(100, 100)
+            .pad_uniform(5, CanvasColor::white())
+            .sequential()
+            .plan()
+            .unwrap();
+        let (b, _) = Pipeline::new(800, 600)
+            .auto_orient(8)
+            .crop_pixels(10, 20, 300, 400)
+            .fit(100, 100)
+            .pad_uniform(5, CanvasColor::white())
+            .plan()
+            .unwrap();
+        assert_eq!(a.layout, b.layout);
+        assert_eq!(a.source_crop, b.source_crop);
+    }
+
+    // ── Effect fusion ──────────────────────────────────────────────────
+
+    fn tilt_warp() -> crate::dimension::WarpEffect {
+        use crate::dimension::{ResolutionPolicy, WarpEffect};
+        WarpEffect::new_f64(
+            [1.0, 0.05, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0001, 1.0],
+            ResolutionPolicy::PreserveInput,
+        )
+    }
+
+    #[test]
+    fn sequential_fuses_rotate_and_warp() {
+        use crate::dimension::{DimensionEffect, FusedEffect, RotateEffect, RotateMode};
+        let rotate = RotateEffect::from_degrees(3.0, RotateMode::InscribedCrop);
+        let commands = [
+            Command::Effect(alloc::boxed::Box::new(rotate)),
+            Command::Effect(alloc::boxed::Box::new(tilt_warp())),
+            Command::Constrain(Constraint::new(ConstraintMode::Fit, 400, 400)),
+        ];
+        let (ideal, _) = compute_layout_sequential(&commands, 800, 600, None).unwrap();
+        assert_eq!(ideal.effects.len(), 1);
+        let fused = &ideal.effects[0];
+        assert_eq!(fused.command_index, 0);
+        assert_eq!(fused.input_dims, Size::new(800, 600));
+
+        // Same dims as the unfused chain.
+        let unfused = FusedEffect::new(alloc::vec![
+            alloc::boxed::Box::new(rotate) as alloc::boxed::Box<dyn DimensionEffect>,
+            alloc::boxed::Box::new(tilt_warp()),
+        ]);
+        let mid = rotate.forward(800, 600).unwrap();
+        let out = tilt_warp().forward(mid.0, mid.1).unwrap();
+        assert_eq!(fused.output_dims, Size::new(out.0, out.1));
+        assert_eq!(unfused.forward(800, 600), Some(out));
+        assert_eq!(ideal.layout.source, fused.output_dims);
+        assert!(fused.effect.warp_matrix(800, 600).is_some());
+    }
+
+    #[test]
+    fn degenerate_warp_is_an_error() {
+        use crate::dimension::{ResolutionPolicy, WarpCoverage, WarpEffect};
+        let warp = WarpEffect::new_f64(
+            [1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.002, 0.0, 1.0],
+            ResolutionPolicy::PreserveInput,
+        )
+        .with_coverage(WarpCoverage::Expand {
+            color: CanvasColor::Transparent,
+        });
+        let invalid = |r: Result<(IdealLayout, DecoderRequest), At<LayoutError>>| {
+            *r.unwrap_err().error() == LayoutError::InvalidEffect
+        };
+        // Fixed mode, before and after the resize.
+        let pre = Pipeline::new(800, 600).effect(warp.clone()).fit(400, 400);
+        assert!(invalid(pre.plan()));
+        let post = Pipeline::new(800, 600).fit(800, 800).effect(warp.clone());
+        assert!(invalid(post.plan()));
+        // Sequential mode.
+        let commands = [
+            Command::Effect(alloc::boxed::Box::new(warp)),
+            Command::Constrain(Constraint::new(ConstraintMode::Fit, 400, 400)),
+        ];
+        assert!(invalid(compute_layout_sequential(
+            &commands, 800, 600, None
+        )));
+    }
+
+    #[test]
+    fn invalid_lens_is_an_error() {
+        use crate::dimension::{LensEffect, LensModel, WarpCoverage};
+        let mut lens = LensEffect::new(LensModel::radial(-0.1, 0.0, 0.0), 900.0, (500.0, 400.0));
+        lens.fx = 0.0;
+        let r = Pipeline::new(1000, 800).effect(lens).fit(500, 500).plan();
+        assert_eq!(*r.unwrap_err().error(), LayoutError::InvalidEffect);
+
+        let fisheye = LensEffect::new(
+            LensModel::Fisheye {
+                k1: -0.5,
+                k2: 0.0,
+                k3: 0.0,
+                k4: 0.0,
+            },
+            300.0,
+            (500.0, 400.0),
+        )
+        .with_coverage(WarpCoverage::Expand {
+            color: CanvasColor::Transparent,
+        });
+        let commands = [
+            Command::Effect(alloc::boxed::Box::new(fisheye)),
+            Command::Constrain(Constraint::new(ConstraintMode::Fit, 500, 500)),
+        ];
+        let r = compute_layout_sequential(&commands, 1000, 800, None);
+        assert_eq!(*r.unwrap_err().error(), LayoutError::InvalidEffect);
+    }
+
+    #[test]
+    fn invalid_equirect_view_is_an_error() {
+        use crate::dimension::EquirectViewEffect;
+        let view = EquirectViewEffect::from_degrees(0.0, 0.0, 0.0, 200.0, 2.0);
+        let r = Pipeline::new(8000, 4000).effect(view).fit(400, 400).plan();
+        assert_eq!(*r.unwrap_err().error(), LayoutError::InvalidEffect);
+        let r = Pipeline::new(8000, 4000)
+            .sequential()
+            .effect(view)
+            .fit(400, 400)
+            .plan();
+        assert_eq!(*r.unwrap_err().error(), LayoutError::InvalidEffect);
+
+        let view = EquirectViewEffect::from_degrees(0.0, 0.0, 0.0, 90.0, 2.0);
+        assert!(
+            Pipeline::new(8000, 4000)
+                .effect(view)
+                .fit(400, 400)
+                .plan()
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn sequential_pad_blocks_fusion() {
+        use crate::dimension::{ExpandEffect, RotateEffect, RotateMode};
+        let commands = [
+            Command::Constrain(Constraint::new(ConstraintMode::Fit, 400, 400)),
+            Command::Effect(alloc::boxed::Box::new(RotateEffect::from_degrees(
+                3.0,
+                RotateMode::CropToOriginal,
+            ))),
+            Command::Pad(Padding::uniform(5, CanvasColor::white())),
+            Command::Effect(alloc::boxed::Box::new(tilt_warp())),
+            // Not matrix-expressible: ends the run.
+            Command::Effect(alloc::boxed::Box::new(ExpandEffect {
+                left: 1,
+                top: 1,
+                right: 1,
+                bottom: 1,
+            })),
+        ];
+        let (ideal, _) = compute_layout_sequential(&commands, 800, 600, None).unwrap();
+        assert_eq!(ideal.effects.len(), 3);
+        assert!(ideal.effects.iter().all(|e| !e.before_resize));
+    }
+
+    #[test]
+    fn sequential_folds_cardinal_rotation() {
+        use crate::dimension::{RotateEffect, RotateMode};
+        let commands = [
+            Command::Effect(alloc::boxed::Box::new(RotateEffect::from_degrees(
+                90.0,
+                RotateMode::Expand {
+                    color: CanvasColor::Transparent,
+                },
+            ))),
+            Command::Constrain(Constraint::new(ConstraintMode::Fit, 300, 400)),
+        ];
+        let (ideal, request) = compute_layout_sequential(&commands, 800, 600, None).unwrap();
+        assert!(ideal.effects.is_empty());
+        assert_eq!(ideal.orientation, Orientation::Rotate90);
+        assert_eq!(request.orientation, Orientation::Rotate90);
+        assert_eq!(ideal.layout.source, Size::new(600, 800));
+        assert_eq!(ideal.layout.resize_to, Size::new(300, 400));
+    }
+
+    #[test]
+    fn sequential_cardinal_after_arbitrary_not_folded() {
+        use crate::dimension::{RotateEffect, RotateMode};
+        let commands = [
+            Command::Rotate(Rotation::Rotate180),
+            Command::Effect(alloc::boxed::Box::new(RotateEffect::from_degrees(
+                180.0,
+                RotateMode::CropToOriginal,
+            ))),
+            Command::Effect(alloc::boxed::Box::new(tilt_warp())),
+            Command::Effect(alloc::boxed::Box::new(RotateEffect::from_degrees(
+                -90.0,
+                RotateMode::Expand {
+                    color: CanvasColor::Transparent,
+                },
+            ))),
+        ];
+        let (ideal, _) = compute_layout_sequential(&commands, 800, 600, None).unwrap();
+        // The leading 180° folds (cancelling the command); the trailing 270°
+        // follows a warp, so it fuses instead.
+        assert_eq!(ideal.orientation, Orientation::Identity);
+        assert_eq!(ideal.effects.len(), 1);
+        assert_eq!(ideal.effects[0].command_index, 2);
+    }
+
+    // ── Rotated crop ──
+
+    #[test]
+    fn rotated_crop_cardinal_plans_as_orientation_and_region() {
+        use crate::dimension::RotatedCropEffect;
+        // The box is drawn on the rotated view; turning it back cancels the
+        // rotation, leaving a plain crop of the stored pixels.
+        let crop = RotatedCropEffect::from_degrees((300.0, 400.0), 200.0, 100.0, 90.0);
+        let (ideal, _) = Pipeline::new(800, 600)
+            .rotate_90()
+            .rotated_crop(crop)
+            .plan()
+            .unwrap();
+        assert!(ideal.effects.is_empty());
+        assert_eq!(ideal.orientation, Orientation::Identity);
+        assert_eq!(
+            ideal.layout.source_crop,
+            Some(Rect::new(300, 250, 200, 100))
+        );
+        assert_eq!(ideal.layout.canvas, Size::new(200, 100));
+
+        let (seq, _) = Pipeline::new(800, 600)
+            .sequential()
+            .rotate_90()
+            .rotated_crop(crop)
+            .plan()
+            .unwrap();
+        assert!(seq.effects.is_empty());
+        assert_eq!(seq.orientation, Orientation::Identity);
+        assert_eq!(seq.layout.canvas, Size::new(200, 100));
+    }
+
+    #[test]
+    fn rotated_crop_tilted_or_after_crop_is_effect() {
+        use crate::dimension::RotatedCropEffect;
+        let tilted = RotatedCropEffect::from_degrees((400.0, 300.0), 200.0, 100.0, 12.0);
+        let (ideal, _) = Pipeline::new(800, 600).rotated_crop(tilted).plan().unwrap();
+        assert_eq!(ideal.effects.len(), 1);
+        assert_eq!(ideal.layout.canvas, Size::new(200, 100));
+
+        let square = RotatedCropEffect::from_degrees((100.0, 100.0), 50.0, 40.0, 180.0);
+        let (ideal, _) = Pipeline::new(800, 600)
+            .sequential()
+            .crop_pixels(100, 100, 400, 300)
+            .rotated_crop(square)
+            .plan()
+            .unwrap();
+        assert_eq!(ideal.effects.len(), 1);
+        assert_eq!(ideal.effects[0].input_dims, Size::new(400, 300));
+        assert_eq!(ideal.effects[0].output_dims, Size::new(50, 40));
+        assert_eq!(ideal.source_crop, Some(Rect::new(100, 100, 400, 300)));
+        assert_eq!(ideal.layout.source, Size::new(50, 40));
+        assert_eq!(ideal.layout.source_crop, None);
+        assert_eq!(ideal.layout.canvas, Size::new(50, 40));
+
+        // Fixed mode: the crop is in source pixels, so it runs before the
+        // resize even when added after the constraint.
+        let (ideal, _) = Pipeline::new(800, 600)
+            .fit(100, 100)
+            .rotated_crop(tilted)
+            .plan()
+            .unwrap();
+        assert_eq!(ideal.effects.len(), 1);
+        assert!(ideal.effects[0].before_resize);
+        assert_eq!(ideal.effects[0].input_dims, Size::new(800, 600));
+        assert_eq!(ideal.effects[0].output_dims, Size::new(200, 100));
+        assert_eq!(ideal.layout.resize_to, Size::new(100, 50));
+    }
+
+    #[test]
+    fn sequential_crops_keep_their_place_among_effects() {
+        use crate::dimension::{DimensionEffect, RotateEffect, RotateMode};
+        let rotate = RotateEffect::from_degrees(
+            10.0,
+            RotateMode::Expand {
+                color: CanvasColor::Transparent,
+            },
+        );
+
+        // Effect then crop: the crop frames the effect's output and is not
+        // sent to the decoder.
+        let (ideal, _) = Pipeline::new(800, 600)
+            .sequential()
+            .effect(rotate)
+            .crop_pixels(0, 0, 100, 100)
+            .plan()
+            .unwrap();
+        assert_eq!(ideal.source_crop, None);
+        assert_eq!(ideal.effects[0].input_dims, Size::new(800, 600));
+        assert_eq!(ideal.layout.canvas, Size::new(100, 100));
+
+        // Crop, effect, crop, effect: the middle crop acts on the first
+        // effect's output and stays a separate step.
+        let (ideal, _) = Pipeline::new(800, 600)
+            .sequential()
+            .crop_pixels(100, 100, 400, 300)
+            .effect(rotate)
+            .crop_pixels(10, 10, 200, 150)
+            .effect(rotate)
+            .plan()
+            .unwrap();
+        let (w, h) = rotate.forward(200, 150).unwrap();
+        assert_eq!(ideal.source_crop, Some(Rect::new(100, 100, 400, 300)));
+        assert_eq!(ideal.effects.len(), 3);
+        assert_eq!(ideal.effects[0].input_dims, Size::new(400, 300));
+        assert_eq!(ideal.effects[1].command_index, 2);
+        assert_eq!(ideal.effects[1].output_dims, Size::new(200, 150));
+        assert_eq!(ideal.effects[2].input_dims, Size::new(200, 150));
+        assert_eq!(ideal.effects[2].output_dims, Size::new(w, h));
+        assert_eq!(ideal.layout.canvas, Size::new(w, h));
+    }
+
+    #[test]
+    fn sequential_does_not_fuse_past_a_crop() {
+        use crate::dimension::{RotateEffect, RotateMode};
+        let expand = |deg| {
+            Command::Effect(alloc::boxed::Box::new(RotateEffect::from_degrees(
+                deg,
+                RotateMode::Expand {
+                    color: CanvasColor::Transparent,
+                },
+            )))
+        };
+        // A crop between two rotations: one resample would bring back the
+        // cropped-away pixels in the second rotation's corners.
+        let commands = [
+            expand(12.0),
+            Command::Crop(SourceCrop::pixels(100, 100, 400, 300)),
+            expand(-12.0),
+            Command::Constrain(Constraint::new(ConstraintMode::Fit, 400, 400)),
+        ];
+        let (ideal, _) = compute_layout_sequential(&commands, 800, 600, None).unwrap();
+        let indices: Vec<usize> = ideal.effects.iter().map(|e| e.command_index).collect();
+        assert_eq!(indices, [0, 1, 2]);
+        assert_eq!(ideal.effects[1].output_dims, Size::new(400, 300));
+        assert_eq!(ideal.effects[2].input_dims, Size::new(400, 300));
+
+        // Likewise a rotation that clips followed by one that fills.
+        let commands = [
+            Command::Effect(alloc::boxed::Box::new(RotateEffect::from_degrees(
+                12.0,
+                RotateMode::InscribedCrop,
+            ))),
+            expand(-12.0),
+        ];
+        let (ideal, _) = compute_layout_sequential(&commands, 800, 600, None).unwrap();
+        let indices: Vec<usize> = ideal.effects.iter().map(|e| e.command_index).collect();
+        assert_eq!(indices, [0, 1]);
+
+        // Filling first and clipping after is still one resample.
+        let commands = [
+            expand(12.0),
+            Command::Effect(alloc::boxed::Box::new(RotateEffect::from_degrees(
+                -12.0,
+                RotateMode::InscribedCrop,
+            ))),
+        ];
+        let (ideal, _) = compute_layout_sequential(&commands, 800, 600, None).unwrap();
+        assert_eq!(ideal.effects.len(), 1);
+    }
+
+    // ── Smart gravity ──
+
+    #[cfg(feature = "smart-crop")]
+    fn subject(x1: f32, y1: f32, x2: f32, y2: f32) -> crate::smart_crop::SmartCropInput {
+        crate::smart_crop::SmartCropInput {
+            focus_regions: vec![crate::smart_crop::FocusRect {
+                x1,
+                y1,
+                x2,
+                y2,
+                weight: 1.0,
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[cfg(feature = "smart-crop")]
+    #[test]
+    fn smart_gravity_places_crop_modes() {
+        use crate::smart_crop::CropConfig;
+        let left = subject(2.0, 30.0, 15.0, 60.0);
+        let (ideal, _) = Pipeline::new(1600, 900)
+            .aspect_crop(1, 1)
+            .smart_gravity(left.clone(), CropConfig::default())
+            .plan()
+            .unwrap();
+        assert_eq!(ideal.layout.source_crop, Some(Rect::new(0, 0, 900, 900)));
+
+        // Mixed WithinCrop keeps a 1000-wide window and slides it left.
+        let (ideal, _) = Pipeline::new(1600, 900)
+            .within_crop(1000, 1000)
+            .smart_gravity(left.clone(), CropConfig::default())
+            .plan()
+            .unwrap();
+        assert_eq!(ideal.layout.source_crop, Some(Rect::new(0, 0, 1000, 900)));
+
+        // Non-crop modes keep their layout.
+        let (ideal, _) = Pipeline::new(1600, 900)
+            .fit(400, 400)
+            .smart_gravity(left, CropConfig::default())
+            .plan()
+            .unwrap();
+        assert_eq!(ideal.layout.source_crop, None);
+    }
+
+    #[cfg(feature = "smart-crop")]
+    #[test]
+    fn smart_gravity_maps_through_orientation_and_crop() {
+        use crate::smart_crop::CropConfig;
+        // Stored portrait; rotating 90° clockwise moves the bottom of the
+        // stored image to the left of the display.
+        let input = subject(30.0, 85.0, 60.0, 95.0);
+        let plan = |sequential: bool, smart: bool| {
+            let mut p = Pipeline::new(900, 1600);
+            if sequential {
+                p = p.sequential();
+            }
+            p = p
+                .rotate_90()
+                .crop_pixels(0, 0, 1200, 900)
+                .fit_crop(400, 400);
+            if smart {
+                p = p.smart_gravity(input.clone(), CropConfig::default());
+            }
+            p.plan().unwrap().0.layout.source_crop.unwrap()
+        };
+        assert_eq!(plan(false, false), Rect::new(150, 0, 900, 900));
+        assert_eq!(plan(false, true), Rect::new(0, 0, 900, 900));
+        assert_eq!(plan(true, true), Rect::new(0, 0, 900, 900));
+    }
 }
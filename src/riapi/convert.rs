@@ -10,6 +10,7 @@ use crate::plan::Pipeline;
 use whereat::At;
 
 use super::instructions::{Anchor1D, FitMode, Instructions, ScaleMode};
+use super::parse::parse_fit_mode;
 
 impl Instructions {
     /// Build a zenlayout [`Pipeline`] from these instructions.
@@ -17,6 +18,12 @@ impl Instructions {
     /// `source_w` / `source_h`: original source image dimensions (pre-orientation).
     /// `exif`: EXIF orientation tag (1–8), if known.
     ///
+    /// With the `smart-crop` feature, `c.focus` places the crop through
+    /// `Pipeline::smart_gravity`, `c.zoom` zooms onto it and `c.finalmode`
+    /// fits the placed crop in place of `mode` (`Pipeline::smart_final_mode`). The `faces`, `saliency` and `auto` keywords leave the
+    /// pipeline waiting on `Pipeline::pending_detection`; with the `saliency`
+    /// feature, `Pipeline::resolve_saliency` resolves them without a detector.
+    ///
     /// Returns `Err` only if the resulting layout is invalid (e.g. zero dimensions).
     #[track_caller]
     pub fn to_pipeline(
@@ -115,6 +122,10 @@ impl Instructions {
 
         // ---- 7. Map mode × scale → ConstraintMode ----
         let constraint_mode = map_mode_scale(mode, scale, target_w, target_h, eff_w, eff_h);
+        #[cfg_attr(not(feature = "smart-crop"), allow(unused_variables))]
+        let final_mode = self
+            .final_mode()
+            .and_then(|m| map_mode_scale(m, scale, target_w, target_h, eff_w, eff_h));
 
         // ---- 8. Gravity ----
         let gravity = self.resolve_gravity();
@@ -189,9 +200,82 @@ impl Instructions {
             pipeline = pipeline.constrain(constraint);
         }
 
+        // ---- 11. Smart crop focus ----
+        #[cfg(feature = "smart-crop")]
+        {
+            pipeline = self.apply_focus(pipeline, src_orient, disp_w, disp_h);
+            if let Some(m) = final_mode {
+                pipeline = pipeline.smart_final_mode(m);
+            }
+        }
+
         Ok(pipeline)
     }
 
+    /// `c.finalmode`, which fits the crop `c.focus` placed in place of `mode`.
+    ///
+    /// `mode` still decides the crop: its window is placed around the focus
+    /// at the target aspect, then this mode fits that window to the target.
+    fn final_mode(&self) -> Option<FitMode> {
+        if !cfg!(feature = "smart-crop") || self.c_focus.is_none() {
+            return None;
+        }
+        parse_fit_mode(self.c_finalmode.as_deref()?)
+    }
+
+    /// Steer the crop with `c.focus`, zooming in with `c.zoom`.
+    ///
+    /// Focus coordinates are percentages of the displayed source, after
+    /// autorotate, `srotate` and `sflip`. A point focus never zooms. Keywords
+    /// leave the pipeline waiting on detection.
+    #[cfg(feature = "smart-crop")]
+    fn apply_focus(
+        &self,
+        pipeline: Pipeline,
+        src_orient: Orientation,
+        disp_w: u32,
+        disp_h: u32,
+    ) -> Pipeline {
+        use super::instructions::CFocus;
+        use crate::smart_crop::{CropConfig, CropMode, Detection, FocusRect, SmartCropInput};
+
+        let mut config = CropConfig {
+            mode: if self.c_zoom == Some(true) {
+                CropMode::Maximal
+            } else {
+                CropMode::Minimal
+            },
+            ..CropConfig::default()
+        };
+        let focus_regions = match &self.c_focus {
+            None => return pipeline,
+            Some(CFocus::Faces) => return pipeline.await_detection(Detection::Faces, config),
+            Some(CFocus::Saliency) => return pipeline.await_detection(Detection::Saliency, config),
+            Some(CFocus::Auto) => {
+                return pipeline.await_detection(Detection::FacesAndSaliency, config);
+            }
+            Some(CFocus::Point([x, y])) => {
+                config.mode = CropMode::Minimal;
+                // A 1% box, so the point has an extent to carry through orientation.
+                let (x, y) = (*x as f32, *y as f32);
+                alloc::vec![FocusRect {
+                    x1: x - 0.5,
+                    y1: y - 0.5,
+                    x2: x + 0.5,
+                    y2: y + 0.5,
+                    weight: 1.0,
+                }]
+            }
+            Some(CFocus::Rects(_)) => self.focus_rects(),
+        };
+        let input = SmartCropInput {
+            focus_regions,
+            ..SmartCropInput::default()
+        }
+        .oriented(src_orient.inverse(), disp_w, disp_h);
+        pipeline.smart_gravity(input, config)
+    }
+
     /// Resolve crop from RIAPI crop parameters to a SourceCrop.
     fn resolve_crop(&self, display_w: u32, display_h: u32) -> Option<SourceCrop> {
         let crop = self.crop.as_ref()?;
@@ -415,6 +499,8 @@ fn map_mode_scale(
 mod tests {
     use super::*;
     use crate::constraint::Size;
+    #[cfg(feature = "smart-crop")]
+    use alloc::vec;
 
     /// Helper: parse query, build pipeline, plan it, return resize_to dimensions.
     fn query_to_resize(query: &str, sw: u32, sh: u32) -> Size {
@@ -814,4 +900,86 @@ mod tests {
         let canvas = query_to_canvas("w=800&h=600&mode=crop&scale=canvas", 400, 1000);
         assert_eq!(canvas, Size::new(800, 600));
     }
+
+    // ---- Smart crop focus ----
+
+    #[cfg(feature = "smart-crop")]
+    fn query_to_crop(query: &str, sw: u32, sh: u32, exif: Option<u8>) -> crate::Rect {
+        let result = crate::riapi::parse(query);
+        let pipeline = result.instructions.to_pipeline(sw, sh, exif).unwrap();
+        let (ideal, _) = pipeline.plan().unwrap();
+        ideal.layout.source_crop.expect("crop expected")
+    }
+
+    #[cfg(feature = "smart-crop")]
+    #[test]
+    fn c_focus_steers_crop() {
+        let q = "w=400&h=400&mode=crop&c.focus=80,30,95,60";
+        let crop = query_to_crop(q, 1600, 900, None);
+        assert_eq!(crop.x + crop.width, 1600);
+
+        // Points are in displayed coordinates: EXIF 6 turns a stored
+        // portrait into a landscape, and the point stays on its right.
+        let q = "w=400&h=400&mode=crop&c.focus=95,50";
+        let crop = query_to_crop(q, 900, 1600, Some(6));
+        assert_eq!(crop, crate::Rect::new(700, 0, 900, 900));
+    }
+
+    #[cfg(feature = "smart-crop")]
+    #[test]
+    fn c_zoom_crops_around_focus() {
+        let q = "w=400&h=400&mode=crop&c.focus=40,40,60,60&c.zoom=true";
+        let crop = query_to_crop(q, 1600, 900, None);
+        assert!(crop.width < 900 && crop.width == crop.height);
+        assert!(crop.x <= 640 && crop.x + crop.width >= 960);
+        assert!(crop.y <= 360 && crop.y + crop.height >= 540);
+        assert_eq!(query_to_resize(q, 1600, 900), Size::new(400, 400));
+    }
+
+    #[cfg(feature = "smart-crop")]
+    #[test]
+    fn c_finalmode_fits_the_focus_crop() {
+        // Without a focus, c.finalmode is ignored.
+        let q = "w=400&h=400&mode=crop&c.finalmode=max";
+        assert_eq!(query_to_crop(q, 1600, 900, None).x, 350);
+        assert_eq!(query_to_resize(q, 1600, 900), Size::new(400, 400));
+
+        // mode=crop still places the square crop around the focus, and max
+        // then fits that crop rather than the whole source.
+        let q = "w=400&h=400&mode=crop&c.focus=5,40,15,60&c.finalmode=max";
+        let focused = query_to_crop(q, 1600, 900, None);
+        assert_eq!(focused, crate::Rect::new(0, 0, 900, 900));
+        assert_eq!(query_to_resize(q, 1600, 900), Size::new(400, 400));
+        let q = "w=400&h=400&mode=crop&c.focus=5,40,15,60";
+        assert_eq!(query_to_crop(q, 1600, 900, None), focused);
+    }
+
+    #[cfg(feature = "smart-crop")]
+    #[test]
+    fn c_focus_keyword_waits_for_detection() {
+        use crate::smart_crop::{Detection, FocusRect, SmartCropInput};
+        let result = crate::riapi::parse("w=400&h=400&mode=crop&c.focus=auto");
+        let pipeline = result.instructions.to_pipeline(1600, 900, None).unwrap();
+        assert_eq!(
+            pipeline.pending_detection(),
+            Some(Detection::FacesAndSaliency)
+        );
+
+        // Unresolved, the crop falls back to the constraint's gravity.
+        let (ideal, _) = pipeline.clone().plan().unwrap();
+        assert_eq!(ideal.layout.source_crop.unwrap().x, 350);
+
+        let faces = SmartCropInput {
+            focus_regions: vec![FocusRect {
+                x1: 2.0,
+                y1: 30.0,
+                x2: 12.0,
+                y2: 50.0,
+                weight: 1.0,
+            }],
+            ..SmartCropInput::default()
+        };
+        let (ideal, _) = pipeline.resolve_detection(faces).plan().unwrap();
+        assert_eq!(ideal.layout.source_crop.unwrap().x, 0);
+    }
 }
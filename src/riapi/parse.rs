@@ -292,13 +292,21 @@ fn dispatch_key(key: &str, value: &str, inst: &mut Instructions, warnings: &mut
             }
         }
         "c.finalmode" => {
-            set_or_warn(
-                &mut inst.c_finalmode,
-                Some(String::from(value)),
-                key,
-                value,
-                warnings,
-            );
+            if parse_fit_mode(value).is_some() {
+                set_or_warn(
+                    &mut inst.c_finalmode,
+                    Some(String::from(value)),
+                    key,
+                    value,
+                    warnings,
+                );
+            } else {
+                warnings.push(ParseWarning::ValueInvalid {
+                    key: "c.finalmode",
+                    value: String::from(value),
+                    reason: "expected max|pad|crop|stretch|aspectcrop",
+                });
+            }
         }
 
         // Background color
@@ -376,7 +384,7 @@ fn parse_bool(s: &str) -> Option<bool> {
     }
 }
 
-fn parse_fit_mode(s: &str) -> Option<FitMode> {
+pub(super) fn parse_fit_mode(s: &str) -> Option<FitMode> {
     match s.trim().to_ascii_lowercase().as_str() {
         "max" => Some(FitMode::Max),
         "pad" => Some(FitMode::Pad),
@@ -785,6 +793,23 @@ mod tests {
         assert_eq!(inst.c_gravity, Some([30.0, 70.0]));
     }
 
+    #[test]
+    fn parse_c_finalmode_validates() {
+        let (inst, warnings) = parse_query("c.finalmode=Max");
+        assert!(warnings.is_empty());
+        assert_eq!(inst.c_finalmode.as_deref(), Some("Max"));
+
+        let (inst, warnings) = parse_query("c.finalmode=zoom");
+        assert_eq!(inst.c_finalmode, None);
+        assert!(warnings.iter().any(|w| matches!(
+            w,
+            ParseWarning::ValueInvalid {
+                key: "c.finalmode",
+                ..
+            }
+        )));
+    }
+
     #[test]
     fn parse_bgcolor_hex() {
         let (inst, _) = parse_query("bgcolor=ff0000");
@@ -0,0 +1,356 @@
+//! Lightweight saliency estimation for smart cropping.
+//!
+//! Turns a small, downsampled copy of the image into a [`HeatMap`] from
+//! edge energy, local contrast and a center bias — enough to resolve
+//! [`Detection::Saliency`](crate::smart_crop::Detection::Saliency) without
+//! an ML backend. This is the only part of the crate that reads pixels;
+//! feed it a thumbnail (64–256 px on the long side), not the full image.
+//!
+//! # Example
+//!
+//! ```
+//! use zenlayout::saliency::{PixelFormat, SaliencyConfig, estimate_saliency};
+//!
+//! // A flat gray 64×36 thumbnail with a bright patch on the right.
+//! let (w, h) = (64, 36);
+//! let mut luma = vec![128u8; w * h];
+//! for y in 12..24 {
+//!     for x in 44..56 {
+//!         luma[y * w + x] = 250;
+//!     }
+//! }
+//! let heatmap = estimate_saliency(&luma, w as u32, h as u32, PixelFormat::Luma8, &SaliencyConfig::default())
+//!     .unwrap();
+//! assert!(heatmap.value_at(78.0, 50.0) > heatmap.value_at(20.0, 50.0));
+//! ```
+//!
+//! Requires the `saliency` feature.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+#[allow(unused_imports)]
+use crate::float_math::Float;
+use crate::smart_crop::HeatMap;
+
+/// Pixel layout of the buffer passed to [`estimate_saliency`].
+///
+/// Rows are tightly packed, 8 bits per channel.
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PixelFormat {
+    /// One luma byte per pixel.
+    Luma8,
+    /// Red, green, blue.
+    Rgb8,
+    /// Red, green, blue, alpha. Alpha is ignored.
+    Rgba8,
+}
+
+impl PixelFormat {
+    /// Bytes per pixel.
+    pub const fn channels(self) -> usize {
+        match self {
+            Self::Luma8 => 1,
+            Self::Rgb8 => 3,
+            Self::Rgba8 => 4,
+        }
+    }
+}
+
+/// Configuration for [`estimate_saliency`].
+#[derive(Debug, Clone)]
+pub struct SaliencyConfig {
+    /// Weight of edge energy (Sobel gradient magnitude) (default: 1.0).
+    pub edge_weight: f32,
+    /// Weight of local contrast against the surrounding mean (default: 1.0).
+    pub contrast_weight: f32,
+    /// How strongly the image center is favored, from 0.0 (not at all) to
+    /// 1.0 (the edges count for nothing) (default: 0.3).
+    pub center_bias: f32,
+    /// Heatmap cells along the longer side, capped at the buffer's own size
+    /// (default: 32).
+    pub grid_size: u32,
+}
+
+impl Default for SaliencyConfig {
+    fn default() -> Self {
+        Self {
+            edge_weight: 1.0,
+            contrast_weight: 1.0,
+            center_bias: 0.3,
+            grid_size: 32,
+        }
+    }
+}
+
+/// Estimate a saliency [`HeatMap`] from a downsampled image buffer.
+///
+/// Values are normalized so the most salient cell is 1.0. The heatmap
+/// covers the whole buffer, so it lines up with the image the buffer was
+/// downsampled from. A featureless image gives an all-zero heatmap.
+///
+/// Returns `None` if a dimension is zero or `pixels` is shorter than
+/// `width × height` pixels of `format`.
+pub fn estimate_saliency(
+    pixels: &[u8],
+    width: u32,
+    height: u32,
+    format: PixelFormat,
+    config: &SaliencyConfig,
+) -> Option<HeatMap> {
+    let (w, h) = (width as usize, height as usize);
+    let len = w.checked_mul(h)?;
+    if len == 0 || pixels.len() < len.checked_mul(format.channels())? {
+        return None;
+    }
+    let luma = to_luma(pixels, len, format);
+
+    let mut edges = sobel(&luma, w, h);
+    let mut contrast = local_contrast(&luma, w, h);
+    normalize(&mut edges);
+    normalize(&mut contrast);
+
+    let ew = config.edge_weight.max(0.0);
+    let cw = config.contrast_weight.max(0.0);
+    let total = ew + cw;
+    let features = if total > 0.0 {
+        edges
+            .iter()
+            .zip(&contrast)
+            .map(|(&e, &c)| (e * ew + c * cw) / total)
+            .collect()
+    } else {
+        vec![0.0; len]
+    };
+    // Edges and contrast light up outlines; spread them so the interior
+    // of an object scores too.
+    let features = box_blur(&features, w, h, neighborhood(w, h));
+
+    let mut heatmap = pool(&features, w, h, config.grid_size);
+    apply_center_bias(&mut heatmap, config.center_bias);
+    normalize(&mut heatmap.data);
+    Some(heatmap)
+}
+
+// ---------------------------------------------------------------------------
+// Feature maps
+// ---------------------------------------------------------------------------
+
+/// Luma in \[0, 1\], BT.601 weights for color input.
+fn to_luma(pixels: &[u8], len: usize, format: PixelFormat) -> Vec<f32> {
+    let channels = format.channels();
+    pixels
+        .chunks_exact(channels)
+        .take(len)
+        .map(|px| match format {
+            PixelFormat::Luma8 => px[0] as f32 / 255.0,
+            PixelFormat::Rgb8 | PixelFormat::Rgba8 => {
+                (0.299 * px[0] as f32 + 0.587 * px[1] as f32 + 0.114 * px[2] as f32) / 255.0
+            }
+        })
+        .collect()
+}
+
+/// Sobel gradient magnitude, with edge pixels repeated past the border.
+fn sobel(luma: &[f32], w: usize, h: usize) -> Vec<f32> {
+    let at = |x: isize, y: isize| -> f32 {
+        let x = x.clamp(0, w as isize - 1) as usize;
+        let y = y.clamp(0, h as isize - 1) as usize;
+        luma[y * w + x]
+    };
+    let mut out = vec![0.0; w * h];
+    for y in 0..h as isize {
+        for x in 0..w as isize {
+            let gx = at(x + 1, y - 1) + 2.0 * at(x + 1, y) + at(x + 1, y + 1)
+                - at(x - 1, y - 1)
+                - 2.0 * at(x - 1, y)
+                - at(x - 1, y + 1);
+            let gy = at(x - 1, y + 1) + 2.0 * at(x, y + 1) + at(x + 1, y + 1)
+                - at(x - 1, y - 1)
+                - 2.0 * at(x, y - 1)
+                - at(x + 1, y - 1);
+            out[y as usize * w + x as usize] = (gx * gx + gy * gy).sqrt();
+        }
+    }
+    out
+}
+
+/// Distance of each pixel from the mean of its neighborhood.
+fn local_contrast(luma: &[f32], w: usize, h: usize) -> Vec<f32> {
+    let mean = box_blur(luma, w, h, neighborhood(w, h));
+    luma.iter()
+        .zip(&mean)
+        .map(|(&l, &m)| (l - m).abs())
+        .collect()
+}
+
+/// Radius of the neighborhood for contrast and smoothing: a box an eighth
+/// of the longer side across.
+fn neighborhood(w: usize, h: usize) -> usize {
+    (w.max(h) / 16).max(1)
+}
+
+/// Mean over a `(2 × radius + 1)`-pixel box, shrunk at the borders.
+fn box_blur(values: &[f32], w: usize, h: usize, radius: usize) -> Vec<f32> {
+    // Summed-area table with a zero row and column in front.
+    let mut sat = vec![0.0_f64; (w + 1) * (h + 1)];
+    for y in 0..h {
+        let mut row = 0.0;
+        for x in 0..w {
+            row += values[y * w + x] as f64;
+            sat[(y + 1) * (w + 1) + x + 1] = sat[y * (w + 1) + x + 1] + row;
+        }
+    }
+    let mut out = vec![0.0; w * h];
+    for y in 0..h {
+        let (y0, y1) = (y.saturating_sub(radius), (y + radius + 1).min(h));
+        for x in 0..w {
+            let (x0, x1) = (x.saturating_sub(radius), (x + radius + 1).min(w));
+            let sum = sat[y1 * (w + 1) + x1] - sat[y0 * (w + 1) + x1] - sat[y1 * (w + 1) + x0]
+                + sat[y0 * (w + 1) + x0];
+            out[y * w + x] = (sum / ((x1 - x0) * (y1 - y0)) as f64) as f32;
+        }
+    }
+    out
+}
+
+// ---------------------------------------------------------------------------
+// Heatmap assembly
+// ---------------------------------------------------------------------------
+
+/// Average `features` into a grid with `grid_size` cells on the longer side.
+fn pool(features: &[f32], w: usize, h: usize, grid_size: u32) -> HeatMap {
+    let long = w.max(h);
+    let cells = (grid_size as usize).clamp(1, long);
+    let gw = ((w * cells) as f64 / long as f64).round().max(1.0) as usize;
+    let gh = ((h * cells) as f64 / long as f64).round().max(1.0) as usize;
+    let mut sums = vec![0.0_f32; gw * gh];
+    let mut counts = vec![0_u32; gw * gh];
+    for y in 0..h {
+        let row = y * gh / h;
+        for x in 0..w {
+            let i = row * gw + x * gw / w;
+            sums[i] += features[y * w + x];
+            counts[i] += 1;
+        }
+    }
+    let data = sums
+        .iter()
+        .zip(&counts)
+        .map(|(&s, &n)| if n > 0 { s / n as f32 } else { 0.0 })
+        .collect();
+    HeatMap {
+        data,
+        width: gw as u32,
+        height: gh as u32,
+    }
+}
+
+/// Fade cells toward the edges with a Gaussian around the center.
+fn apply_center_bias(heatmap: &mut HeatMap, bias: f32) {
+    let bias = bias.clamp(0.0, 1.0);
+    if bias == 0.0 {
+        return;
+    }
+    let (gw, gh) = (heatmap.width as f32, heatmap.height as f32);
+    for row in 0..heatmap.height {
+        let dy = (row as f32 + 0.5) / gh - 0.5;
+        for col in 0..heatmap.width {
+            let dx = (col as f32 + 0.5) / gw - 0.5;
+            let falloff = (-(dx * dx + dy * dy) / (2.0 * 0.25 * 0.25)).exp();
+            heatmap.data[(row * heatmap.width + col) as usize] *= 1.0 - bias + bias * falloff;
+        }
+    }
+}
+
+/// Scale so the largest value is 1.0. Input that is zero up to rounding
+/// error becomes all zero.
+fn normalize(values: &mut [f32]) {
+    let max = values.iter().copied().fold(0.0_f32, f32::max);
+    for v in values {
+        *v = if max > 1e-4 { *v / max } else { 0.0 };
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::smart_crop::{AspectRatio, CropConfig, compute_crop};
+
+    /// `w × h` luma buffer, gray with a bright `size`-pixel square at `(x, y)`.
+    fn patch(w: usize, h: usize, x: usize, y: usize, size: usize) -> Vec<u8> {
+        let mut luma = vec![100u8; w * h];
+        for row in y..y + size {
+            for col in x..x + size {
+                luma[row * w + col] = 240;
+            }
+        }
+        luma
+    }
+
+    #[test]
+    fn rejects_bad_buffers() {
+        let config = SaliencyConfig::default();
+        assert!(estimate_saliency(&[], 0, 10, PixelFormat::Luma8, &config).is_none());
+        assert!(estimate_saliency(&[0; 99], 10, 10, PixelFormat::Luma8, &config).is_none());
+        assert!(estimate_saliency(&[0; 100], 10, 10, PixelFormat::Rgb8, &config).is_none());
+        let flat = estimate_saliency(&[7; 100], 10, 10, PixelFormat::Luma8, &config).unwrap();
+        assert!(flat.data.iter().all(|&v| v == 0.0));
+    }
+
+    #[test]
+    fn finds_off_center_subject() {
+        let luma = patch(96, 54, 72, 20, 12);
+        let config = SaliencyConfig::default();
+        let hm = estimate_saliency(&luma, 96, 54, PixelFormat::Luma8, &config).unwrap();
+        assert_eq!((hm.width, hm.height), (32, 18));
+        assert!(hm.data.iter().all(|&v| (0.0..=1.0).contains(&v)));
+        assert!(hm.value_at(81.0, 48.0) > 0.5);
+        assert!(hm.value_at(20.0, 48.0) < 0.1);
+
+        // Same answer from RGBA.
+        let rgba: Vec<u8> = luma.iter().flat_map(|&l| [l, l, l, 255]).collect();
+        let color = estimate_saliency(&rgba, 96, 54, PixelFormat::Rgba8, &config).unwrap();
+        for (a, b) in hm.data.iter().zip(&color.data) {
+            assert!((a - b).abs() < 0.02);
+        }
+
+        // The crop follows it.
+        let crop_config = CropConfig {
+            target_aspect: AspectRatio { w: 1, h: 1 },
+            ..CropConfig::default()
+        };
+        let crop = compute_crop(1920, 1080, &[], Some(&hm), &crop_config).unwrap();
+        assert_eq!(crop.x + crop.width, 1920);
+    }
+
+    #[test]
+    fn center_bias_favors_middle() {
+        // Identical patches near the left edge and in the middle.
+        let mut luma = patch(64, 32, 2, 12, 8);
+        for row in 12..20 {
+            for col in 28..36 {
+                luma[row * 64 + col] = 240;
+            }
+        }
+        let biased = SaliencyConfig {
+            center_bias: 0.8,
+            ..SaliencyConfig::default()
+        };
+        let hm = estimate_saliency(&luma, 64, 32, PixelFormat::Luma8, &biased).unwrap();
+        assert!(hm.value_at(50.0, 50.0) > 2.0 * hm.value_at(9.0, 50.0));
+
+        let unbiased = SaliencyConfig {
+            center_bias: 0.0,
+            ..SaliencyConfig::default()
+        };
+        let hm = estimate_saliency(&luma, 64, 32, PixelFormat::Luma8, &unbiased).unwrap();
+        let (left, middle) = (hm.value_at(9.0, 50.0), hm.value_at(50.0, 50.0));
+        assert!((left - middle).abs() < 0.2 * middle);
+    }
+}
@@ -4,9 +4,18 @@
 //! optimal crop rectangles at various aspect ratios. Designed for UIs where
 //! users pick from several crop candidates overlaid on the source image.
 //!
-//! Two modes per aspect ratio:
+//! Modes per aspect ratio:
 //! - **Minimal**: largest crop at target ratio, positioned to keep subjects visible.
 //! - **Maximal**: tightest crop at target ratio, zoomed in on the subject.
+//! - **Balanced**: anywhere between the two, set by [`CropConfig::zoom`].
+//!
+//! [`compute_candidates`] returns several ranked crops per ratio with a
+//! [`CropMetrics`] score breakdown, spanning the sizes between the two modes.
+//! For video and bursts, [`compute_trajectory`] and [`CropTracker`] turn
+//! per-frame crops into a smooth path with bounded pan speed.
+//!
+//! [`HeatMap`] builders rasterize points and focus boxes, blend layers and
+//! follow orientation changes, so any detection can feed the heatmap path.
 //!
 //! # Usage
 //!
@@ -17,7 +26,7 @@
 //! let faces = vec![
 //!     FocusRect { x1: 40.0, y1: 30.0, x2: 60.0, y2: 60.0, weight: 0.9 },
 //! ];
-//! let input = SmartCropInput { focus_regions: faces, heatmap: None };
+//! let input = SmartCropInput { focus_regions: faces, heatmap: None, regions: vec![] };
 //!
 //! // Generate crop candidates for a UI picker
 //! let targets = [
@@ -32,9 +41,10 @@
 
 use alloc::vec::Vec;
 
-use crate::Rect;
+use crate::dimension::DimensionEffect;
 #[allow(unused_imports)]
 use crate::float_math::Float;
+use crate::{Gravity, Orientation, Rect, Size};
 
 /// A weighted region of interest, in percentage coordinates (0.0–100.0).
 ///
@@ -70,6 +80,18 @@ pub struct HeatMap {
     pub height: u32,
 }
 
+/// How [`HeatMap::combine`] merges layers.
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HeatMapBlend {
+    /// Per-cell maximum of the weighted layers.
+    Max,
+    /// Per-cell sum of the weighted layers, capped at 1.0.
+    Sum,
+    /// Per-cell weighted mean of the layers.
+    Weighted,
+}
+
 /// Target aspect ratio as integer width:height.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct AspectRatio {
@@ -92,6 +114,40 @@ pub enum CropMode {
     Minimal,
     /// Tightest crop at target ratio. Zooms in on the subject.
     Maximal,
+    /// Between the two, [`CropConfig::zoom`] of the way from the minimal
+    /// crop to the maximal one.
+    Balanced,
+}
+
+/// Where the subject lands within the crop.
+///
+/// Line-based compositions put the subject on the nearer of two lines per
+/// axis; [`CropConfig::subject_direction`] picks the line that leaves room
+/// in front of the subject. Several subjects are balanced about their
+/// weighted centroid, or centred as a group when the group spans more
+/// than the gap between the lines.
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Composition {
+    /// Horizontally centred, vertically at
+    /// [`focus_vertical_position`](CropConfig::focus_vertical_position).
+    #[default]
+    Centered,
+    /// Rule of thirds: lines at 1/3 and 2/3.
+    Thirds,
+    /// Golden ratio: lines at 0.382 and 0.618.
+    GoldenRatio,
+}
+
+impl Composition {
+    /// Distance of the first line from the crop edge, as a fraction.
+    fn line(self) -> Option<f64> {
+        match self {
+            Self::Centered => None,
+            Self::Thirds => Some(1.0 / 3.0),
+            Self::GoldenRatio => Some(2.0 - 1.618_033_988_749_895),
+        }
+    }
 }
 
 /// Configuration for [`compute_crop`].
@@ -110,6 +166,20 @@ pub struct CropConfig {
     /// Padding around the subject as a fraction of subject size
     /// (maximal mode, default: 0.5).
     pub zoom_padding: f32,
+    /// Where subjects land in the crop (default: [`Composition::Centered`]).
+    pub composition: Composition,
+    /// Direction the primary subject faces or moves, as `(x, y)` with +x
+    /// right and +y down (default: `None`). Line-based compositions leave
+    /// lead room on that side; components under 0.1 in magnitude count as
+    /// no preference.
+    pub subject_direction: Option<(f32, f32)>,
+    /// How far [`CropMode::Balanced`] zooms, from 0.0 (the minimal crop) to
+    /// 1.0 (the maximal crop) (default: 0.5).
+    pub zoom: f32,
+    /// Smallest crop in source pixels (default: `None`). Set it to the final
+    /// output size so the crop is never upscaled. Crops grow about their
+    /// centre to reach it, up to the largest crop at the target ratio.
+    pub min_crop_size: Option<Size>,
 }
 
 impl Default for CropConfig {
@@ -120,25 +190,207 @@ impl Default for CropConfig {
             focus_vertical_position: 0.38,
             min_focus_visibility: 0.7,
             zoom_padding: 0.5,
+            composition: Composition::Centered,
+            subject_direction: None,
+            zoom: 0.5,
+            min_crop_size: None,
+        }
+    }
+}
+
+/// A scored crop proposal from [`compute_candidates`].
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CropCandidate {
+    /// Crop rectangle in source pixels.
+    pub rect: Rect,
+    /// Overall score in \[0.0, 1.0\]; higher is better.
+    pub score: f32,
+    /// The measurements the score was built from.
+    pub metrics: CropMetrics,
+}
+
+/// Score breakdown for a [`CropCandidate`].
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CropMetrics {
+    /// Weighted fraction of focus-region area inside the crop
+    /// (1.0 when there are no qualifying regions).
+    pub focus_coverage: f32,
+    /// Fraction of total heatmap mass inside the crop
+    /// (1.0 without a heatmap or with an all-zero one).
+    pub heatmap_retained: f32,
+    /// Focus regions the crop edge cuts through (partly inside, partly out).
+    pub subjects_cut: u32,
+    /// How close the subject lands to the spot the configured
+    /// [`Composition`] puts it. 1.0 = exactly there, 0.0 = at a crop corner
+    /// or beyond.
+    pub composition: f32,
+    /// Content regions the crop violates (see [`RegionRole`]).
+    pub regions_violated: u32,
+}
+
+/// How a crop must treat a [`ContentRegion`].
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RegionRole {
+    /// Fully inside or fully outside the crop, never sliced through
+    /// (text blocks, logos).
+    KeepWhole,
+    /// Fully outside the crop (watermarks, burned-in captions).
+    Exclude,
+}
+
+/// A region the crop edge must respect, in percentage coordinates
+/// (0.0–100.0) like [`FocusRect`].
+///
+/// Unlike focus regions these are hard constraints: crops slide, then
+/// shrink, to honour them. [`SmartCropInput::compute_crop_report`] says
+/// which ones could not be honoured.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ContentRegion {
+    /// Left edge as percentage of image width (0.0–100.0).
+    pub x1: f32,
+    /// Top edge as percentage of image height (0.0–100.0).
+    pub y1: f32,
+    /// Right edge as percentage of image width (0.0–100.0).
+    pub x2: f32,
+    /// Bottom edge as percentage of image height (0.0–100.0).
+    pub y2: f32,
+    /// What the crop must do with this region.
+    pub role: RegionRole,
+}
+
+impl ContentRegion {
+    /// Pixel bounds, rounded outward.
+    fn pixels(&self, sw: f64, sh: f64) -> (f64, f64, f64, f64) {
+        (
+            (self.x1 as f64 / 100.0 * sw).floor(),
+            (self.y1 as f64 / 100.0 * sh).floor(),
+            (self.x2 as f64 / 100.0 * sw).ceil(),
+            (self.y2 as f64 / 100.0 * sh).ceil(),
+        )
+    }
+
+    /// Whether a crop at `(x, y)` of size `cw × ch` honours this region.
+    fn honoured_by(&self, x: f64, y: f64, cw: f64, ch: f64, sw: f64, sh: f64) -> bool {
+        let (rx1, ry1, rx2, ry2) = self.pixels(sw, sh);
+        let outside = x + cw <= rx1 || x >= rx2 || y + ch <= ry1 || y >= ry2;
+        let inside = x <= rx1 && x + cw >= rx2 && y <= ry1 && y + ch >= ry2;
+        match self.role {
+            RegionRole::KeepWhole => outside || inside,
+            RegionRole::Exclude => outside,
         }
     }
 }
 
+/// A crop from [`SmartCropInput::compute_crop_report`] and the content
+/// regions it could not honour.
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq)]
+pub struct CropReport {
+    /// Crop rectangle in source pixels.
+    pub rect: Rect,
+    /// Indices into [`SmartCropInput::regions`] that the crop violates.
+    /// Empty when every constraint holds.
+    pub unsatisfied: Vec<usize>,
+}
+
+impl CropReport {
+    /// Whether every content region is honoured.
+    pub fn is_satisfied(&self) -> bool {
+        self.unsatisfied.is_empty()
+    }
+}
+
+/// A detector whose output a crop is waiting on.
+///
+/// Recorded by [`Pipeline::await_detection`](crate::Pipeline::await_detection)
+/// for the caller's detection backend to run; its output comes back as a
+/// [`SmartCropInput`].
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Detection {
+    /// Face detection, reported as focus regions.
+    Faces,
+    /// Saliency estimation, reported as a heatmap.
+    Saliency,
+    /// Faces and saliency together.
+    FacesAndSaliency,
+}
+
+/// Limits on how a crop may move between frames, for
+/// [`compute_trajectory`] and [`CropTracker`].
+///
+/// Speeds are fractions of the source size per second: a pan of `0.5`
+/// crosses half the source width (or height) each second. Crop size is
+/// measured against the largest crop at the target ratio. A non-positive
+/// limit disables it.
+#[derive(Debug, Clone)]
+pub struct TrajectoryConfig {
+    /// Per-frame crop settings.
+    pub crop: CropConfig,
+    /// Fastest pan or zoom, in source fractions per second (default: 0.5).
+    pub max_velocity: f32,
+    /// Fastest change of velocity, in source fractions per second squared
+    /// (default: 2.0).
+    pub max_acceleration: f32,
+    /// Target moves smaller than this source fraction are ignored, so a
+    /// still subject gives a still crop (default: 0.02).
+    pub dead_zone: f32,
+}
+
+impl Default for TrajectoryConfig {
+    fn default() -> Self {
+        Self {
+            crop: CropConfig::default(),
+            max_velocity: 0.5,
+            max_acceleration: 2.0,
+            dead_zone: 0.02,
+        }
+    }
+}
+
+/// One frame of a video or burst for [`compute_trajectory`].
+#[derive(Debug, Clone, Default)]
+pub struct CropFrame {
+    /// Presentation time in seconds.
+    pub time: f64,
+    /// Subjects in this frame.
+    pub input: SmartCropInput,
+    /// This frame starts a new shot: the crop jumps to it instead of panning.
+    pub scene_cut: bool,
+}
+
 /// Input for smart crop computation.
 ///
-/// Holds focus regions and an optional heatmap from any source —
-/// ML detectors, manual annotation, or programmatic generation.
+/// Holds focus regions, an optional heatmap and content regions from any
+/// source — ML detectors, manual annotation, or programmatic generation.
 /// Call [`compute_crops`](SmartCropInput::compute_crops) to generate
 /// crop candidates for multiple aspect ratios from a single set of inputs.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct SmartCropInput {
     /// Weighted regions of interest (faces, objects, etc.).
     pub focus_regions: Vec<FocusRect>,
     /// Optional importance heatmap (saliency, depth, etc.).
     pub heatmap: Option<HeatMap>,
+    /// Regions the crop must keep whole or avoid (text, logos, watermarks).
+    pub regions: Vec<ContentRegion>,
 }
 
 impl SmartCropInput {
+    /// Create from focus regions and an optional heatmap, with no content
+    /// regions.
+    ///
+    /// Unlike a struct literal, this keeps compiling as fields are added.
+    pub fn new(focus_regions: Vec<FocusRect>, heatmap: Option<HeatMap>) -> Self {
+        Self {
+            focus_regions,
+            heatmap,
+            regions: Vec::new(),
+        }
+    }
+
     /// Compute optimal crops for multiple (ratio, mode) pairs.
     ///
     /// Uses default `CropConfig` parameters (focus_vertical_position=0.38,
@@ -157,26 +409,73 @@ impl SmartCropInput {
                     mode,
                     ..CropConfig::default()
                 };
-                compute_crop(
-                    src_w,
-                    src_h,
-                    &self.focus_regions,
-                    self.heatmap.as_ref(),
-                    &config,
-                )
+                self.compute_crop(src_w, src_h, &config)
             })
             .collect()
     }
 
     /// Compute a single crop with full control over parameters.
+    ///
+    /// Honours [`regions`](Self::regions) where possible; use
+    /// [`compute_crop_report`](Self::compute_crop_report) to learn which
+    /// could not be honoured.
     pub fn compute_crop(&self, src_w: u32, src_h: u32, config: &CropConfig) -> Option<Rect> {
-        compute_crop(
-            src_w,
-            src_h,
-            &self.focus_regions,
-            self.heatmap.as_ref(),
-            config,
-        )
+        self.compute_crop_report(src_w, src_h, config)
+            .map(|r| r.rect)
+    }
+
+    /// Compute a single crop and report which content regions it violates.
+    ///
+    /// The crop from `config.mode` is moved to the nearest placement that
+    /// keeps every [`KeepWhole`](RegionRole::KeepWhole) region whole and
+    /// every [`Exclude`](RegionRole::Exclude) region out, shrinking about
+    /// its centre (down to 30% of its size, and never below
+    /// [`min_crop_size`](CropConfig::min_crop_size)) if no placement works. If even
+    /// that fails, the crop keeps its size at the placement violating the
+    /// fewest regions, listed in [`CropReport::unsatisfied`].
+    ///
+    /// Returns `None` for degenerate dimensions, as [`compute_crop`] does.
+    pub fn compute_crop_report(
+        &self,
+        src_w: u32,
+        src_h: u32,
+        config: &CropConfig,
+    ) -> Option<CropReport> {
+        crop_with_regions(src_w, src_h, self.inputs(), config)
+    }
+
+    /// Ranked crop candidates for one aspect ratio, best first.
+    ///
+    /// See [`compute_candidates`]. Candidates that violate content regions
+    /// are penalised.
+    pub fn compute_candidates(
+        &self,
+        src_w: u32,
+        src_h: u32,
+        config: &CropConfig,
+        max_candidates: usize,
+    ) -> Vec<CropCandidate> {
+        candidates(src_w, src_h, self.inputs(), config, max_candidates)
+    }
+
+    /// Score an arbitrary crop rectangle. See [`score_crop`].
+    pub fn score_crop(
+        &self,
+        src_w: u32,
+        src_h: u32,
+        config: &CropConfig,
+        rect: Rect,
+    ) -> CropCandidate {
+        let sat = self.heatmap.as_ref().map(SummedArea::new);
+        score_rect(src_w, src_h, self.inputs(), sat.as_ref(), config, rect)
+    }
+
+    fn inputs(&self) -> Inputs<'_> {
+        Inputs {
+            focus_regions: &self.focus_regions,
+            heatmap: self.heatmap.as_ref(),
+            regions: &self.regions,
+        }
     }
 }
 
@@ -196,10 +495,12 @@ pub fn compute_crop(
 
     let qualifying: Vec<&FocusRect> = focus_regions.iter().filter(|f| f.weight >= 0.5).collect();
 
-    match config.mode {
+    let rect = match config.mode {
         CropMode::Minimal => minimal_crop(src_w, src_h, &qualifying, heatmap, config),
         CropMode::Maximal => maximal_crop(src_w, src_h, &qualifying, heatmap, config),
-    }
+        CropMode::Balanced => balanced_crop(src_w, src_h, &qualifying, heatmap, config),
+    }?;
+    Some(enforce_min_size(rect, src_w, src_h, config))
 }
 
 // ---------------------------------------------------------------------------
@@ -223,18 +524,10 @@ fn minimal_crop(
     let cw = crop_w as f64;
     let ch = crop_h as f64;
 
-    let (focus_x, focus_y, has_regions) = find_focus(regions, heatmap, sw, sh);
-
-    // Position crop
-    let cx = focus_x - cw / 2.0;
-
-    let cy = if has_regions {
-        let primary = primary_region(regions);
-        let pcy = region_center_y(primary, sh);
-        pcy - ch * config.focus_vertical_position as f64
-    } else {
-        focus_y - ch / 2.0
-    };
+    let has_regions = !regions.is_empty();
+    let anchor = composition_anchor(regions, heatmap, sw, sh, cw, ch, config);
+    let cx = anchor.x - cw * anchor.frac_x;
+    let cy = anchor.y - ch * anchor.frac_y;
 
     let mut x = clamp_f64(cx, 0.0, sw - cw);
     let mut y = clamp_f64(cy, 0.0, sh - ch);
@@ -251,7 +544,10 @@ fn minimal_crop(
             config.min_focus_visibility,
         );
     } else if let Some(hm) = heatmap {
-        shift_for_heatmap_coverage(&mut x, &mut y, cw, ch, sw, sh, hm);
+        let sat = SummedArea::new(hm);
+        if sat.total() > 1e-10 {
+            (x, y) = best_heatmap_window(&sat, cw, ch, sw, sh, (x, y));
+        }
     }
 
     Some(Rect {
@@ -299,39 +595,1259 @@ fn maximal_crop(
         expand_to_aspect(&mut sx1, &mut sy1, &mut sx2, &mut sy2, config.target_aspect);
     }
 
-    // Headroom adjustment (focus region mode)
-    if has_regions {
-        let primary = primary_region(regions);
-        let pcy = region_center_y(primary, sh);
-        let crop_h = sy2 - sy1;
-        let desired_top = pcy - crop_h * config.focus_vertical_position as f64;
-        let shift = desired_top - sy1;
-        sy1 += shift;
-        sy2 += shift;
+    // Composition adjustment: move the subject to its spot in the crop.
+    if config.composition != Composition::Centered {
+        let (cw, ch) = (sx2 - sx1, sy2 - sy1);
+        let anchor = composition_anchor(regions, heatmap, sw, sh, cw, ch, config);
+        let shift_x = anchor.x - cw * anchor.frac_x - sx1;
+        let shift_y = anchor.y - ch * anchor.frac_y - sy1;
+        sx1 += shift_x;
+        sx2 += shift_x;
+        sy1 += shift_y;
+        sy2 += shift_y;
+    } else if has_regions {
+        // Headroom adjustment (focus region mode)
+        let primary = primary_region(regions);
+        let pcy = region_center_y(primary, sh);
+        let crop_h = sy2 - sy1;
+        let desired_top = pcy - crop_h * config.focus_vertical_position as f64;
+        let shift = desired_top - sy1;
+        sy1 += shift;
+        sy2 += shift;
+    }
+
+    clamp_rect_to_bounds(&mut sx1, &mut sy1, &mut sx2, &mut sy2, sw, sh);
+    enforce_aspect_after_clamp(
+        &mut sx1,
+        &mut sy1,
+        &mut sx2,
+        &mut sy2,
+        sw,
+        sh,
+        config.target_aspect,
+    );
+
+    // Heatmap only: slide the window to where it retains the most mass.
+    if let (false, Some(hm)) = (has_regions, heatmap) {
+        let sat = SummedArea::new(hm);
+        if sat.total() > 1e-10 {
+            let (cw, ch) = (sx2 - sx1, sy2 - sy1);
+            (sx1, sy1) = best_heatmap_window(&sat, cw, ch, sw, sh, (sx1, sy1));
+            (sx2, sy2) = (sx1 + cw, sy1 + ch);
+        }
+    }
+
+    let crop_w = (sx2 - sx1).round() as u32;
+    let crop_h = (sy2 - sy1).round() as u32;
+    if crop_w == 0 || crop_h == 0 {
+        return None;
+    }
+
+    Some(Rect {
+        x: sx1.round() as u32,
+        y: sy1.round() as u32,
+        width: crop_w,
+        height: crop_h,
+    })
+}
+
+// ---------------------------------------------------------------------------
+// Balanced mode
+// ---------------------------------------------------------------------------
+
+/// The crop `config.zoom` of the way from the minimal crop to the maximal one.
+///
+/// Edges are interpolated, so the ratio holds, and while the minimal crop
+/// contains the maximal one so does every crop between them.
+fn balanced_crop(
+    src_w: u32,
+    src_h: u32,
+    regions: &[&FocusRect],
+    heatmap: Option<&HeatMap>,
+    config: &CropConfig,
+) -> Option<Rect> {
+    let minimal = minimal_crop(src_w, src_h, regions, heatmap, config)?;
+    let maximal = maximal_crop(src_w, src_h, regions, heatmap, config)?;
+    let t = clamp_f64(config.zoom as f64, 0.0, 1.0);
+    let lerp = |a: u32, b: u32| (a as f64 + (b as f64 - a as f64) * t).round() as u32;
+    let x = lerp(minimal.x, maximal.x);
+    let y = lerp(minimal.y, maximal.y);
+    let right = lerp(minimal.x + minimal.width, maximal.x + maximal.width);
+    let bottom = lerp(minimal.y + minimal.height, maximal.y + maximal.height);
+    if right <= x || bottom <= y {
+        return None;
+    }
+    Some(Rect::new(x, y, right - x, bottom - y))
+}
+
+/// Grow `rect` about its centre to at least `config.min_crop_size`, keeping
+/// the target ratio and staying inside the source.
+fn enforce_min_size(rect: Rect, src_w: u32, src_h: u32, config: &CropConfig) -> Rect {
+    let Some(min) = config.min_crop_size else {
+        return rect;
+    };
+    if rect.width >= min.width && rect.height >= min.height {
+        return rect;
+    }
+    let (lw, lh) = largest_rect_at_ratio(src_w, src_h, config.target_aspect);
+    let scale = f64::max(
+        min.width as f64 / rect.width as f64,
+        min.height as f64 / rect.height as f64,
+    );
+    let cw = (rect.width as f64 * scale).ceil() as u32;
+    let ch = (rect.height as f64 * scale).ceil() as u32;
+    let (cw, ch) = if cw >= lw || ch >= lh {
+        (lw, lh)
+    } else {
+        (cw, ch)
+    };
+    let cx = rect.x as f64 + rect.width as f64 / 2.0;
+    let cy = rect.y as f64 + rect.height as f64 / 2.0;
+    let x = clamp_f64((cx - cw as f64 / 2.0).round(), 0.0, (src_w - cw) as f64);
+    let y = clamp_f64((cy - ch as f64 / 2.0).round(), 0.0, (src_h - ch) as f64);
+    Rect::new(x as u32, y as u32, cw, ch)
+}
+
+// ---------------------------------------------------------------------------
+// Ranked candidates
+// ---------------------------------------------------------------------------
+
+const WEIGHT_FOCUS: f64 = 0.4;
+const WEIGHT_HEATMAP: f64 = 0.3;
+const WEIGHT_COMPOSITION: f64 = 0.3;
+/// Penalty when every focus region is cut; scales with the cut fraction.
+const PENALTY_CUT: f64 = 0.5;
+/// Penalty when every content region is violated; scales with the fraction.
+const PENALTY_VIOLATION: f64 = 1.0;
+/// Candidates overlapping a better one by more than this IoU are dropped.
+const DUPLICATE_IOU: f64 = 0.85;
+
+/// Ranked crop candidates at `config.target_aspect`, best first.
+///
+/// Candidates span every size from the [`Maximal`](CropMode::Maximal) crop
+/// to the [`Minimal`](CropMode::Minimal) crop, each tried at several
+/// positions, and are scored with [`score_crop`]. Near-duplicates of a
+/// better candidate are dropped, and at most `max_candidates` are returned.
+/// `config.mode` is ignored; both modes contribute.
+///
+/// Returns an empty list for degenerate dimensions.
+pub fn compute_candidates(
+    src_w: u32,
+    src_h: u32,
+    focus_regions: &[FocusRect],
+    heatmap: Option<&HeatMap>,
+    config: &CropConfig,
+    max_candidates: usize,
+) -> Vec<CropCandidate> {
+    let inputs = Inputs {
+        focus_regions,
+        heatmap,
+        regions: &[],
+    };
+    candidates(src_w, src_h, inputs, config, max_candidates)
+}
+
+/// [`compute_candidates`] with content regions.
+fn candidates(
+    src_w: u32,
+    src_h: u32,
+    inputs: Inputs<'_>,
+    config: &CropConfig,
+    max_candidates: usize,
+) -> Vec<CropCandidate> {
+    let crop_in_mode = |mode| {
+        let config = CropConfig {
+            mode,
+            ..config.clone()
+        };
+        crop_with_regions(src_w, src_h, inputs, &config).map(|r| r.rect)
+    };
+    let (Some(minimal), Some(maximal)) = (
+        crop_in_mode(CropMode::Minimal),
+        crop_in_mode(CropMode::Maximal),
+    ) else {
+        return Vec::new();
+    };
+
+    let mut rects = alloc::vec![minimal, maximal];
+    let (lw, lh) = largest_rect_at_ratio(src_w, src_h, config.target_aspect);
+    let min_frac = (maximal.width as f64 / lw.max(1) as f64).min(1.0);
+    let centre = |r: &Rect| {
+        (
+            r.x as f64 + r.width as f64 / 2.0,
+            r.y as f64 + r.height as f64 / 2.0,
+        )
+    };
+    let (min_c, max_c) = (centre(&minimal), centre(&maximal));
+    const STEPS: u32 = 4;
+    for i in 0..=STEPS {
+        let t = i as f64 / STEPS as f64;
+        let frac = min_frac + (1.0 - min_frac) * t;
+        let w = ((lw as f64 * frac).floor() as u32).clamp(1, src_w);
+        let h = ((lh as f64 * frac).floor() as u32).clamp(1, src_h);
+        let (slack_x, slack_y) = ((src_w - w) as f64, (src_h - h) as f64);
+        // Between the two modes' placements, then a sweep of the slack.
+        let cx = max_c.0 + (min_c.0 - max_c.0) * t;
+        let cy = max_c.1 + (min_c.1 - max_c.1) * t;
+        let at = |x: f64, y: f64| Rect {
+            x: clamp_f64(x, 0.0, slack_x).round() as u32,
+            y: clamp_f64(y, 0.0, slack_y).round() as u32,
+            width: w,
+            height: h,
+        };
+        rects.push(at(cx - w as f64 / 2.0, cy - h as f64 / 2.0));
+        for j in 0..=STEPS {
+            for k in 0..=STEPS {
+                rects.push(at(
+                    slack_x * j as f64 / STEPS as f64,
+                    slack_y * k as f64 / STEPS as f64,
+                ));
+            }
+        }
+    }
+
+    let sat = inputs.heatmap.map(SummedArea::new);
+    let mut scored: Vec<CropCandidate> = rects
+        .into_iter()
+        .map(|r| score_rect(src_w, src_h, inputs, sat.as_ref(), config, r))
+        .collect();
+    // Stable: on ties the mode crops (generated first) win.
+    scored.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(core::cmp::Ordering::Equal)
+    });
+    let mut kept: Vec<CropCandidate> = Vec::new();
+    for candidate in scored {
+        if kept.len() >= max_candidates {
+            break;
+        }
+        if kept
+            .iter()
+            .all(|k| rect_iou(&k.rect, &candidate.rect) <= DUPLICATE_IOU)
+        {
+            kept.push(candidate);
+        }
+    }
+    kept
+}
+
+/// Score an arbitrary crop rectangle, e.g. one a user dragged in the picker.
+///
+/// Uses the composition fields of `config` (`composition`,
+/// `subject_direction`, `focus_vertical_position`); others are ignored.
+/// Focus regions below weight 0.5 are ignored, as in [`compute_crop`].
+pub fn score_crop(
+    src_w: u32,
+    src_h: u32,
+    focus_regions: &[FocusRect],
+    heatmap: Option<&HeatMap>,
+    config: &CropConfig,
+    rect: Rect,
+) -> CropCandidate {
+    let sat = heatmap.map(SummedArea::new);
+    let inputs = Inputs {
+        focus_regions,
+        heatmap,
+        regions: &[],
+    };
+    score_rect(src_w, src_h, inputs, sat.as_ref(), config, rect)
+}
+
+/// [`score_crop`] with content regions and the heatmap's summed-area
+/// table already built.
+fn score_rect(
+    src_w: u32,
+    src_h: u32,
+    inputs: Inputs<'_>,
+    sat: Option<&SummedArea>,
+    config: &CropConfig,
+    rect: Rect,
+) -> CropCandidate {
+    let Inputs {
+        focus_regions,
+        heatmap,
+        regions: content_regions,
+    } = inputs;
+    let sw = src_w.max(1) as f64;
+    let sh = src_h.max(1) as f64;
+    let (x, y) = (rect.x as f64, rect.y as f64);
+    let (cw, ch) = (rect.width.max(1) as f64, rect.height.max(1) as f64);
+    let regions: Vec<&FocusRect> = focus_regions.iter().filter(|f| f.weight >= 0.5).collect();
+
+    let mut weighted = 0.0;
+    let mut total_weight = 0.0;
+    let mut subjects_cut = 0u32;
+    for region in &regions {
+        let frac = region_overlap_fraction(region, x, y, cw, ch, sw, sh);
+        weighted += frac * region.weight as f64;
+        total_weight += region.weight as f64;
+        if frac > 0.01 && frac < 0.99 {
+            subjects_cut += 1;
+        }
+    }
+    let focus_coverage = if total_weight > 0.0 {
+        weighted / total_weight
+    } else {
+        1.0
+    };
+
+    let heatmap_retained = sat.map_or(1.0, |sat| sat.fraction(x, y, cw, ch, sw, sh));
+
+    let anchor = composition_anchor(&regions, heatmap, sw, sh, cw, ch, config);
+    let dx = (anchor.x - (x + cw * anchor.frac_x)) / (cw / 2.0);
+    let dy = (anchor.y - (y + ch * anchor.frac_y)) / (ch / 2.0);
+    let composition = (1.0 - (dx * dx + dy * dy).sqrt() / core::f64::consts::SQRT_2).max(0.0);
+
+    let cut_fraction = if regions.is_empty() {
+        0.0
+    } else {
+        subjects_cut as f64 / regions.len() as f64
+    };
+    let regions_violated = content_regions
+        .iter()
+        .filter(|r| !r.honoured_by(x, y, cw, ch, sw, sh))
+        .count() as u32;
+    let violated_fraction = if content_regions.is_empty() {
+        0.0
+    } else {
+        regions_violated as f64 / content_regions.len() as f64
+    };
+    let score = WEIGHT_FOCUS * focus_coverage
+        + WEIGHT_HEATMAP * heatmap_retained
+        + WEIGHT_COMPOSITION * composition
+        - PENALTY_CUT * cut_fraction
+        - PENALTY_VIOLATION * violated_fraction;
+
+    CropCandidate {
+        rect,
+        score: score.clamp(0.0, 1.0) as f32,
+        metrics: CropMetrics {
+            focus_coverage: focus_coverage as f32,
+            heatmap_retained: heatmap_retained as f32,
+            subjects_cut,
+            composition: composition as f32,
+            regions_violated,
+        },
+    }
+}
+
+/// Intersection over union of two rectangles.
+fn rect_iou(a: &Rect, b: &Rect) -> f64 {
+    let ix = (a.x + a.width)
+        .min(b.x + b.width)
+        .saturating_sub(a.x.max(b.x)) as f64;
+    let iy = (a.y + a.height)
+        .min(b.y + b.height)
+        .saturating_sub(a.y.max(b.y)) as f64;
+    let inter = ix * iy;
+    let union = a.width as f64 * a.height as f64 + b.width as f64 * b.height as f64 - inter;
+    if union <= 0.0 { 1.0 } else { inter / union }
+}
+
+// ---------------------------------------------------------------------------
+// Content regions
+// ---------------------------------------------------------------------------
+
+/// Smallest crop, as a fraction of the proposed size, tried before giving up.
+const MIN_REGION_SHRINK: f64 = 0.3;
+
+/// Borrowed smart-crop inputs.
+#[derive(Clone, Copy)]
+struct Inputs<'a> {
+    focus_regions: &'a [FocusRect],
+    heatmap: Option<&'a HeatMap>,
+    regions: &'a [ContentRegion],
+}
+
+/// Crop per `config.mode`, then fitted around the content regions.
+fn crop_with_regions(
+    src_w: u32,
+    src_h: u32,
+    inputs: Inputs<'_>,
+    config: &CropConfig,
+) -> Option<CropReport> {
+    let rect = compute_crop(src_w, src_h, inputs.focus_regions, inputs.heatmap, config)?;
+    Some(place_around_regions(
+        rect,
+        inputs.regions,
+        src_w,
+        src_h,
+        config.min_crop_size,
+    ))
+}
+
+/// Move the crop — shrinking it about its centre if moving is not enough —
+/// to the nearest placement that honours every content region.
+///
+/// If none exists down to [`MIN_REGION_SHRINK`] or `min_size`, keeps the original size
+/// at the placement violating the fewest regions and reports them.
+fn place_around_regions(
+    rect: Rect,
+    regions: &[ContentRegion],
+    src_w: u32,
+    src_h: u32,
+    min_size: Option<Size>,
+) -> CropReport {
+    if regions.is_empty() {
+        return CropReport {
+            rect,
+            unsatisfied: Vec::new(),
+        };
+    }
+    let (sw, sh) = (src_w as f64, src_h as f64);
+    let (mid_x, mid_y) = (
+        rect.x as f64 + rect.width as f64 / 2.0,
+        rect.y as f64 + rect.height as f64 / 2.0,
+    );
+    let violations = |x: f64, y: f64, cw: f64, ch: f64| -> Vec<usize> {
+        regions
+            .iter()
+            .enumerate()
+            .filter(|(_, r)| !r.honoured_by(x, y, cw, ch, sw, sh))
+            .map(|(i, _)| i)
+            .collect()
+    };
+    // The nearest honouring placement is the proposal clamped into one of
+    // the boxes the region edges carve out, so only these coordinates can
+    // be optimal on each axis.
+    let stops = |proposal: f64, size: f64, horizontal: bool| {
+        let limit = if horizontal { sw } else { sh };
+        let slack = (limit - size).max(0.0);
+        let mut stops = alloc::vec![clamp_f64(proposal, 0.0, slack), 0.0, slack];
+        for r in regions {
+            let (x1, y1, x2, y2) = r.pixels(sw, sh);
+            let (lo, hi) = if horizontal { (x1, x2) } else { (y1, y2) };
+            stops.extend([lo - size, lo, hi - size, hi].map(|v| clamp_f64(v, 0.0, slack)));
+        }
+        stops
+    };
+
+    let mut best_fallback: Option<(usize, f64, Rect, Vec<usize>)> = None;
+    let mut scale = 1.0;
+    while scale >= MIN_REGION_SHRINK - 1e-9 {
+        let cw = (rect.width as f64 * scale).floor().max(1.0);
+        let ch = (rect.height as f64 * scale).floor().max(1.0);
+        if scale < 1.0 && min_size.is_some_and(|m| cw < m.width as f64 || ch < m.height as f64) {
+            break;
+        }
+        let (px, py) = (mid_x - cw / 2.0, mid_y - ch / 2.0);
+        let xs = stops(px, cw, true);
+        let ys = stops(py, ch, false);
+        let mut best: Option<(f64, f64, f64)> = None;
+        for &x in &xs {
+            for &y in &ys {
+                let (x, y) = (x.round(), y.round());
+                let dist = (x - px).powi(2) + (y - py).powi(2);
+                let broken = violations(x, y, cw, ch);
+                if broken.is_empty() {
+                    if best.is_none_or(|b| dist < b.2) {
+                        best = Some((x, y, dist));
+                    }
+                } else if scale == 1.0
+                    && best_fallback
+                        .as_ref()
+                        .is_none_or(|f| (broken.len(), dist) < (f.0, f.1))
+                {
+                    let at = Rect::new(x as u32, y as u32, cw as u32, ch as u32);
+                    best_fallback = Some((broken.len(), dist, at, broken));
+                }
+            }
+        }
+        if let Some((x, y, _)) = best {
+            return CropReport {
+                rect: Rect::new(x as u32, y as u32, cw as u32, ch as u32),
+                unsatisfied: Vec::new(),
+            };
+        }
+        scale -= 0.1;
+    }
+    let (_, _, rect, unsatisfied) = best_fallback.unwrap_or_else(|| {
+        let broken = violations(
+            rect.x as f64,
+            rect.y as f64,
+            rect.width as f64,
+            rect.height as f64,
+        );
+        (broken.len(), 0.0, rect, broken)
+    });
+    CropReport { rect, unsatisfied }
+}
+
+// ---------------------------------------------------------------------------
+// Heatmap window search
+// ---------------------------------------------------------------------------
+
+/// Window positions evaluated per search before switching to coarse-to-fine.
+const MAX_WINDOW_POSITIONS: usize = 1 << 16;
+/// Positions retaining within this fraction of the total mass of the best
+/// one count as ties, broken by distance to the preferred position.
+const WINDOW_TIE_FRACTION: f64 = 0.005;
+
+/// Summed-area table over a [`HeatMap`]: the mass of any window in O(1).
+///
+/// The heatmap is treated as piecewise constant per cell, so windows with
+/// fractional edges get exact partial-cell mass.
+struct SummedArea {
+    width: usize,
+    height: usize,
+    /// `(width + 1) × (height + 1)` prefix sums; row 0 and column 0 are zero.
+    sums: Vec<f64>,
+}
+
+impl SummedArea {
+    fn new(hm: &HeatMap) -> Self {
+        let (width, height) = (hm.width as usize, hm.height as usize);
+        let stride = width + 1;
+        let mut sums = alloc::vec![0.0; stride * (height + 1)];
+        for row in 0..height {
+            let mut row_sum = 0.0;
+            for col in 0..width {
+                row_sum += hm
+                    .data
+                    .get(row * width + col)
+                    .copied()
+                    .unwrap_or(0.0)
+                    .max(0.0) as f64;
+                sums[(row + 1) * stride + col + 1] = sums[row * stride + col + 1] + row_sum;
+            }
+        }
+        Self {
+            width,
+            height,
+            sums,
+        }
+    }
+
+    fn total(&self) -> f64 {
+        self.sums[self.sums.len() - 1]
+    }
+
+    /// Mass above and left of `(u, v)`, in cell units.
+    fn integral(&self, u: f64, v: f64) -> f64 {
+        let u = u.clamp(0.0, self.width as f64);
+        let v = v.clamp(0.0, self.height as f64);
+        let (i, j) = (
+            (u.floor() as usize).min(self.width.saturating_sub(1)),
+            (v.floor() as usize).min(self.height.saturating_sub(1)),
+        );
+        let (fu, fv) = (u - i as f64, v - j as f64);
+        let stride = self.width + 1;
+        let at = |c: usize, r: usize| self.sums[r * stride + c];
+        // Bilinear in the prefix sums is exact for a piecewise-constant map.
+        let top = at(i, j) + (at(i + 1, j) - at(i, j)) * fu;
+        let bottom = at(i, j + 1) + (at(i + 1, j + 1) - at(i, j + 1)) * fu;
+        top + (bottom - top) * fv
+    }
+
+    /// Mass of the cell-unit window `[x0, x1) × [y0, y1)`.
+    fn mass(&self, x0: f64, y0: f64, x1: f64, y1: f64) -> f64 {
+        self.integral(x1, y1) - self.integral(x0, y1) - self.integral(x1, y0)
+            + self.integral(x0, y0)
+    }
+
+    /// Fraction of the total mass inside a crop given in source pixels.
+    fn fraction(&self, x: f64, y: f64, cw: f64, ch: f64, sw: f64, sh: f64) -> f64 {
+        let total = self.total();
+        if total < 1e-10 {
+            return 1.0;
+        }
+        let (kx, ky) = (self.width as f64 / sw, self.height as f64 / sh);
+        self.mass(x * kx, y * ky, (x + cw) * kx, (y + ch) * ky) / total
+    }
+}
+
+/// Top-left of the `cw × ch` window (source pixels) retaining the most
+/// heatmap mass, searched at heatmap-cell resolution.
+///
+/// Near-ties go to the position nearest `preferred`, so flat maps keep the
+/// composition placement. Large maps are searched coarse-to-fine.
+fn best_heatmap_window(
+    sat: &SummedArea,
+    cw: f64,
+    ch: f64,
+    sw: f64,
+    sh: f64,
+    preferred: (f64, f64),
+) -> (f64, f64) {
+    let (kx, ky) = (sat.width as f64 / sw, sat.height as f64 / sh);
+    let (wg, hg) = (cw * kx, ch * ky);
+    let (slack_x, slack_y) = (
+        (sat.width as f64 - wg).max(0.0),
+        (sat.height as f64 - hg).max(0.0),
+    );
+    let pref = (
+        clamp_f64(preferred.0 * kx, 0.0, slack_x),
+        clamp_f64(preferred.1 * ky, 0.0, slack_y),
+    );
+
+    let (nx, ny) = (slack_x as usize + 1, slack_y as usize + 1);
+    let stride = ((nx * ny) as f64 / MAX_WINDOW_POSITIONS as f64)
+        .sqrt()
+        .ceil()
+        .max(1.0) as usize;
+    let mut evaluated: Vec<(f64, f64, f64)> = Vec::new();
+    let sweep = |out: &mut Vec<(f64, f64, f64)>,
+                 x0: usize,
+                 x1: usize,
+                 y0: usize,
+                 y1: usize,
+                 step: usize| {
+        let xs = (x0..=x1).step_by(step).map(|x| x as f64).chain([slack_x]);
+        for gx in xs.filter(|&x| x <= slack_x) {
+            let ys = (y0..=y1).step_by(step).map(|y| y as f64).chain([slack_y]);
+            for gy in ys.filter(|&y| y <= slack_y) {
+                out.push((gx, gy, sat.mass(gx, gy, gx + wg, gy + hg)));
+            }
+        }
+    };
+    sweep(&mut evaluated, 0, nx - 1, 0, ny - 1, stride);
+    if stride > 1 {
+        let &(bx, by, _) = evaluated
+            .iter()
+            .max_by(|a, b| a.2.partial_cmp(&b.2).unwrap_or(core::cmp::Ordering::Equal))
+            .unwrap();
+        let (bx, by) = (bx as usize, by as usize);
+        sweep(
+            &mut evaluated,
+            bx.saturating_sub(stride),
+            (bx + stride).min(nx - 1),
+            by.saturating_sub(stride),
+            (by + stride).min(ny - 1),
+            1,
+        );
+    }
+    evaluated.push((
+        pref.0,
+        pref.1,
+        sat.mass(pref.0, pref.1, pref.0 + wg, pref.1 + hg),
+    ));
+
+    let best = evaluated.iter().map(|e| e.2).fold(f64::MIN, f64::max);
+    let floor = best - sat.total() * WINDOW_TIE_FRACTION;
+    let dist = |e: &(f64, f64, f64)| (e.0 - pref.0).powi(2) + (e.1 - pref.1).powi(2);
+    let &(gx, gy, _) = evaluated
+        .iter()
+        .filter(|e| e.2 >= floor)
+        .min_by(|a, b| {
+            dist(a)
+                .partial_cmp(&dist(b))
+                .unwrap_or(core::cmp::Ordering::Equal)
+        })
+        .unwrap();
+    (gx / kx, gy / ky)
+}
+
+// ---------------------------------------------------------------------------
+// Temporal smoothing
+// ---------------------------------------------------------------------------
+
+/// Smooth crop path for a sequence of frames, one rectangle per frame.
+///
+/// Each frame's crop is computed as [`SmartCropInput::compute_crop`] would,
+/// then the crop centre and size follow it within the velocity and
+/// acceleration limits of `config`, easing in and out instead of jumping.
+/// Frames marked [`scene_cut`](CropFrame::scene_cut) restart the path at
+/// their own crop. Frames are taken in order; a timestamp that does not
+/// advance holds the crop.
+///
+/// Returns an empty vec for degenerate dimensions.
+///
+/// ```
+/// use zenlayout::smart_crop::*;
+///
+/// let at = |time: f64, x: f32| CropFrame {
+///     time,
+///     input: SmartCropInput {
+///         focus_regions: vec![FocusRect { x1: x, y1: 40.0, x2: x + 5.0, y2: 60.0, weight: 1.0 }],
+///         ..Default::default()
+///     },
+///     scene_cut: false,
+/// };
+/// // The subject jumps across the frame; the crop pans after it.
+/// let frames = [at(0.0, 10.0), at(0.1, 80.0), at(0.2, 80.0)];
+/// let path = compute_trajectory(1920, 1080, &frames, &TrajectoryConfig::default());
+/// assert_eq!(path.len(), 3);
+/// assert!(path[0].x < path[1].x && path[1].x < path[2].x);
+/// ```
+pub fn compute_trajectory(
+    src_w: u32,
+    src_h: u32,
+    frames: &[CropFrame],
+    config: &TrajectoryConfig,
+) -> Vec<Rect> {
+    let mut tracker = CropTracker::new(src_w, src_h, config.clone());
+    let mut path = Vec::with_capacity(frames.len());
+    for frame in frames {
+        if frame.scene_cut {
+            tracker.reset();
+        }
+        match tracker.update(frame.time, &frame.input) {
+            Some(rect) => path.push(rect),
+            None => return Vec::new(),
+        }
+    }
+    path
+}
+
+/// Streaming form of [`compute_trajectory`], one frame at a time.
+///
+/// Suited to live sources, where frames arrive as they are decoded.
+#[derive(Debug, Clone)]
+pub struct CropTracker {
+    src_w: u32,
+    src_h: u32,
+    config: TrajectoryConfig,
+    state: Option<TrackState>,
+}
+
+/// Where the tracked crop is, as source fractions.
+#[derive(Debug, Clone, Copy)]
+struct TrackState {
+    time: f64,
+    center_x: Follower,
+    center_y: Follower,
+    size: Follower,
+}
+
+impl CropTracker {
+    /// Track crops of a `src_w × src_h` source.
+    pub fn new(src_w: u32, src_h: u32, config: TrajectoryConfig) -> Self {
+        Self {
+            src_w,
+            src_h,
+            config,
+            state: None,
+        }
+    }
+
+    /// Forget the path, so the next frame's crop is used as is.
+    ///
+    /// Call at scene cuts.
+    pub fn reset(&mut self) {
+        self.state = None;
+    }
+
+    /// Crop for a frame at `time` seconds.
+    ///
+    /// Returns `None` for degenerate dimensions, as [`compute_crop`] does.
+    pub fn update(&mut self, time: f64, input: &SmartCropInput) -> Option<Rect> {
+        let (sw, sh) = (self.src_w as f64, self.src_h as f64);
+        let target = input.compute_crop(self.src_w, self.src_h, &self.config.crop)?;
+        let (max_w, max_h) =
+            largest_rect_at_ratio(self.src_w, self.src_h, self.config.crop.target_aspect);
+        let goal_x = (target.x as f64 + target.width as f64 / 2.0) / sw;
+        let goal_y = (target.y as f64 + target.height as f64 / 2.0) / sh;
+        let goal_size = target.width as f64 / max_w.max(1) as f64;
+
+        let state = match &mut self.state {
+            Some(state) => {
+                let dt = time - state.time;
+                if dt > 0.0 {
+                    let limits = Limits::from(&self.config);
+                    state.size.step(goal_size, dt, &limits);
+                    state.size.pos = clamp_f64(state.size.pos, 0.0, 1.0);
+                    state.center_x.step(goal_x, dt, &limits);
+                    state.center_y.step(goal_y, dt, &limits);
+                    state.time = time;
+                }
+                *state
+            }
+            None => *self.state.insert(TrackState {
+                time,
+                center_x: Follower::at(goal_x),
+                center_y: Follower::at(goal_y),
+                size: Follower::at(goal_size),
+            }),
+        };
+
+        let cw = ((max_w as f64 * state.size.pos).round() as u32).clamp(1, self.src_w);
+        let ch = ((max_h as f64 * state.size.pos).round() as u32).clamp(1, self.src_h);
+        let (max_x, max_y) = ((self.src_w - cw) as f64, (self.src_h - ch) as f64);
+        let x = state.center_x.pos * sw - cw as f64 / 2.0;
+        let y = state.center_y.pos * sh - ch as f64 / 2.0;
+
+        // A follower pushed past the source edge is pinned to it, so it does
+        // not stall on the way back.
+        if let Some(s) = &mut self.state {
+            if !(0.0..=max_x).contains(&x) {
+                s.center_x = Follower::at((clamp_f64(x, 0.0, max_x) + cw as f64 / 2.0) / sw);
+            }
+            if !(0.0..=max_y).contains(&y) {
+                s.center_y = Follower::at((clamp_f64(y, 0.0, max_y) + ch as f64 / 2.0) / sh);
+            }
+        }
+        let x = clamp_f64(x.round(), 0.0, max_x) as u32;
+        let y = clamp_f64(y.round(), 0.0, max_y) as u32;
+        Some(Rect::new(x, y, cw, ch))
+    }
+}
+
+/// Trajectory limits in source fractions, with disabled ones infinite.
+struct Limits {
+    velocity: f64,
+    acceleration: f64,
+    dead_zone: f64,
+}
+
+impl From<&TrajectoryConfig> for Limits {
+    fn from(config: &TrajectoryConfig) -> Self {
+        let limit = |v: f32| if v > 0.0 { v as f64 } else { f64::INFINITY };
+        Self {
+            velocity: limit(config.max_velocity),
+            acceleration: limit(config.max_acceleration),
+            dead_zone: config.dead_zone.max(0.0) as f64,
+        }
+    }
+}
+
+/// One coordinate chasing a target under velocity and acceleration limits.
+#[derive(Debug, Clone, Copy)]
+struct Follower {
+    pos: f64,
+    vel: f64,
+}
+
+impl Follower {
+    fn at(pos: f64) -> Self {
+        Self { pos, vel: 0.0 }
+    }
+
+    /// Advance `dt` seconds toward `target`, braking in time to stop on it.
+    fn step(&mut self, target: f64, dt: f64, limits: &Limits) {
+        let dist = target - self.pos;
+        let wanted = if dist.abs() <= limits.dead_zone {
+            0.0
+        } else {
+            let braking = (2.0 * limits.acceleration * dist.abs()).sqrt();
+            dist.signum() * (dist.abs() / dt).min(limits.velocity).min(braking)
+        };
+        let max_dv = limits.acceleration * dt;
+        self.vel += (wanted - self.vel).clamp(-max_dv, max_dv);
+        let step = self.vel * dt;
+        if dist.abs() > limits.dead_zone && step * dist > 0.0 && step.abs() >= dist.abs() {
+            // Arrived.
+            self.pos = target;
+            self.vel = 0.0;
+        } else {
+            self.pos += step;
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Heatmap construction
+// ---------------------------------------------------------------------------
+
+impl HeatMap {
+    /// An all-zero `width × height` grid.
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            data: alloc::vec![0.0; width as usize * height as usize],
+            width,
+            height,
+        }
+    }
+
+    /// A `width × height` grid with a Gaussian blob at each weighted point.
+    ///
+    /// Points are `(x, y, weight)` in percentage coordinates; see
+    /// [`add_point`](Self::add_point) for `sigma`.
+    pub fn from_points(width: u32, height: u32, points: &[(f32, f32, f32)], sigma: f32) -> Self {
+        let mut hm = Self::new(width, height);
+        for &(x, y, weight) in points {
+            hm.add_point(x, y, weight, sigma);
+        }
+        hm
+    }
+
+    /// A `width × height` grid covering each focus region, weighted by its
+    /// [`weight`](FocusRect::weight), with Gaussian falloff around it.
+    ///
+    /// Lets point and box detections use the heatmap path.
+    ///
+    /// ```
+    /// use zenlayout::smart_crop::{FocusRect, HeatMap};
+    ///
+    /// let face = FocusRect { x1: 60.0, y1: 20.0, x2: 80.0, y2: 50.0, weight: 0.9 };
+    /// let hm = HeatMap::from_focus_rects(32, 18, &[face], 5.0);
+    /// assert_eq!(hm.value_at(70.0, 35.0), 0.9);
+    /// assert!(hm.value_at(10.0, 90.0) < 0.01);
+    /// ```
+    pub fn from_focus_rects(width: u32, height: u32, rects: &[FocusRect], sigma: f32) -> Self {
+        let mut hm = Self::new(width, height);
+        for r in rects {
+            hm.add_rect(r, sigma);
+        }
+        hm
+    }
+
+    /// Raise cells toward `weight` around the point `(x, y)`, in percentage
+    /// coordinates.
+    ///
+    /// `sigma` is the Gaussian spread in percent of the grid's longer side;
+    /// `0.0` marks only the cell under the point. Cells keep the larger of
+    /// their value and the blob's, so overlapping blobs do not exceed 1.0.
+    pub fn add_point(&mut self, x: f32, y: f32, weight: f32, sigma: f32) {
+        self.add_rect(
+            &FocusRect {
+                x1: x,
+                y1: y,
+                x2: x,
+                y2: y,
+                weight,
+            },
+            sigma,
+        );
+    }
+
+    /// Raise cells inside `rect` to its weight, falling off outside it as
+    /// [`add_point`](Self::add_point) does around a point.
+    pub fn add_rect(&mut self, rect: &FocusRect, sigma: f32) {
+        let (gw, gh) = (self.width as f64, self.height as f64);
+        if self.data.len() < (self.width * self.height) as usize {
+            return;
+        }
+        let weight = clamp_f64(rect.weight as f64, 0.0, 1.0);
+        // Everything in cell units, where the grid is isotropic.
+        let x1 = rect.x1.min(rect.x2) as f64 / 100.0 * gw;
+        let x2 = rect.x1.max(rect.x2) as f64 / 100.0 * gw;
+        let y1 = rect.y1.min(rect.y2) as f64 / 100.0 * gh;
+        let y2 = rect.y1.max(rect.y2) as f64 / 100.0 * gh;
+        let sigma = sigma.max(0.0) as f64 / 100.0 * gw.max(gh);
+        for row in 0..self.height {
+            for col in 0..self.width {
+                let (cx, cy) = (col as f64 + 0.5, row as f64 + 0.5);
+                let value = if sigma > 0.0 {
+                    let dx = (x1 - cx).max(cx - x2).max(0.0);
+                    let dy = (y1 - cy).max(cy - y2).max(0.0);
+                    weight * (-(dx * dx + dy * dy) / (2.0 * sigma * sigma)).exp()
+                } else {
+                    // The cells the rectangle touches, at least the one under it.
+                    let hit_x = cx + 0.5 > x1 && (cx - 0.5 < x2 || cx - 0.5 <= x1);
+                    let hit_y = cy + 0.5 > y1 && (cy - 0.5 < y2 || cy - 0.5 <= y1);
+                    if hit_x && hit_y { weight } else { 0.0 }
+                };
+                let cell = &mut self.data[(row * self.width + col) as usize];
+                *cell = cell.max(value as f32);
+            }
+        }
+    }
+
+    /// Value at `(x, y)` in percentage coordinates, from the cell under it.
+    ///
+    /// Returns 0.0 outside the grid.
+    pub fn value_at(&self, x: f32, y: f32) -> f32 {
+        let col = (x as f64 / 100.0 * self.width as f64).floor();
+        let row = (y as f64 / 100.0 * self.height as f64).floor();
+        if col < 0.0 || row < 0.0 || col >= self.width as f64 || row >= self.height as f64 {
+            return 0.0;
+        }
+        let i = row as usize * self.width as usize + col as usize;
+        self.data.get(i).copied().unwrap_or(0.0)
+    }
+
+    /// This heatmap resampled to a `width × height` grid, bilinearly.
+    pub fn resampled(&self, width: u32, height: u32) -> Self {
+        if (width, height) == (self.width, self.height) {
+            return self.clone();
+        }
+        let mut out = Self::new(width, height);
+        if self.width == 0 || self.height == 0 {
+            return out;
+        }
+        let cell = |col: i64, row: i64| -> f64 {
+            let col = col.clamp(0, self.width as i64 - 1) as usize;
+            let row = row.clamp(0, self.height as i64 - 1) as usize;
+            self.data
+                .get(row * self.width as usize + col)
+                .copied()
+                .unwrap_or(0.0) as f64
+        };
+        let (kx, ky) = (
+            self.width as f64 / width as f64,
+            self.height as f64 / height as f64,
+        );
+        for row in 0..height {
+            // Centre of the output cell in source cell units, relative to
+            // source cell centres.
+            let sy = (row as f64 + 0.5) * ky - 0.5;
+            let (y0, fy) = (sy.floor(), sy - sy.floor());
+            for col in 0..width {
+                let sx = (col as f64 + 0.5) * kx - 0.5;
+                let (x0, fx) = (sx.floor(), sx - sx.floor());
+                let (x0, y0) = (x0 as i64, y0 as i64);
+                let top = cell(x0, y0) * (1.0 - fx) + cell(x0 + 1, y0) * fx;
+                let bottom = cell(x0, y0 + 1) * (1.0 - fx) + cell(x0 + 1, y0 + 1) * fx;
+                out.data[(row * width + col) as usize] = (top * (1.0 - fy) + bottom * fy) as f32;
+            }
+        }
+        out
+    }
+
+    /// This heatmap as seen after applying `orientation` to the image.
+    ///
+    /// Cells move exactly; a rotation by 90° or 270° swaps the grid size.
+    pub fn oriented(&self, orientation: Orientation) -> Self {
+        let size = orientation.transform_dimensions(self.width, self.height);
+        let mut out = Self::new(size.width, size.height);
+        for row in 0..self.height {
+            for col in 0..self.width {
+                let (x, y) = orientation.transform_point_to_display(
+                    col as f32 + 0.5,
+                    row as f32 + 0.5,
+                    self.width,
+                    self.height,
+                );
+                let i = (row * self.width + col) as usize;
+                let o = (y as u32 * size.width + x as u32) as usize;
+                out.data[o] = self.data.get(i).copied().unwrap_or(0.0);
+            }
+        }
+        out
+    }
+
+    /// Merge weighted layers on the first layer's grid.
+    ///
+    /// Other layers are [`resampled`](Self::resampled) to it. Returns `None`
+    /// when `layers` is empty.
+    ///
+    /// ```
+    /// use zenlayout::smart_crop::{HeatMap, HeatMapBlend};
+    ///
+    /// let faces = HeatMap::from_points(16, 9, &[(25.0, 40.0, 1.0)], 5.0);
+    /// let saliency = HeatMap::from_points(32, 18, &[(75.0, 40.0, 1.0)], 10.0);
+    /// let both = HeatMap::combine(&[(&faces, 1.0), (&saliency, 0.5)], HeatMapBlend::Max).unwrap();
+    /// assert_eq!((both.width, both.height), (16, 9));
+    /// assert!(both.value_at(25.0, 40.0) > both.value_at(75.0, 40.0));
+    /// ```
+    pub fn combine(layers: &[(&HeatMap, f32)], blend: HeatMapBlend) -> Option<Self> {
+        let (first, _) = layers.first()?;
+        let (width, height) = (first.width, first.height);
+        let mut out = Self::new(width, height);
+        let mut total_weight = 0.0_f32;
+        for &(layer, weight) in layers {
+            let layer = layer.resampled(width, height);
+            let weight = weight.max(0.0);
+            total_weight += weight;
+            for (cell, &v) in out.data.iter_mut().zip(&layer.data) {
+                let v = v * weight;
+                *cell = match blend {
+                    HeatMapBlend::Max => cell.max(v),
+                    HeatMapBlend::Sum | HeatMapBlend::Weighted => *cell + v,
+                };
+            }
+        }
+        for cell in &mut out.data {
+            *cell = match blend {
+                HeatMapBlend::Max => *cell,
+                HeatMapBlend::Sum => cell.min(1.0),
+                HeatMapBlend::Weighted if total_weight > 0.0 => *cell / total_weight,
+                HeatMapBlend::Weighted => 0.0,
+            };
+        }
+        Some(out)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Coordinate mapping
+// ---------------------------------------------------------------------------
+
+impl SmartCropInput {
+    /// The same inputs seen after applying `orientation` to a
+    /// `src_w × src_h` source.
+    ///
+    /// Focus and content regions stay axis-aligned; the heatmap grid is
+    /// rotated and flipped with them.
+    pub fn oriented(&self, orientation: Orientation, src_w: u32, src_h: u32) -> Self {
+        let display = orientation.transform_dimensions(src_w, src_h);
+        let back = orientation.inverse();
+        remap(
+            self,
+            (src_w, src_h),
+            (display.width, display.height),
+            |x, y| Some(orientation.transform_point_to_display(x, y, src_w, src_h)),
+            |x, y| Some(back.transform_point_to_display(x, y, display.width, display.height)),
+        )
+        .unwrap_or_default()
+    }
+
+    /// The same inputs relative to `crop`, a rectangle of a
+    /// `src_w × src_h` image.
+    ///
+    /// Regions are clipped to the crop; regions entirely outside it are
+    /// dropped.
+    pub fn cropped(&self, crop: Rect, src_w: u32, src_h: u32) -> Self {
+        self.reframed(
+            crop.x as i32,
+            crop.y as i32,
+            (src_w, src_h),
+            (crop.width, crop.height),
+        )
+    }
+
+    /// The same inputs after `effect` turns a `w × h` image into its output.
+    ///
+    /// Returns `None` if the effect cannot map points in both directions,
+    /// such as a [`ContentMapped`](crate::EffectKind::ContentMapped) effect
+    /// or an analysis barrier.
+    pub fn through_effect(&self, effect: &dyn DimensionEffect, w: u32, h: u32) -> Option<Self> {
+        let (out_w, out_h) = effect.forward(w, h)?;
+        remap(
+            self,
+            (w, h),
+            (out_w, out_h),
+            |x, y| effect.forward_point(x, y, w, h),
+            |x, y| effect.inverse_point(x, y, w, h),
+        )
+    }
+
+    /// Move the frame origin to `(left, top)` and resize it to `to`.
+    ///
+    /// Unlike [`cropped`](Self::cropped) the new frame may extend past the
+    /// old one, as a padded [`Region`](crate::Region) viewport does.
+    pub(crate) fn reframed(&self, left: i32, top: i32, from: (u32, u32), to: (u32, u32)) -> Self {
+        let (dx, dy) = (left as f32, top as f32);
+        remap(
+            self,
+            from,
+            to,
+            |x, y| Some((x - dx, y - dy)),
+            |x, y| Some((x + dx, y + dy)),
+        )
+        .unwrap_or_default()
+    }
+
+    /// Crop gravity that places a `window`-sized crop of a `w × h` image
+    /// where a smart crop at the window's aspect ratio would go.
+    ///
+    /// Only the placement of the smart crop is used; the window size is
+    /// fixed by the caller. An axis without slack gets 0.5.
+    pub(crate) fn crop_gravity(
+        &self,
+        w: u32,
+        h: u32,
+        window: Size,
+        config: &CropConfig,
+    ) -> Option<Gravity> {
+        let config = CropConfig {
+            target_aspect: AspectRatio {
+                w: window.width,
+                h: window.height,
+            },
+            ..config.clone()
+        };
+        let rect = self.compute_crop(w, h, &config)?;
+        let axis = |start: u32, len: u32, src: u32, win: u32| -> f32 {
+            if win >= src {
+                return 0.5;
+            }
+            let center = start as f64 + len as f64 / 2.0;
+            clamp_f64((center - win as f64 / 2.0) / (src - win) as f64, 0.0, 1.0) as f32
+        };
+        Some(Gravity::Percentage(
+            axis(rect.x, rect.width, w, window.width),
+            axis(rect.y, rect.height, h, window.height),
+        ))
     }
+}
 
-    clamp_rect_to_bounds(&mut sx1, &mut sy1, &mut sx2, &mut sy2, sw, sh);
-    enforce_aspect_after_clamp(
-        &mut sx1,
-        &mut sy1,
-        &mut sx2,
-        &mut sy2,
-        sw,
-        sh,
-        config.target_aspect,
-    );
-
-    let crop_w = (sx2 - sx1).round() as u32;
-    let crop_h = (sy2 - sy1).round() as u32;
-    if crop_w == 0 || crop_h == 0 {
+/// Carry inputs from a `from` frame to a `to` frame.
+///
+/// Region corners go through `forward` and become their bounding box;
+/// heatmap cells are resampled by pulling each new cell centre back
+/// through `inverse`. Returns `None` if either mapping fails.
+fn remap(
+    input: &SmartCropInput,
+    (w, h): (u32, u32),
+    (to_w, to_h): (u32, u32),
+    forward: impl Fn(f32, f32) -> Option<(f32, f32)>,
+    inverse: impl Fn(f32, f32) -> Option<(f32, f32)>,
+) -> Option<SmartCropInput> {
+    if w == 0 || h == 0 || to_w == 0 || to_h == 0 {
         return None;
     }
+    let map_bounds = |x1: f32, y1: f32, x2: f32, y2: f32| -> Option<[f32; 4]> {
+        let (sx, sy) = (w as f32 / 100.0, h as f32 / 100.0);
+        let mut b = [f32::MAX, f32::MAX, f32::MIN, f32::MIN];
+        for (x, y) in [(x1, y1), (x2, y1), (x1, y2), (x2, y2)] {
+            let (px, py) = forward(x * sx, y * sy)?;
+            b = [b[0].min(px), b[1].min(py), b[2].max(px), b[3].max(py)];
+        }
+        let (kx, ky) = (100.0 / to_w as f32, 100.0 / to_h as f32);
+        Some([
+            (b[0] * kx).clamp(0.0, 100.0),
+            (b[1] * ky).clamp(0.0, 100.0),
+            (b[2] * kx).clamp(0.0, 100.0),
+            (b[3] * ky).clamp(0.0, 100.0),
+        ])
+    };
+    let visible = |b: &[f32; 4]| b[2] > b[0] && b[3] > b[1];
+
+    let mut focus_regions = Vec::with_capacity(input.focus_regions.len());
+    for f in &input.focus_regions {
+        let b = map_bounds(f.x1, f.y1, f.x2, f.y2)?;
+        if visible(&b) {
+            focus_regions.push(FocusRect {
+                x1: b[0],
+                y1: b[1],
+                x2: b[2],
+                y2: b[3],
+                weight: f.weight,
+            });
+        }
+    }
+    let mut regions = Vec::with_capacity(input.regions.len());
+    for r in &input.regions {
+        let b = map_bounds(r.x1, r.y1, r.x2, r.y2)?;
+        if visible(&b) {
+            regions.push(ContentRegion {
+                x1: b[0],
+                y1: b[1],
+                x2: b[2],
+                y2: b[3],
+                role: r.role,
+            });
+        }
+    }
 
-    Some(Rect {
-        x: sx1.round() as u32,
-        y: sy1.round() as u32,
-        width: crop_w,
-        height: crop_h,
+    let heatmap = match &input.heatmap {
+        Some(hm) if hm.width > 0 && hm.height > 0 => {
+            // Keep roughly the source cell size in the new frame.
+            let gw = ((hm.width as f64 * to_w as f64 / w as f64).round() as u32).max(1);
+            let gh = ((hm.height as f64 * to_h as f64 / h as f64).round() as u32).max(1);
+            let mut data = Vec::with_capacity((gw * gh) as usize);
+            for gy in 0..gh {
+                for gx in 0..gw {
+                    let px = (gx as f32 + 0.5) * to_w as f32 / gw as f32;
+                    let py = (gy as f32 + 0.5) * to_h as f32 / gh as f32;
+                    let (sx, sy) = inverse(px, py)?;
+                    let cx = (sx / w as f32 * hm.width as f32).floor();
+                    let cy = (sy / h as f32 * hm.height as f32).floor();
+                    let inside =
+                        cx >= 0.0 && cy >= 0.0 && cx < hm.width as f32 && cy < hm.height as f32;
+                    data.push(if inside {
+                        let i = cy as usize * hm.width as usize + cx as usize;
+                        hm.data.get(i).copied().unwrap_or(0.0)
+                    } else {
+                        0.0
+                    });
+                }
+            }
+            Some(HeatMap {
+                data,
+                width: gw,
+                height: gh,
+            })
+        }
+        _ => None,
+    };
+
+    Some(SmartCropInput {
+        focus_regions,
+        heatmap,
+        regions,
     })
 }
 
@@ -355,24 +1871,103 @@ fn largest_rect_at_ratio(src_w: u32, src_h: u32, ratio: AspectRatio) -> (u32, u3
     }
 }
 
-/// Find the focus point for positioning the crop.
-fn find_focus(
+/// A subject point in source pixels and where it should land in the crop,
+/// as fractions of the crop size.
+struct Anchor {
+    x: f64,
+    y: f64,
+    frac_x: f64,
+    frac_y: f64,
+}
+
+/// Subject point and target placement for a `cw × ch` crop.
+fn composition_anchor(
     regions: &[&FocusRect],
     heatmap: Option<&HeatMap>,
     sw: f64,
     sh: f64,
-) -> (f64, f64, bool) {
-    if !regions.is_empty() {
-        let (all_x1, _, all_x2, _) = enclosing_bbox_pixels(regions, sw, sh);
-        let focus_x = (all_x1 + all_x2) / 2.0;
-        let primary = primary_region(regions);
-        let focus_y = region_center_y(primary, sh);
-        (focus_x, focus_y, true)
+    cw: f64,
+    ch: f64,
+    config: &CropConfig,
+) -> Anchor {
+    let Some(line) = config.composition.line() else {
+        return if !regions.is_empty() {
+            let (x1, _, x2, _) = enclosing_bbox_pixels(regions, sw, sh);
+            Anchor {
+                x: (x1 + x2) / 2.0,
+                y: region_center_y(primary_region(regions), sh),
+                frac_x: 0.5,
+                frac_y: config.focus_vertical_position as f64,
+            }
+        } else if let Some(hm) = heatmap {
+            let (cx, cy) = heatmap_center_of_mass(hm);
+            Anchor {
+                x: cx * sw,
+                y: cy * sh,
+                frac_x: 0.5,
+                frac_y: 0.5,
+            }
+        } else {
+            Anchor {
+                x: sw / 2.0,
+                y: sh / 2.0,
+                frac_x: 0.5,
+                frac_y: 0.5,
+            }
+        };
+    };
+
+    // Subject centroid and the extent of the subject group.
+    let ((px, py), (gx1, gy1, gx2, gy2)) = if !regions.is_empty() {
+        let (mut sx, mut sy, mut sm) = (0.0, 0.0, 0.0);
+        for f in regions {
+            let (x1, y1) = (f.x1 as f64 / 100.0 * sw, f.y1 as f64 / 100.0 * sh);
+            let (x2, y2) = (f.x2 as f64 / 100.0 * sw, f.y2 as f64 / 100.0 * sh);
+            let mass = f.weight as f64 * ((x2 - x1) * (y2 - y1)).max(1.0);
+            sx += (x1 + x2) / 2.0 * mass;
+            sy += (y1 + y2) / 2.0 * mass;
+            sm += mass;
+        }
+        ((sx / sm, sy / sm), enclosing_bbox_pixels(regions, sw, sh))
     } else if let Some(hm) = heatmap {
         let (cx, cy) = heatmap_center_of_mass(hm);
-        (cx * sw, cy * sh, false)
+        let (x1, y1, x2, y2) = heatmap_bbox(hm, 0.5);
+        ((cx * sw, cy * sh), (x1 * sw, y1 * sh, x2 * sw, y2 * sh))
+    } else {
+        return Anchor {
+            x: sw / 2.0,
+            y: sh / 2.0,
+            frac_x: 0.5,
+            frac_y: 0.5,
+        };
+    };
+
+    let (dir_x, dir_y) = config.subject_direction.unwrap_or((0.0, 0.0));
+    // Room goes in front of the subject; without a hint, the subject keeps
+    // its side of the frame horizontally and its headroom vertically.
+    let pick = |dir: f32, default_near: bool| {
+        if dir > 0.1 || (dir >= -0.1 && default_near) {
+            line
+        } else {
+            1.0 - line
+        }
+    };
+    let gap = 1.0 - 2.0 * line;
+    let (x, frac_x) = if gx2 - gx1 > gap * cw {
+        ((gx1 + gx2) / 2.0, 0.5)
+    } else {
+        (px, pick(dir_x, px < sw / 2.0))
+    };
+    let (y, frac_y) = if gy2 - gy1 > gap * ch {
+        ((gy1 + gy2) / 2.0, 0.5)
     } else {
-        (sw / 2.0, sh / 2.0, false)
+        (py, pick(dir_y, true))
+    };
+    Anchor {
+        x,
+        y,
+        frac_x,
+        frac_y,
     }
 }
 
@@ -716,39 +2311,6 @@ fn shift_for_focus_visibility(
     }
 }
 
-/// Shift crop to maximize coverage of the hot region in the heatmap.
-fn shift_for_heatmap_coverage(
-    x: &mut f64,
-    y: &mut f64,
-    cw: f64,
-    ch: f64,
-    sw: f64,
-    sh: f64,
-    hm: &HeatMap,
-) {
-    let (bx1, by1, bx2, by2) = heatmap_bbox(hm, 0.3);
-    let hm_x1 = bx1 * sw;
-    let hm_y1 = by1 * sh;
-    let hm_x2 = bx2 * sw;
-    let hm_y2 = by2 * sh;
-
-    if hm_y1 < *y {
-        // Hot region extends above the crop (or is taller than the crop) — prioritize top
-        *y = clamp_f64(hm_y1, 0.0, sh - ch);
-    } else if hm_y2 > *y + ch {
-        *y = clamp_f64(hm_y2 - ch, 0.0, sh - ch);
-    }
-
-    if hm_x1 < *x && hm_x2 > *x + cw {
-        let hm_cx = (hm_x1 + hm_x2) / 2.0;
-        *x = clamp_f64(hm_cx - cw / 2.0, 0.0, sw - cw);
-    } else if hm_x1 < *x {
-        *x = clamp_f64(hm_x1, 0.0, sw - cw);
-    } else if hm_x2 > *x + cw {
-        *x = clamp_f64(hm_x2 - cw, 0.0, sw - cw);
-    }
-}
-
 fn clamp_f64(v: f64, lo: f64, hi: f64) -> f64 {
     v.max(lo).min(hi)
 }
@@ -760,6 +2322,7 @@ fn clamp_f64(v: f64, lo: f64, hi: f64) -> f64 {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use alloc::vec;
 
     fn focus(x1: f32, y1: f32, x2: f32, y2: f32, weight: f32) -> FocusRect {
         FocusRect {
@@ -1132,6 +2695,7 @@ mod tests {
         let input = SmartCropInput {
             focus_regions: regions.to_vec(),
             heatmap: None,
+            regions: Vec::new(),
         };
 
         let targets = [
@@ -1155,4 +2719,513 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn candidates_ranked_and_valid() {
+        let input = SmartCropInput {
+            focus_regions: vec![focus(60.0, 20.0, 75.0, 50.0, 0.9)],
+            heatmap: None,
+            regions: Vec::new(),
+        };
+        let config = CropConfig {
+            target_aspect: SQUARE,
+            ..CropConfig::default()
+        };
+        let candidates = input.compute_candidates(1920, 1080, &config, 5);
+        assert!(candidates.len() > 1 && candidates.len() <= 5);
+        for pair in candidates.windows(2) {
+            assert!(pair[0].score >= pair[1].score);
+        }
+        for c in &candidates {
+            assert_crop_inside(&c.rect, 1920, 1080);
+            assert_approx_aspect(&c.rect, SQUARE, 0.03);
+            assert!((0.0..=1.0).contains(&c.score));
+        }
+        let best = &candidates[0];
+        assert!(best.metrics.focus_coverage > 0.99, "{best:?}");
+        assert_eq!(best.metrics.subjects_cut, 0);
+        // No two candidates are near-duplicates.
+        for (i, a) in candidates.iter().enumerate() {
+            for b in &candidates[i + 1..] {
+                assert!(rect_iou(&a.rect, &b.rect) <= DUPLICATE_IOU);
+            }
+        }
+    }
+
+    #[test]
+    fn score_penalizes_cut_subjects() {
+        let regions = [focus(40.0, 30.0, 60.0, 60.0, 0.9)];
+        let config = CropConfig::default();
+        let whole = score_crop(
+            1000,
+            1000,
+            &regions,
+            None,
+            &config,
+            Rect::new(300, 200, 400, 500),
+        );
+        let half = score_crop(
+            1000,
+            1000,
+            &regions,
+            None,
+            &config,
+            Rect::new(500, 200, 400, 500),
+        );
+        assert_eq!(whole.metrics.subjects_cut, 0);
+        assert_eq!(half.metrics.subjects_cut, 1);
+        assert!((half.metrics.focus_coverage - 0.5).abs() < 0.01);
+        assert!(whole.score > half.score);
+    }
+
+    #[test]
+    fn score_heatmap_mass_retained() {
+        // Hot block in the left quarter of a 4:1 map.
+        let hm = make_heatmap_rect(40, 10, 0, 0, 10, 10, 1.0);
+        let config = CropConfig::default();
+        let left = score_crop(400, 100, &[], Some(&hm), &config, Rect::new(0, 0, 100, 100));
+        let right = score_crop(
+            400,
+            100,
+            &[],
+            Some(&hm),
+            &config,
+            Rect::new(300, 0, 100, 100),
+        );
+        assert!((left.metrics.heatmap_retained - 1.0).abs() < 1e-6);
+        assert_eq!(right.metrics.heatmap_retained, 0.0);
+        assert!(left.score > right.score);
+    }
+
+    #[test]
+    fn candidates_degenerate_source_empty() {
+        let input = SmartCropInput {
+            focus_regions: Vec::new(),
+            heatmap: None,
+            regions: Vec::new(),
+        };
+        assert!(
+            input
+                .compute_candidates(0, 100, &CropConfig::default(), 4)
+                .is_empty()
+        );
+    }
+
+    fn thirds(direction: Option<(f32, f32)>) -> CropConfig {
+        CropConfig {
+            composition: Composition::Thirds,
+            subject_direction: direction,
+            ..CropConfig::default()
+        }
+    }
+
+    #[test]
+    fn thirds_places_subject_on_line() {
+        let regions = [focus(60.0, 40.0, 66.0, 50.0, 0.9)];
+        let face_x = 0.63 * 1920.0;
+        // Subject sits right of centre, so it keeps to the right-hand line.
+        let crop = compute_crop(1920, 1080, &regions, None, &thirds(None)).unwrap();
+        let at = (face_x - crop.x as f64) / crop.width as f64;
+        assert!((at - 2.0 / 3.0).abs() < 0.01, "subject at {at:.3} of crop");
+        // Facing right: lead room on the right, subject on the left line.
+        let crop = compute_crop(1920, 1080, &regions, None, &thirds(Some((1.0, 0.0)))).unwrap();
+        let at = (face_x - crop.x as f64) / crop.width as f64;
+        assert!((at - 1.0 / 3.0).abs() < 0.01, "subject at {at:.3} of crop");
+    }
+
+    #[test]
+    fn golden_ratio_headroom() {
+        let regions = [focus(45.0, 30.0, 55.0, 35.0, 0.9)];
+        let config = CropConfig {
+            target_aspect: LANDSCAPE_16_9,
+            composition: Composition::GoldenRatio,
+            ..CropConfig::default()
+        };
+        let face_y = 0.325 * 1920.0;
+        let crop = compute_crop(1080, 1920, &regions, None, &config).unwrap();
+        let at = (face_y - crop.y as f64) / crop.height as f64;
+        assert!((at - 0.382).abs() < 0.01, "subject at {at:.3} of crop");
+        // Looking up: room above, subject on the lower line.
+        let up = CropConfig {
+            subject_direction: Some((0.0, -1.0)),
+            ..config
+        };
+        let crop = compute_crop(1080, 1920, &regions, None, &up).unwrap();
+        let at = (face_y - crop.y as f64) / crop.height as f64;
+        assert!((at - 0.618).abs() < 0.01, "subject at {at:.3} of crop");
+    }
+
+    #[test]
+    fn thirds_balances_multiple_subjects() {
+        // A tight pair: its centroid goes on the line.
+        let pair = [
+            focus(58.0, 40.0, 61.0, 50.0, 0.9),
+            focus(64.0, 40.0, 67.0, 50.0, 0.9),
+        ];
+        let crop = compute_crop(1920, 1080, &pair, None, &thirds(None)).unwrap();
+        let at = (0.625 * 1920.0 - crop.x as f64) / crop.width as f64;
+        assert!((at - 2.0 / 3.0).abs() < 0.01, "centroid at {at:.3} of crop");
+        // A group wider than the gap between the lines is centred instead.
+        let wide = [
+            focus(50.0, 40.0, 55.0, 50.0, 0.9),
+            focus(70.0, 40.0, 75.0, 50.0, 0.9),
+        ];
+        let crop = compute_crop(1920, 1080, &wide, None, &thirds(None)).unwrap();
+        let at = (0.625 * 1920.0 - crop.x as f64) / crop.width as f64;
+        assert!((at - 0.5).abs() < 0.01, "group centre at {at:.3} of crop");
+    }
+
+    #[test]
+    fn maximal_thirds() {
+        let regions = [focus(46.0, 45.0, 54.0, 55.0, 0.9)];
+        let config = CropConfig {
+            target_aspect: SQUARE,
+            mode: CropMode::Maximal,
+            ..thirds(None)
+        };
+        let crop = compute_crop(1920, 1080, &regions, None, &config).unwrap();
+        assert_crop_inside(&crop, 1920, 1080);
+        let at_x = (960.0 - crop.x as f64) / crop.width as f64;
+        let at_y = (540.0 - crop.y as f64) / crop.height as f64;
+        assert!((at_x - 2.0 / 3.0).abs() < 0.01, "x at {at_x:.3}");
+        assert!((at_y - 1.0 / 3.0).abs() < 0.01, "y at {at_y:.3}");
+    }
+
+    #[test]
+    fn summed_area_matches_brute_force() {
+        let data: Vec<f32> = (0..35).map(|i| ((i * 7) % 11) as f32 / 10.0).collect();
+        let hm = HeatMap {
+            data,
+            width: 7,
+            height: 5,
+        };
+        let sat = SummedArea::new(&hm);
+        let brute = |x0: f64, y0: f64, x1: f64, y1: f64| {
+            let mut sum = 0.0;
+            for row in 0..5 {
+                for col in 0..7 {
+                    let ox = (x1.min(col as f64 + 1.0) - x0.max(col as f64)).max(0.0);
+                    let oy = (y1.min(row as f64 + 1.0) - y0.max(row as f64)).max(0.0);
+                    sum += ox * oy * hm.data[row * 7 + col] as f64;
+                }
+            }
+            sum
+        };
+        for &(x0, y0, x1, y1) in &[
+            (0.0, 0.0, 7.0, 5.0),
+            (1.0, 2.0, 4.0, 3.0),
+            (0.5, 0.25, 6.75, 4.5),
+            (2.3, 1.7, 2.9, 4.1),
+        ] {
+            let (a, b) = (sat.mass(x0, y0, x1, y1), brute(x0, y0, x1, y1));
+            assert!((a - b).abs() < 1e-9, "({x0},{y0})-({x1},{y1}): {a} vs {b}");
+        }
+    }
+
+    #[test]
+    fn minimal_heatmap_picks_heaviest_window() {
+        // A small bright spot on the left, a larger warm area on the right.
+        let mut hm = make_heatmap_rect(192, 108, 150, 30, 181, 70, 0.8);
+        for row in 50..61 {
+            for col in 10..21 {
+                hm.data[row * 192 + col] = 1.0;
+            }
+        }
+        let crop = compute_crop(1920, 1080, &[], Some(&hm), &CropConfig::default()).unwrap();
+        assert!(
+            crop.x <= 1500 && crop.x + crop.width >= 1810,
+            "crop should hold the warm area, got {crop:?}"
+        );
+        let scored = score_crop(1920, 1080, &[], Some(&hm), &CropConfig::default(), crop);
+        let expected = (31.0 * 40.0 * 0.8) / (31.0 * 40.0 * 0.8 + 121.0);
+        assert!((scored.metrics.heatmap_retained as f64 - expected).abs() < 1e-3);
+    }
+
+    #[test]
+    fn minimal_flat_heatmap_stays_centered() {
+        let hm = make_heatmap_rect(64, 36, 0, 0, 64, 36, 0.5);
+        let crop = compute_crop(1920, 1080, &[], Some(&hm), &CropConfig::default()).unwrap();
+        let center_x = crop.x as f64 + crop.width as f64 / 2.0;
+        assert!((center_x - 960.0).abs() < 2.0, "center_x={center_x}");
+    }
+
+    #[test]
+    fn large_heatmap_search_coarse_to_fine() {
+        let hm = make_heatmap_rect(1024, 1024, 700, 300, 703, 303, 1.0);
+        let sat = SummedArea::new(&hm);
+        let (x, y) = best_heatmap_window(&sat, 100.0, 100.0, 1024.0, 1024.0, (0.0, 0.0));
+        assert!(x <= 700.0 && x + 100.0 >= 703.0, "x={x}");
+        assert!(y <= 300.0 && y + 100.0 >= 303.0, "y={y}");
+    }
+
+    fn content(x1: f32, y1: f32, x2: f32, y2: f32, role: RegionRole) -> ContentRegion {
+        ContentRegion {
+            x1,
+            y1,
+            x2,
+            y2,
+            role,
+        }
+    }
+
+    #[test]
+    fn exclude_region_pushes_crop_away() {
+        let input = SmartCropInput {
+            focus_regions: vec![focus(80.0, 30.0, 90.0, 50.0, 0.9)],
+            heatmap: None,
+            regions: vec![content(92.0, 90.0, 100.0, 100.0, RegionRole::Exclude)],
+        };
+        let report = input
+            .compute_crop_report(1920, 1080, &CropConfig::default())
+            .unwrap();
+        assert!(report.is_satisfied());
+        assert!(
+            report.rect.x + report.rect.width <= 1766,
+            "{:?}",
+            report.rect
+        );
+        assert_approx_aspect(&report.rect, PORTRAIT_9_16, 0.01);
+        assert_eq!(
+            input.compute_crop(1920, 1080, &CropConfig::default()),
+            Some(report.rect)
+        );
+    }
+
+    #[test]
+    fn keep_whole_region_not_sliced() {
+        // The centred crop's left edge (x=656) would slice the logo.
+        let input = SmartCropInput {
+            regions: vec![content(30.0, 10.0, 36.0, 20.0, RegionRole::KeepWhole)],
+            ..SmartCropInput::default()
+        };
+        let report = input
+            .compute_crop_report(1920, 1080, &CropConfig::default())
+            .unwrap();
+        assert!(report.is_satisfied());
+        // Nearest fix: slide right until the logo is fully outside.
+        assert_eq!(report.rect.x, 692);
+    }
+
+    #[test]
+    fn regions_shrink_crop_when_sliding_fails() {
+        let input = SmartCropInput {
+            regions: vec![content(40.0, 0.0, 60.0, 100.0, RegionRole::Exclude)],
+            ..SmartCropInput::default()
+        };
+        let config = CropConfig {
+            target_aspect: SQUARE,
+            ..CropConfig::default()
+        };
+        let report = input.compute_crop_report(1920, 1080, &config).unwrap();
+        assert!(report.is_satisfied());
+        assert_eq!((report.rect.width, report.rect.height), (756, 756));
+        assert!(report.rect.x + report.rect.width <= 768 || report.rect.x >= 1152);
+    }
+
+    #[test]
+    fn unsatisfiable_regions_reported() {
+        let input = SmartCropInput {
+            regions: vec![
+                content(0.0, 0.0, 100.0, 100.0, RegionRole::Exclude),
+                content(10.0, 10.0, 20.0, 20.0, RegionRole::KeepWhole),
+            ],
+            ..SmartCropInput::default()
+        };
+        let report = input
+            .compute_crop_report(1920, 1080, &CropConfig::default())
+            .unwrap();
+        assert!(!report.is_satisfied());
+        assert_eq!(report.unsatisfied, vec![0]);
+        assert_eq!(report.rect.height, 1080);
+
+        let candidates = input.compute_candidates(1920, 1080, &CropConfig::default(), 3);
+        assert!(candidates.iter().all(|c| c.metrics.regions_violated >= 1));
+    }
+
+    #[test]
+    fn oriented_and_cropped_inputs_follow_pixels() {
+        let input = SmartCropInput {
+            focus_regions: vec![focus(10.0, 20.0, 30.0, 40.0, 1.0)],
+            heatmap: Some(make_heatmap(4, 2, &[(0, 0, 1.0)])),
+            regions: vec![content(80.0, 0.0, 100.0, 10.0, RegionRole::Exclude)],
+        };
+
+        // Rotating 90° clockwise moves the top-left corner to the top-right.
+        let rotated = input.oriented(Orientation::Rotate90, 400, 200);
+        let f = rotated.focus_regions[0];
+        assert_eq!((f.x1, f.y1, f.x2, f.y2), (60.0, 10.0, 80.0, 30.0));
+        let hm = rotated.heatmap.unwrap();
+        assert_eq!((hm.width, hm.height), (2, 4));
+        assert_eq!(hm.data[1], 1.0);
+        assert_eq!(hm.data.iter().sum::<f32>(), 1.0);
+
+        // The right half loses the focus region and the hot cell.
+        let cropped = input.cropped(Rect::new(200, 0, 200, 200), 400, 200);
+        assert!(cropped.focus_regions.is_empty());
+        let r = cropped.regions[0];
+        assert_eq!((r.x1, r.x2), (60.0, 100.0));
+        let hm = cropped.heatmap.unwrap();
+        assert_eq!((hm.width, hm.height), (2, 2));
+        assert!(hm.data.iter().all(|&v| v == 0.0));
+    }
+
+    fn frame_at(time: f64, x: f32) -> CropFrame {
+        CropFrame {
+            time,
+            input: SmartCropInput {
+                focus_regions: vec![focus(x, 40.0, x + 5.0, 60.0, 1.0)],
+                ..SmartCropInput::default()
+            },
+            scene_cut: false,
+        }
+    }
+
+    #[test]
+    fn trajectory_pans_within_limits() {
+        let config = TrajectoryConfig::default();
+        let mut frames = vec![frame_at(0.0, 5.0)];
+        frames.extend((1..90).map(|i| frame_at(i as f64 / 30.0, 85.0)));
+        let path = compute_trajectory(1920, 1080, &frames, &config);
+        assert_eq!(path.len(), frames.len());
+
+        let max_step = 0.5 / 30.0 * 1920.0 + 1.0;
+        for pair in path.windows(2) {
+            assert!((pair[1].x as f64 - pair[0].x as f64).abs() <= max_step);
+        }
+        // Eases in: the first step is far below full speed.
+        assert!(path[1].x - path[0].x < 10);
+
+        let settled = frames[89]
+            .input
+            .compute_crop(1920, 1080, &config.crop)
+            .unwrap();
+        assert!((path[89].x as f64 - settled.x as f64).abs() <= 0.02 * 1920.0 + 1.0);
+    }
+
+    #[test]
+    fn trajectory_resets_at_scene_cut() {
+        let mut frames = vec![frame_at(0.0, 5.0), frame_at(1.0 / 30.0, 85.0)];
+        frames[1].scene_cut = true;
+        let config = TrajectoryConfig::default();
+        let path = compute_trajectory(1920, 1080, &frames, &config);
+        let cut = frames[1]
+            .input
+            .compute_crop(1920, 1080, &config.crop)
+            .unwrap();
+        assert_eq!(path[1], cut);
+    }
+
+    #[test]
+    fn trajectory_ignores_jitter() {
+        let frames: Vec<CropFrame> = (0..30)
+            .map(|i| frame_at(i as f64 / 30.0, if i % 2 == 0 { 50.0 } else { 50.8 }))
+            .collect();
+        let path = compute_trajectory(1920, 1080, &frames, &TrajectoryConfig::default());
+        assert!(path.iter().all(|r| *r == path[0]));
+    }
+
+    #[test]
+    fn balanced_crop_interpolates_between_modes() {
+        let faces = [focus(40.0, 30.0, 50.0, 50.0, 1.0)];
+        let at = |mode, zoom| {
+            let config = CropConfig {
+                target_aspect: SQUARE,
+                mode,
+                zoom,
+                ..CropConfig::default()
+            };
+            compute_crop(1920, 1080, &faces, None, &config).unwrap()
+        };
+        let minimal = at(CropMode::Minimal, 0.5);
+        let maximal = at(CropMode::Maximal, 0.5);
+        assert_eq!(at(CropMode::Balanced, 0.0), minimal);
+        assert_eq!(at(CropMode::Balanced, 1.0), maximal);
+
+        let half = at(CropMode::Balanced, 0.5);
+        assert!(maximal.width < half.width && half.width < minimal.width);
+        assert!((half.width as i32 - half.height as i32).abs() <= 1);
+        assert!(half.x <= maximal.x && half.x + half.width >= maximal.x + maximal.width);
+        assert!(half.y <= maximal.y && half.y + half.height >= maximal.y + maximal.height);
+    }
+
+    #[test]
+    fn min_crop_size_prevents_upscale() {
+        let faces = [focus(40.0, 30.0, 45.0, 40.0, 1.0)];
+        let config = CropConfig {
+            target_aspect: SQUARE,
+            mode: CropMode::Maximal,
+            min_crop_size: Some(Size::new(600, 600)),
+            ..CropConfig::default()
+        };
+        let crop = compute_crop(1920, 1080, &faces, None, &config).unwrap();
+        assert_eq!((crop.width, crop.height), (600, 600));
+        assert_crop_inside(&crop, 1920, 1080);
+
+        // Larger than the source allows: the largest crop instead.
+        let config = CropConfig {
+            min_crop_size: Some(Size::new(2000, 2000)),
+            ..config
+        };
+        let crop = compute_crop(1920, 1080, &faces, None, &config).unwrap();
+        assert_eq!((crop.width, crop.height), (1080, 1080));
+
+        // Content regions may not shrink the crop below it either.
+        let input = SmartCropInput {
+            regions: vec![content(40.0, 0.0, 60.0, 100.0, RegionRole::Exclude)],
+            ..SmartCropInput::default()
+        };
+        let config = CropConfig {
+            target_aspect: SQUARE,
+            min_crop_size: Some(Size::new(1000, 1000)),
+            ..CropConfig::default()
+        };
+        let report = input.compute_crop_report(1920, 1080, &config).unwrap();
+        assert!(report.rect.width >= 1000);
+        assert!(!report.is_satisfied());
+    }
+
+    #[test]
+    fn heatmap_builders_rasterize_focus() {
+        let hm = HeatMap::from_points(20, 10, &[(25.0, 50.0, 0.8)], 10.0);
+        assert_eq!(hm.data.len(), 200);
+        assert!((hm.value_at(25.0, 50.0) - 0.8).abs() < 0.05);
+        assert!(hm.value_at(40.0, 50.0) < hm.value_at(30.0, 50.0));
+        assert!(hm.value_at(95.0, 50.0) < 0.01);
+
+        // Hard edges: only the covered cells.
+        let hm = HeatMap::from_focus_rects(10, 10, &[focus(20.0, 20.0, 40.0, 40.0, 1.0)], 0.0);
+        assert_eq!(hm.data.iter().filter(|&&v| v > 0.0).count(), 4);
+
+        // The heatmap path finds a point-only subject.
+        let config = CropConfig {
+            target_aspect: SQUARE,
+            ..CropConfig::default()
+        };
+        let hm = HeatMap::from_points(32, 18, &[(85.0, 50.0, 1.0)], 5.0);
+        let crop = compute_crop(1920, 1080, &[], Some(&hm), &config).unwrap();
+        assert_eq!(crop.x + crop.width, 1920);
+    }
+
+    #[test]
+    fn heatmap_combine_and_orient() {
+        let a = HeatMap::from_points(10, 10, &[(15.0, 15.0, 1.0)], 0.0);
+        let b = HeatMap::from_points(20, 20, &[(85.0, 85.0, 1.0)], 0.0);
+        let max = HeatMap::combine(&[(&a, 1.0), (&b, 0.5)], HeatMapBlend::Max).unwrap();
+        assert_eq!((max.width, max.height), (10, 10));
+        assert_eq!(max.value_at(15.0, 15.0), 1.0);
+        assert!(max.value_at(85.0, 85.0) > 0.0 && max.value_at(85.0, 85.0) <= 0.5);
+        let mean = HeatMap::combine(&[(&a, 1.0), (&a, 3.0)], HeatMapBlend::Weighted).unwrap();
+        assert_eq!(mean.value_at(15.0, 15.0), 1.0);
+        let sum = HeatMap::combine(&[(&a, 1.0), (&a, 1.0)], HeatMapBlend::Sum).unwrap();
+        assert_eq!(sum.value_at(15.0, 15.0), 1.0);
+        assert!(HeatMap::combine(&[], HeatMapBlend::Max).is_none());
+
+        // A hot cell in the top-left of a wide map lands top-right after Rotate90.
+        let hm = HeatMap::from_points(4, 2, &[(10.0, 25.0, 1.0)], 0.0);
+        let rotated = hm.oriented(Orientation::Rotate90);
+        assert_eq!((rotated.width, rotated.height), (2, 4));
+        assert_eq!(rotated.value_at(75.0, 10.0), 1.0);
+        assert_eq!(rotated.data.iter().filter(|&&v| v > 0.0).count(), 1);
+        assert_eq!(rotated.oriented(Orientation::Rotate270).data, hm.data);
+    }
 }
@@ -0,0 +1,382 @@
+//! Lightroom / Camera Raw crop settings import.
+//!
+//! Converts the XMP `crs:` crop values (`CropTop`, `CropLeft`, `CropBottom`,
+//! `CropRight`, `CropAngle`, `HasCrop`) into a [`Command`] list for
+//! [`compute_layout_sequential()`](crate::compute_layout_sequential), so
+//! proofs match the framing the photographer saw in Lightroom.
+//!
+//! XMP parsing is out of scope — callers extract the floats themselves.
+//!
+//! # Coordinate conventions
+//!
+//! Lightroom stores the crop in normalized (0.0–1.0) coordinates of the
+//! image **as stored** (before EXIF orientation). With a non-zero
+//! `CropAngle`, the image is straightened first and the crop is an
+//! axis-aligned rectangle in the straightened frame; `(CropLeft, CropTop)`
+//! and `(CropRight, CropBottom)` are where that rectangle's top-left and
+//! bottom-right corners land on the unrotated image.
+//!
+//! ```text
+//!     stored image             straightened frame
+//!     ┌──────────────┐         ┌────────────────┐
+//!     │   TL ·       │         │  TL ┌──────┐   │
+//!     │        ╲     │  ──►    │     │ crop │   │
+//!     │     ╲    · BR│         │     └──────┘BR │
+//!     └──────────────┘         └────────────────┘
+//! ```
+//!
+//! # Example
+//!
+//! ```
+//! use zenlayout::xmp::XmpCrop;
+//! use zenlayout::{Orientation, compute_layout_sequential};
+//!
+//! let commands = XmpCrop::new(0.1, 0.2, 0.9, 0.8)
+//!     .with_angle(3.5)
+//!     .to_commands(6000, 4000, Orientation::Identity)
+//!     .unwrap();
+//!
+//! let (ideal, _request) = compute_layout_sequential(&commands, 6000, 4000, None).unwrap();
+//! assert!(ideal.layout.canvas.width < 6000);
+//! ```
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+
+use crate::constraint::{CanvasColor, LayoutError};
+use crate::dimension::{DimensionEffect, RotateEffect, RotateMode};
+use crate::orientation::Orientation;
+use crate::plan::{Command, Region, RegionCoord};
+use whereat::{At, at};
+
+/// Crop settings from Lightroom / Camera Raw XMP metadata.
+///
+/// Field names mirror the `crs:` properties. All edge values are normalized
+/// to the stored image dimensions (0.0 = left/top edge, 1.0 = right/bottom).
+#[non_exhaustive]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct XmpCrop {
+    /// `crs:CropTop` — top edge, fraction of stored height.
+    pub top: f32,
+    /// `crs:CropLeft` — left edge, fraction of stored width.
+    pub left: f32,
+    /// `crs:CropBottom` — bottom edge, fraction of stored height.
+    pub bottom: f32,
+    /// `crs:CropRight` — right edge, fraction of stored width.
+    pub right: f32,
+    /// `crs:CropAngle` — straighten angle in degrees. Positive values tilt
+    /// the crop frame clockwise, i.e. the image content is rotated
+    /// counter-clockwise on output.
+    pub angle: f32,
+    /// `crs:HasCrop` — when `false`, Lightroom ignores the other fields.
+    pub has_crop: bool,
+}
+
+impl Default for XmpCrop {
+    fn default() -> Self {
+        Self {
+            top: 0.0,
+            left: 0.0,
+            bottom: 1.0,
+            right: 1.0,
+            angle: 0.0,
+            has_crop: false,
+        }
+    }
+}
+
+impl XmpCrop {
+    /// Crop from `crs:CropTop/Left/Bottom/Right` (in that order), with no
+    /// angle and `HasCrop` set.
+    pub fn new(top: f32, left: f32, bottom: f32, right: f32) -> Self {
+        Self {
+            top,
+            left,
+            bottom,
+            right,
+            angle: 0.0,
+            has_crop: true,
+        }
+    }
+
+    /// Set `crs:CropAngle` in degrees.
+    pub fn with_angle(mut self, degrees: f32) -> Self {
+        self.angle = degrees;
+        self
+    }
+
+    /// Set `crs:HasCrop`.
+    pub fn with_has_crop(mut self, has_crop: bool) -> Self {
+        self.has_crop = has_crop;
+        self
+    }
+
+    /// Convert to commands for [`compute_layout_sequential()`](crate::compute_layout_sequential).
+    ///
+    /// `source_w`/`source_h` are the stored image dimensions and
+    /// `orientation` its EXIF orientation. The result is, in order:
+    ///
+    /// 1. [`Command::AutoOrient`] — only when `orientation` is not identity
+    /// 2. [`Command::Effect`] with a [`RotateEffect`] — only for a non-zero angle
+    /// 3. [`Command::Region`] in percentage coordinates of the straightened frame
+    ///
+    /// Append a [`Command::Constrain`] to size the output. Returns an empty
+    /// list when `has_crop` is `false`.
+    ///
+    /// The rotation uses [`RotateMode::InscribedCrop`] when the crop lies
+    /// inside the inscribed rectangle (the usual case — Lightroom's
+    /// straighten tool crops to it), and falls back to
+    /// [`RotateMode::Expand`] when the crop reaches past it, so no content
+    /// visible in Lightroom is lost.
+    #[track_caller]
+    pub fn to_commands(
+        &self,
+        source_w: u32,
+        source_h: u32,
+        orientation: Orientation,
+    ) -> Result<Vec<Command>, At<LayoutError>> {
+        if !self.has_crop {
+            return Ok(Vec::new());
+        }
+        if !self.top.is_finite()
+            || !self.left.is_finite()
+            || !self.bottom.is_finite()
+            || !self.right.is_finite()
+            || !self.angle.is_finite()
+        {
+            return Err(at!(LayoutError::NonFiniteFloat));
+        }
+        if source_w == 0 || source_h == 0 {
+            return Err(at!(LayoutError::ZeroSourceDimension));
+        }
+        if self.right <= self.left || self.bottom <= self.top {
+            return Err(at!(LayoutError::ZeroRegionDimension));
+        }
+
+        let mut commands = Vec::new();
+        if !orientation.is_identity() {
+            commands.push(Command::AutoOrient(orientation.to_exif()));
+        }
+
+        // Crop corners in display (post-orientation) pixel coordinates.
+        let (sw, sh) = (source_w as f32, source_h as f32);
+        let a = orientation.transform_point_to_display(
+            self.left * sw,
+            self.top * sh,
+            source_w,
+            source_h,
+        );
+        let b = orientation.transform_point_to_display(
+            self.right * sw,
+            self.bottom * sh,
+            source_w,
+            source_h,
+        );
+        let display = orientation.transform_dimensions(source_w, source_h);
+        let (dw, dh) = (display.width, display.height);
+
+        // A mirroring orientation reverses the sense of rotation.
+        let mirrored = matches!(
+            orientation,
+            Orientation::FlipH
+                | Orientation::FlipV
+                | Orientation::Transpose
+                | Orientation::Transverse
+        );
+        let angle = if mirrored { self.angle } else { -self.angle };
+
+        let (a, b, out_w, out_h) = if angle.abs() < 1e-4 {
+            (a, b, dw, dh)
+        } else {
+            let inscribed = RotateEffect::from_degrees(angle, RotateMode::InscribedCrop);
+            let (iw, ih) = inscribed.forward(dw, dh).unwrap_or((dw, dh));
+            let ia = inscribed.forward_point(a.0, a.1, dw, dh).unwrap_or(a);
+            let ib = inscribed.forward_point(b.0, b.1, dw, dh).unwrap_or(b);
+            // Half a pixel of slack absorbs float noise in Lightroom's values.
+            let inside = |p: (f32, f32)| {
+                p.0 >= -0.5 && p.1 >= -0.5 && p.0 <= iw as f32 + 0.5 && p.1 <= ih as f32 + 0.5
+            };
+            if inside(ia) && inside(ib) {
+                commands.push(Command::Effect(Box::new(inscribed)));
+                (ia, ib, iw, ih)
+            } else {
+                let expand = RotateEffect::from_degrees(
+                    angle,
+                    RotateMode::Expand {
+                        color: CanvasColor::Transparent,
+                    },
+                );
+                let (ew, eh) = expand.forward(dw, dh).unwrap_or((dw, dh));
+                let ea = expand.forward_point(a.0, a.1, dw, dh).unwrap_or(a);
+                let eb = expand.forward_point(b.0, b.1, dw, dh).unwrap_or(b);
+                commands.push(Command::Effect(Box::new(expand)));
+                (ea, eb, ew, eh)
+            }
+        };
+
+        // The two corners are diagonal opposites in the straightened frame;
+        // orientation may have turned them into the other diagonal.
+        let norm = |v: f32, dim: u32| (v / dim.max(1) as f32).clamp(0.0, 1.0);
+        commands.push(Command::Region(Region {
+            left: RegionCoord::pct(norm(a.0.min(b.0), out_w)),
+            top: RegionCoord::pct(norm(a.1.min(b.1), out_h)),
+            right: RegionCoord::pct(norm(a.0.max(b.0), out_w)),
+            bottom: RegionCoord::pct(norm(a.1.max(b.1), out_h)),
+            color: CanvasColor::Transparent,
+        }));
+        Ok(commands)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constraint::Size;
+    use crate::plan::compute_layout_sequential;
+    use alloc::format;
+
+    fn region_of(commands: &[Command]) -> Region {
+        match commands.last() {
+            Some(Command::Region(r)) => *r,
+            other => panic!("expected trailing Region, got {other:?}"),
+        }
+    }
+
+    /// Source-space corners (normalized) of an axis-aligned `cw × ch` crop
+    /// centred in the frame of an image rotated by `-angle_deg` (Lightroom
+    /// sign convention).
+    fn lightroom_corners(w: u32, h: u32, cw: f32, ch: f32, angle_deg: f32) -> XmpCrop {
+        let theta = angle_deg.to_radians();
+        let (sin, cos) = (theta.sin(), theta.cos());
+        let (cx, cy) = (w as f32 / 2.0, h as f32 / 2.0);
+        // Straightened frame → source: rotate by +angle around the centre.
+        let to_src = |dx: f32, dy: f32| (cx + dx * cos - dy * sin, cy + dx * sin + dy * cos);
+        let tl = to_src(-cw / 2.0, -ch / 2.0);
+        let br = to_src(cw / 2.0, ch / 2.0);
+        XmpCrop::new(
+            tl.1 / h as f32,
+            tl.0 / w as f32,
+            br.1 / h as f32,
+            br.0 / w as f32,
+        )
+        .with_angle(angle_deg)
+    }
+
+    #[test]
+    fn no_crop_is_empty() {
+        let crop = XmpCrop::new(0.1, 0.1, 0.9, 0.9).with_has_crop(false);
+        let commands = crop.to_commands(1000, 800, Orientation::Identity).unwrap();
+        assert!(commands.is_empty());
+        assert!(!XmpCrop::default().has_crop);
+    }
+
+    #[test]
+    fn unrotated_crop_is_plain_region() {
+        let commands = XmpCrop::new(0.1, 0.2, 0.9, 0.8)
+            .to_commands(1000, 800, Orientation::Identity)
+            .unwrap();
+        assert_eq!(commands.len(), 1);
+        let (ideal, _) = compute_layout_sequential(&commands, 1000, 800, None).unwrap();
+        assert_eq!(ideal.layout.canvas, Size::new(600, 640));
+        let crop = ideal.layout.source_crop.unwrap();
+        assert_eq!((crop.x, crop.y), (200, 80));
+    }
+
+    #[test]
+    fn straightened_crop_recovers_frame_size() {
+        // A 600×400 crop in the straightened frame survives the round trip
+        // through Lightroom's source-space corners.
+        for angle in [-7.0f32, -2.5, 1.0, 4.0, 9.0] {
+            let crop = lightroom_corners(1000, 800, 600.0, 400.0, angle);
+            let commands = crop.to_commands(1000, 800, Orientation::Identity).unwrap();
+            assert!(matches!(commands[0], Command::Effect(_)));
+            let (ideal, _) = compute_layout_sequential(&commands, 1000, 800, None).unwrap();
+            let canvas = ideal.layout.canvas;
+            assert!(
+                (canvas.width as i32 - 600).abs() <= 1 && (canvas.height as i32 - 400).abs() <= 1,
+                "angle={angle} canvas={canvas:?}"
+            );
+            assert_eq!(ideal.effects.len(), 1);
+        }
+    }
+
+    #[test]
+    fn crop_past_inscribed_rect_expands() {
+        // Nearly full-frame crop at 5° does not fit the inscribed rect.
+        let crop = lightroom_corners(1000, 800, 900.0, 760.0, 5.0);
+        let commands = crop.to_commands(1000, 800, Orientation::Identity).unwrap();
+        let Command::Effect(effect) = &commands[0] else {
+            panic!("expected effect");
+        };
+        let (ew, eh) = effect.forward(1000, 800).unwrap();
+        assert!(
+            ew > 1000 && eh > 800,
+            "expected expanded canvas, got {ew}×{eh}"
+        );
+        let (ideal, _) = compute_layout_sequential(&commands, 1000, 800, None).unwrap();
+        let canvas = ideal.layout.canvas;
+        assert!((canvas.width as i32 - 900).abs() <= 1, "{canvas:?}");
+        assert!((canvas.height as i32 - 760).abs() <= 1, "{canvas:?}");
+    }
+
+    #[test]
+    fn orientation_maps_crop_to_display_space() {
+        // Stored 1000×800, EXIF 6 (Rotate90) → display 800×1000.
+        // Stored left strip [0, 0.25] becomes the display top strip.
+        let commands = XmpCrop::new(0.0, 0.0, 1.0, 0.25)
+            .to_commands(1000, 800, Orientation::Rotate90)
+            .unwrap();
+        assert!(matches!(commands[0], Command::AutoOrient(6)));
+        let r = region_of(&commands);
+        assert_eq!(r.left.percent, 0.0);
+        assert_eq!(r.right.percent, 1.0);
+        assert_eq!(r.top.percent, 0.0);
+        assert_eq!(r.bottom.percent, 0.25);
+    }
+
+    #[test]
+    fn mirrored_orientation_reverses_angle() {
+        let crop = lightroom_corners(1000, 800, 600.0, 400.0, 4.0);
+        let plain = crop.to_commands(1000, 800, Orientation::Identity).unwrap();
+        let flipped = crop.to_commands(1000, 800, Orientation::FlipH).unwrap();
+        let angle = |c: &Command| match c {
+            Command::Effect(e) => format!("{e:?}"),
+            _ => panic!("expected effect"),
+        };
+        let expected = RotateEffect::from_degrees(4.0, RotateMode::InscribedCrop);
+        assert_eq!(angle(&flipped[1]), format!("{expected:?}"));
+        assert_ne!(angle(&plain[0]), angle(&flipped[1]));
+        // Mirroring swaps left/right in the region but keeps its size.
+        let (a, b) = (region_of(&plain), region_of(&flipped));
+        let width = |r: Region| r.right.percent - r.left.percent;
+        assert!((width(a) - width(b)).abs() < 1e-4);
+        assert!((a.left.percent - (1.0 - b.right.percent)).abs() < 1e-3);
+    }
+
+    #[test]
+    fn rejects_invalid_values() {
+        let nan = XmpCrop::new(f32::NAN, 0.0, 1.0, 1.0);
+        assert_eq!(
+            *nan.to_commands(100, 100, Orientation::Identity)
+                .unwrap_err()
+                .error(),
+            LayoutError::NonFiniteFloat
+        );
+        let inverted = XmpCrop::new(0.5, 0.5, 0.4, 0.9);
+        assert_eq!(
+            *inverted
+                .to_commands(100, 100, Orientation::Identity)
+                .unwrap_err()
+                .error(),
+            LayoutError::ZeroRegionDimension
+        );
+        let crop = XmpCrop::new(0.0, 0.0, 1.0, 1.0);
+        assert_eq!(
+            *crop
+                .to_commands(0, 100, Orientation::Identity)
+                .unwrap_err()
+                .error(),
+            LayoutError::ZeroSourceDimension
+        );
+    }
+}
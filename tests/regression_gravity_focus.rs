@@ -501,10 +501,7 @@ mod focus_smart_crop {
             y2: 65.0,
             weight: 0.9,
         };
-        let input = SmartCropInput {
-            focus_regions: vec![face],
-            heatmap: None,
-        };
+        let input = SmartCropInput::new(vec![face], None);
         let config = CropConfig {
             target_aspect: PORTRAIT_9_16,
             mode: CropMode::Minimal,
@@ -542,10 +539,7 @@ mod focus_smart_crop {
             y2: 90.0,
             weight: 0.9,
         };
-        let input = SmartCropInput {
-            focus_regions: vec![face],
-            heatmap: None,
-        };
+        let input = SmartCropInput::new(vec![face], None);
         let config = CropConfig {
             target_aspect: PORTRAIT_9_16,
             mode: CropMode::Minimal,
@@ -589,10 +583,7 @@ mod focus_smart_crop {
             y2: 90.0,
             weight: 0.9,
         };
-        let input = SmartCropInput {
-            focus_regions: vec![focus],
-            heatmap: None,
-        };
+        let input = SmartCropInput::new(vec![focus], None);
         let config = CropConfig {
             target_aspect: PORTRAIT_3_4,
             mode: CropMode::Minimal,
@@ -629,10 +620,7 @@ mod focus_smart_crop {
             y2: 90.0,
             weight: 0.9,
         };
-        let input = SmartCropInput {
-            focus_regions: vec![focus],
-            heatmap: None,
-        };
+        let input = SmartCropInput::new(vec![focus], None);
         let config = CropConfig {
             target_aspect: LANDSCAPE_16_9,
             mode: CropMode::Minimal,
@@ -678,10 +666,7 @@ mod focus_smart_crop {
             y2: 90.0,
             weight: 0.9,
         };
-        let input = SmartCropInput {
-            focus_regions: vec![focus],
-            heatmap: None,
-        };
+        let input = SmartCropInput::new(vec![focus], None);
         let config = CropConfig {
             target_aspect: PORTRAIT_9_16,
             mode: CropMode::Minimal,
@@ -724,10 +709,7 @@ mod focus_smart_crop {
             y2: 90.0,
             weight: 0.9,
         };
-        let input = SmartCropInput {
-            focus_regions: vec![focus],
-            heatmap: None,
-        };
+        let input = SmartCropInput::new(vec![focus], None);
         let config = CropConfig {
             target_aspect: SQUARE,
             mode: CropMode::Maximal,